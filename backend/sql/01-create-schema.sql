@@ -1,11 +1,249 @@
+-- User account
+CREATE TYPE user_role_enum AS ENUM (
+    'viewer', -- read only
+    'contributor', -- creates quotes, changes their own
+    'editor', -- changes all the quotes, published without moderation
+    'moderator', -- moderation queue and reports
+    'admin'
+);
+
+CREATE TABLE user_account (
+    id bigserial PRIMARY KEY,
+    username text NOT NULL UNIQUE, -- lowercase
+    email text NOT NULL UNIQUE, -- lowercase
+    pwd_hash text NOT NULL, -- argon2id, PHC string format
+    role user_role_enum NOT NULL DEFAULT 'contributor',
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    disabled boolean NOT NULL DEFAULT false,
+    email_verified boolean NOT NULL DEFAULT false -- by the link of a verification email
+);
+ALTER Sequence user_account_id_seq RESTART WITH 1000;
+
+-- Login session, referenced by its auth tokens, so it can be revoked (deleted) before they expire
+CREATE TABLE session (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    last_seen timestamp with time zone NOT NULL DEFAULT now(),
+    ip text,
+    user_agent text,
+    expires timestamp with time zone NOT NULL
+);
+CREATE INDEX session_user_idx ON session (user_id);
+ALTER Sequence session_id_seq RESTART WITH 1000;
+
+-- API key of a user, for scripts. Only the SHA-256 of the key is stored, the prefix is shown to recognize it.
+CREATE TYPE api_scope_enum AS ENUM (
+    'quotes:read',
+    'quotes:write',
+    'admin' -- all the rights of the user (moderation, sessions, api keys)
+);
+
+CREATE TABLE api_key (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    prefix text NOT NULL, -- start of the key, e.g., `qapi_1f3a9c0b`
+    key_hash text NOT NULL UNIQUE, -- sha256 hex
+    scopes api_scope_enum[] NOT NULL,
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    expires timestamp with time zone, -- none for keys that do not expire
+    last_used timestamp with time zone
+);
+CREATE INDEX api_key_user_idx ON api_key (user_id);
+ALTER Sequence api_key_id_seq RESTART WITH 1000;
+
+-- External identity (JWT issuer and subject) of a user, provisioned the first time its token is seen
+CREATE TABLE user_identity (
+    issuer text NOT NULL, -- `iss` claim, '' when the token has none
+    subject text NOT NULL, -- `sub` claim
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (issuer, subject)
+);
+CREATE INDEX user_identity_user_idx ON user_identity (user_id);
+
+-- TOTP two-factor authentication of a user, enabled once a first code confirms the enrolment
+CREATE TABLE user_totp (
+    user_id bigint PRIMARY KEY REFERENCES user_account (id) ON DELETE CASCADE,
+    secret text NOT NULL, -- base32, as in the otpauth URI
+    enabled boolean NOT NULL DEFAULT false,
+    last_step bigint, -- time step of the last accepted code, so a code is only used once
+    failed_attempts integer NOT NULL DEFAULT 0, -- codes checked since the last accepted one
+    locked_until timestamp with time zone, -- no code is checked until then, after too many failed ones
+    ctime timestamp with time zone NOT NULL DEFAULT now()
+);
+
+-- Single-use recovery codes of the TOTP, only their SHA-256 is stored
+CREATE TABLE totp_recovery_code (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    code_hash text NOT NULL, -- sha256 hex
+    used timestamp with time zone
+);
+CREATE INDEX totp_recovery_code_user_idx ON totp_recovery_code (user_id);
+
+-- Invite code of an admin, to register (required with QUOTE_REGISTRATION=invite). Only the SHA-256 of the code is stored.
+CREATE TABLE invite (
+    id bigserial PRIMARY KEY,
+    cid bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE, -- creator (admin)
+    prefix text NOT NULL, -- start of the code, e.g., `inv_1f3a9c0b`
+    code_hash text NOT NULL UNIQUE, -- sha256 hex
+    role user_role_enum NOT NULL DEFAULT 'contributor', -- of the registered users
+    max_uses integer NOT NULL DEFAULT 1,
+    uses integer NOT NULL DEFAULT 0,
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    expires timestamp with time zone NOT NULL
+);
+ALTER Sequence invite_id_seq RESTART WITH 1000;
+
+-- Registration with an invite
+CREATE TABLE invite_use (
+    invite_id bigint NOT NULL REFERENCES invite (id) ON DELETE CASCADE,
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (invite_id, user_id)
+);
+
+-- Token of the link of a password reset or email verification email, used once. Only the SHA-256 of the token is stored.
+CREATE TYPE email_token_purpose_enum AS ENUM ('password_reset', 'email_verification');
+CREATE TABLE email_token (
+    token_hash text PRIMARY KEY, -- sha256 hex
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    purpose email_token_purpose_enum NOT NULL,
+    email text NOT NULL, -- the address the email was sent to
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    expires timestamp with time zone NOT NULL
+);
+CREATE INDEX email_token_user_idx ON email_token (user_id);
+
+-- OpenID Connect login in progress, from the redirect to the provider to its callback (used once)
+CREATE TABLE oidc_login (
+    state text PRIMARY KEY, -- random, sent to the provider and back
+    nonce text NOT NULL, -- random, expected in the ID token
+    code_verifier text NOT NULL, -- PKCE
+    expires timestamp with time zone NOT NULL
+);
+
+-- SSO login of a user with two-factor authentication, waiting for its TOTP code. Only the SHA-256 of the ticket is stored.
+CREATE TABLE oidc_totp_login (
+    ticket_hash text PRIMARY KEY, -- sha256 hex
+    user_id bigint NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    expires timestamp with time zone NOT NULL
+);
+
 -- Quote
+CREATE TYPE quote_status_enum AS ENUM (
+    'pending', -- waiting for a moderator (QUOTE_MODERATION=untrusted)
+    'approved',
+    'rejected',
+    'hidden' -- reported too many times, until a moderator resolves the reports
+);
+
 CREATE TABLE quote (
-    id bigserial,
+    id bigserial PRIMARY KEY,
     cid bigint NOT NULL, -- creator user id
     ctime timestamp with time zone DEFAULT now(),
     mid bigint, --modified user id
     mtime timestamp with time zone,
     quote text NOT NULL,
-    author text NOT NULL DEFAULT 'unknown'
+    author text NOT NULL DEFAULT 'unknown',
+    source text, -- where the quote comes from (book, speech, ...)
+    locator text, -- where in the source (page, location, ...)
+    tags text[] NOT NULL DEFAULT '{}',
+    favorite_count bigint NOT NULL DEFAULT 0, -- maintained by favorite_count_trigger
+    lang text NOT NULL DEFAULT 'und', -- BCP 47 language tag
+    original_id bigint REFERENCES quote(id) ON DELETE CASCADE, -- set for translations
+    translator text,
+    version bigint NOT NULL DEFAULT 1, -- incremented on each update (ETag)
+    status quote_status_enum NOT NULL DEFAULT 'approved',
+    moderation_reason text, -- given by the moderator, shown to the submitter
+    moderated_by bigint, -- moderator user id
+    moderated_at timestamp with time zone
 );
 ALTER Sequence quote_id_seq RESTART WITH 1000;
+
+-- Collection (curated, ordered set of quotes)
+CREATE TYPE collection_visibility_enum AS ENUM (
+    'private',
+    'unlisted',
+    'public'
+);
+
+CREATE TABLE collection (
+    id bigserial PRIMARY KEY,
+    cid bigint NOT NULL, -- owner user id
+    ctime timestamp with time zone DEFAULT now(),
+    title text NOT NULL,
+    description text,
+    visibility collection_visibility_enum NOT NULL DEFAULT 'private'
+);
+ALTER Sequence collection_id_seq RESTART WITH 1000;
+
+CREATE TABLE collection_quote (
+    collection_id bigint NOT NULL REFERENCES collection(id) ON DELETE CASCADE,
+    quote_id bigint NOT NULL REFERENCES quote(id) ON DELETE CASCADE,
+    position integer NOT NULL, -- 0 based, dense within a collection
+    PRIMARY KEY (collection_id, quote_id)
+);
+
+-- Report (user flags a quote), resolved by a moderator
+CREATE TYPE report_reason_enum AS ENUM (
+    'offensive',
+    'misattributed',
+    'duplicate',
+    'other'
+);
+
+CREATE TYPE report_outcome_enum AS ENUM (
+    'dismissed', -- nothing wrong, the quote stays published
+    'corrected', -- the quote was fixed, and stays published
+    'removed' -- the quote is rejected
+);
+
+CREATE TABLE report (
+    id bigserial PRIMARY KEY,
+    quote_id bigint NOT NULL REFERENCES quote(id) ON DELETE CASCADE,
+    cid bigint NOT NULL, -- reporter user id
+    ctime timestamp with time zone NOT NULL DEFAULT now(),
+    reason report_reason_enum NOT NULL,
+    comment text,
+    -- resolution (audit)
+    outcome report_outcome_enum, -- NULL while open
+    resolution_note text,
+    resolved_by bigint, -- moderator user id
+    resolved_at timestamp with time zone
+);
+ALTER Sequence report_id_seq RESTART WITH 1000;
+-- one open report per user and quote
+CREATE UNIQUE INDEX report_open_idx ON report (quote_id, cid) WHERE outcome IS NULL;
+CREATE INDEX report_cid_ctime_idx ON report (cid, ctime);
+
+-- Favorite (user likes a quote)
+CREATE TABLE favorite (
+    user_id bigint NOT NULL,
+    quote_id bigint NOT NULL REFERENCES quote(id) ON DELETE CASCADE,
+    ctime timestamp with time zone DEFAULT now(),
+    PRIMARY KEY (user_id, quote_id)
+);
+
+CREATE FUNCTION favorite_count_update() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE quote SET favorite_count = favorite_count + 1 WHERE id = NEW.quote_id;
+    ELSE
+        UPDATE quote SET favorite_count = favorite_count - 1 WHERE id = OLD.quote_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER favorite_count_trigger AFTER INSERT OR DELETE ON favorite
+    FOR EACH ROW EXECUTE FUNCTION favorite_count_update();
+
+-- Import checkpoint (to resume long imports, e.g., a wikiquote dump)
+CREATE TABLE import_checkpoint (
+    name text PRIMARY KEY, -- e.g., the dump file name
+    page_id bigint NOT NULL, -- last page imported
+    mtime timestamp with time zone DEFAULT now()
+);
@@ -1,3 +1,25 @@
 -- Dev seed
+-- users, with the password `welcome-dev` (alice is admin, the others contributors), and verified emails
+INSERT INTO user_account (id, username, email, pwd_hash, role, email_verified) VALUES
+    (123, 'alice', 'alice@example.com', '$argon2id$v=19$m=19456,t=2,p=1$SjSpaOzxAwdkipkXzzGLTg$CJauZI/fLucKc4vupvyFAD+VJHjYJ0018BVhaif76SM', 'admin', true),
+    (456, 'bob', 'bob@example.com', '$argon2id$v=19$m=19456,t=2,p=1$SjSpaOzxAwdkipkXzzGLTg$CJauZI/fLucKc4vupvyFAD+VJHjYJ0018BVhaif76SM', 'contributor', true),
+    (789, 'carol', 'carol@example.com', '$argon2id$v=19$m=19456,t=2,p=1$SjSpaOzxAwdkipkXzzGLTg$CJauZI/fLucKc4vupvyFAD+VJHjYJ0018BVhaif76SM', 'contributor', true);
+
+-- a session per user, with the id of the user (tokens `new_token(123, 123)` in the tests), expiring as a login one
+INSERT INTO session (id, user_id, user_agent, expires) VALUES
+    (123, 123, 'dev seed', now() + interval '30 days'),
+    (456, 456, 'dev seed', now() + interval '30 days'),
+    (789, 789, 'dev seed', now() + interval '30 days');
+
+-- alice, admin, has to use TOTP: the secret of the authenticator app is `JBSWY3DPEHPK3PXP`
+INSERT INTO user_totp (user_id, secret, enabled) VALUES (123, 'JBSWY3DPEHPK3PXP', true);
+
 INSERT INTO quote (id, cid, quote, author) VALUES (100, 123, 'test quote 100', 'test author');
-INSERT INTO quote (id, cid, quote) VALUES (101, 123, 'test quote 101');
\ No newline at end of file
+INSERT INTO quote (id, cid, quote) VALUES (101, 123, 'test quote 101');
+
+INSERT INTO collection (id, cid, title, visibility) VALUES (100, 123, 'test collection 100', 'public');
+INSERT INTO collection_quote (collection_id, quote_id, position) VALUES (100, 101, 0);
+INSERT INTO collection_quote (collection_id, quote_id, position) VALUES (100, 100, 1);
+INSERT INTO collection (id, cid, title) VALUES (101, 456, 'test collection 101 (private)');
+
+INSERT INTO favorite (user_id, quote_id) VALUES (456, 100);
\ No newline at end of file
@@ -0,0 +1,181 @@
+// Command line tools, run against the existing app database (e.g., `cargo run -- import quotes.csv --user 123`).
+
+use crate::export::{self, ExportFormat, StrfileIndex};
+use crate::import::{self, ImportFormat, ImportOptions, WikiquoteOptions};
+use crate::model::{connect_db, QuoteFilter, QuoteMac, QuoteSort};
+use crate::security;
+use futures::StreamExt;
+use std::fs;
+use std::path::Path;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+const USAGE: &str = "usage: import <file> --user <id> [--format csv|json|ndjson|fortune|kindle] [--map field:column,...] [--notes] [--dry-run] [--batch-size <n>]
+       wikiquote <dump.xml> --user <id> [--dry-run] [--batch-size <n>] [--restart]
+       export <file> --user <id> [--format csv|json|ndjson|fortune] [--sort newest|popular] [--lang <tag>] [--collection <id>]";
+
+/// The command line commands (the first argument), when not starting the web server.
+pub const COMMANDS: &[&str] = &["import", "wikiquote", "export"];
+
+pub async fn run(args: &[String]) -> Result<(), String> {
+	match args.first().map(String::as_str) {
+		Some("import") => run_import(&args[1..]).await,
+		Some("wikiquote") => run_wikiquote(&args[1..]).await,
+		Some("export") => run_export(&args[1..]).await,
+		_ => Err(USAGE.to_string()),
+	}
+}
+
+async fn run_import(args: &[String]) -> Result<(), String> {
+	let mut file: Option<&str> = None;
+	let mut user_id: Option<i64> = None;
+	let mut format: Option<ImportFormat> = None;
+	let mut mapping = None;
+	let mut dry_run = false;
+	let mut include_notes = false;
+	let mut batch_size = None;
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		let mut value = || args.next().ok_or_else(|| format!("missing value for {}\n{}", arg, USAGE));
+		match arg.as_str() {
+			"--user" => user_id = Some(value()?.parse().map_err(|_| "--user must be a user id".to_string())?),
+			"--format" => format = Some(value()?.parse().map_err(|ex: import::Error| ex.to_string())?),
+			"--map" => mapping = Some(import::parse_mapping(value()?).map_err(|ex| ex.to_string())?),
+			"--batch-size" => batch_size = Some(value()?.parse().map_err(|_| "--batch-size must be a number".to_string())?),
+			"--dry-run" => dry_run = true,
+			"--notes" => include_notes = true,
+			other if file.is_none() && !other.starts_with("--") => file = Some(other),
+			other => return Err(format!("unexpected argument '{}'\n{}", other, USAGE)),
+		}
+	}
+	let file = file.ok_or_else(|| USAGE.to_string())?;
+	let user_id = user_id.ok_or_else(|| format!("--user is required\n{}", USAGE))?;
+	let format = match format {
+		Some(format) => format,
+		None => format_of(file)?,
+	};
+
+	let mut options = ImportOptions::new(format);
+	options.dry_run = dry_run;
+	options.include_notes = include_notes;
+	options.batch_size = batch_size.unwrap_or(options.batch_size);
+	options.mapping = mapping.unwrap_or_default();
+
+	let content = fs::read_to_string(file).map_err(|ex| format!("cannot read '{}' - {}", file, ex))?;
+	let db = connect_db().await.map_err(|ex| format!("cannot connect to the db - {}", ex))?;
+	let utx = security::utx_from_user_id(&db, user_id).await.map_err(|ex| ex.to_string())?;
+
+	let report = match import::import_quotes(&db, &utx, &content, &options).await {
+		Ok(report) => report,
+		Err(ex) => {
+			// print what the committed batches created before failing
+			if let import::Error::Interrupted { report, .. } = &ex {
+				println!("{}", serde_json::to_string_pretty(report).map_err(|ex| ex.to_string())?);
+			}
+			return Err(ex.to_string());
+		}
+	};
+	println!("{}", serde_json::to_string_pretty(&report).map_err(|ex| ex.to_string())?);
+
+	Ok(())
+}
+
+async fn run_wikiquote(args: &[String]) -> Result<(), String> {
+	let mut file: Option<&str> = None;
+	let mut user_id: Option<i64> = None;
+	let mut options = WikiquoteOptions {
+		dry_run: false,
+		batch_size: import::DEFAULT_BATCH_SIZE,
+		restart: false,
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		let mut value = || args.next().ok_or_else(|| format!("missing value for {}\n{}", arg, USAGE));
+		match arg.as_str() {
+			"--user" => user_id = Some(value()?.parse().map_err(|_| "--user must be a user id".to_string())?),
+			"--batch-size" => options.batch_size = value()?.parse().map_err(|_| "--batch-size must be a number".to_string())?,
+			"--dry-run" => options.dry_run = true,
+			"--restart" => options.restart = true,
+			other if file.is_none() && !other.starts_with("--") => file = Some(other),
+			other => return Err(format!("unexpected argument '{}'\n{}", other, USAGE)),
+		}
+	}
+	let file = file.ok_or_else(|| USAGE.to_string())?;
+	let user_id = user_id.ok_or_else(|| format!("--user is required\n{}", USAGE))?;
+
+	let db = connect_db().await.map_err(|ex| format!("cannot connect to the db - {}", ex))?;
+	let utx = security::utx_from_user_id(&db, user_id).await.map_err(|ex| ex.to_string())?;
+
+	let report = import::import_wikiquote(&db, &utx, Path::new(file), &options).await.map_err(|ex| ex.to_string())?;
+	println!("{}", serde_json::to_string_pretty(&report).map_err(|ex| ex.to_string())?);
+
+	Ok(())
+}
+
+async fn run_export(args: &[String]) -> Result<(), String> {
+	let mut file: Option<&str> = None;
+	let mut user_id: Option<i64> = None;
+	let mut format: Option<ExportFormat> = None;
+	let mut filter = QuoteFilter::default();
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		let mut value = || args.next().ok_or_else(|| format!("missing value for {}\n{}", arg, USAGE));
+		match arg.as_str() {
+			"--user" => user_id = Some(value()?.parse().map_err(|_| "--user must be a user id".to_string())?),
+			"--format" => format = Some(value()?.parse().map_err(|ex: export::Error| ex.to_string())?),
+			"--sort" => {
+				filter.sort = match value()?.as_str() {
+					"newest" => Some(QuoteSort::Newest),
+					"popular" => Some(QuoteSort::Popular),
+					other => return Err(format!("unknown sort '{}' (expected newest or popular)", other)),
+				}
+			}
+			"--lang" => filter.lang = Some(value()?.to_string()),
+			"--collection" => {
+				filter.collection_id = Some(value()?.parse().map_err(|_| "--collection must be a collection id".to_string())?)
+			}
+			other if file.is_none() && !other.starts_with("--") => file = Some(other),
+			other => return Err(format!("unexpected argument '{}'\n{}", other, USAGE)),
+		}
+	}
+	let file = file.ok_or_else(|| USAGE.to_string())?;
+	let user_id = user_id.ok_or_else(|| format!("--user is required\n{}", USAGE))?;
+	let format = match format {
+		Some(format) => format,
+		None => format_of(file)?,
+	};
+
+	let db = connect_db().await.map_err(|ex| format!("cannot connect to the db - {}", ex))?;
+	let utx = security::utx_from_user_id(&db, user_id).await.map_err(|ex| ex.to_string())?;
+
+	// write the chunks as they come, the export is never fully in memory
+	let out = tokio::fs::File::create(file).await.map_err(|ex| format!("cannot create '{}' - {}", file, ex))?;
+	let mut out = BufWriter::new(out);
+	let mut index = StrfileIndex::default(); // fortune only, one chunk per entry
+	let quotes = QuoteMac::stream(&db, &utx, &filter).map_err(|ex| ex.to_string())?;
+	let mut chunks = export::encode(quotes, format);
+	while let Some(chunk) = chunks.next().await {
+		let chunk = chunk.map_err(|ex| ex.to_string())?;
+		out.write_all(&chunk).await.map_err(|ex| ex.to_string())?;
+		index.add(&chunk);
+	}
+	out.flush().await.map_err(|ex| ex.to_string())?;
+
+	// the strfile index next to the fortune file, so `fortune <file>` works directly
+	if format == ExportFormat::Fortune {
+		let dat = format!("{}.dat", file);
+		fs::write(&dat, index.to_bytes()).map_err(|ex| format!("cannot write '{}' - {}", dat, ex))?;
+	}
+
+	Ok(())
+}
+
+// region: Utils
+/// Format from the file extension, when not given.
+fn format_of<F: std::str::FromStr>(file: &str) -> Result<F, String> {
+	let ext = Path::new(file).extension().and_then(|e| e.to_str()).unwrap_or_default();
+	ext.parse().map_err(|_| format!("cannot guess the format of '{}', use --format", file))
+}
+// endregion: Utils
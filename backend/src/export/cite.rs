@@ -0,0 +1,202 @@
+// Citations of quotes (BibTeX, CSL-JSON, APA and MLA), from their author, source and locator.
+
+use super::Error;
+use crate::model::{Quote, DEFAULT_AUTHOR};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiteFormat {
+	Bibtex,
+	CslJson,
+	Apa,
+	Mla,
+}
+
+impl CiteFormat {
+	pub fn content_type(self) -> &'static str {
+		match self {
+			CiteFormat::Bibtex => "application/x-bibtex; charset=utf-8",
+			CiteFormat::CslJson => "application/vnd.citationstyles.csl+json",
+			CiteFormat::Apa | CiteFormat::Mla => "text/plain; charset=utf-8",
+		}
+	}
+}
+
+impl FromStr for CiteFormat {
+	type Err = Error;
+
+	fn from_str(format: &str) -> Result<Self, Error> {
+		match format.to_ascii_lowercase().as_str() {
+			"bibtex" => Ok(CiteFormat::Bibtex),
+			"csl-json" => Ok(CiteFormat::CslJson),
+			"apa" => Ok(CiteFormat::Apa),
+			"mla" => Ok(CiteFormat::Mla),
+			other => Err(Error::UnknownCiteFormat(other.to_string())),
+		}
+	}
+}
+
+/// The citation of each quote, in the given order (APA and MLA reference lists are sorted by author).
+/// Fails, listing each quote and what it misses, when a quote has no known author or no source.
+pub fn cite(quotes: &[Quote], format: CiteFormat) -> Result<String, Error> {
+	let missing: Vec<String> = quotes
+		.iter()
+		.filter_map(|quote| {
+			let mut missing = Vec::new();
+			if quote.author.trim().is_empty() || quote.author == DEFAULT_AUTHOR {
+				missing.push("author");
+			}
+			if quote.source.as_deref().is_none_or(|s| s.trim().is_empty()) {
+				missing.push("source");
+			}
+			(!missing.is_empty()).then(|| format!("quote {} has no {}", quote.id, missing.join(" and no ")))
+		})
+		.collect();
+	if !missing.is_empty() {
+		return Err(Error::MissingCitationMetadata(missing.join(", ")));
+	}
+
+	let citations = quotes.iter().map(Citation::from);
+	let text = match format {
+		CiteFormat::Bibtex => citations.map(|c| c.bibtex()).collect::<Vec<_>>().join("\n"),
+		CiteFormat::CslJson => {
+			let items: Vec<Value> = citations.map(|c| c.csl_json()).collect();
+			serde_json::to_string_pretty(&items)?
+		}
+		CiteFormat::Apa | CiteFormat::Mla => {
+			let mut citations: Vec<Citation> = citations.collect();
+			citations.sort_by(|a, b| (a.family, a.given).cmp(&(b.family, b.given)));
+			let lines: Vec<String> = citations.iter().map(|c| if format == CiteFormat::Apa { c.apa() } else { c.mla() }).collect();
+			lines.join("\n") + "\n"
+		}
+	};
+
+	Ok(text)
+}
+
+/// What the citations are made of. The author `Albert Einstein` is family `Einstein`, given `Albert`.
+struct Citation<'a> {
+	id: i64,
+	family: &'a str,
+	given: Option<&'a str>,
+	title: &'a str,
+	locator: Option<&'a str>,
+	quote: &'a str,
+	lang: &'a str,
+}
+
+impl<'a> From<&'a Quote> for Citation<'a> {
+	fn from(quote: &'a Quote) -> Self {
+		let author = quote.author.trim();
+		let (given, family) = match author.rsplit_once(' ') {
+			Some((given, family)) => (Some(given.trim()), family),
+			None => (None, author),
+		};
+		Citation {
+			id: quote.id,
+			family,
+			given,
+			title: quote.source.as_deref().unwrap_or_default().trim(),
+			locator: quote.locator.as_deref(),
+			quote: &quote.quote,
+			lang: &quote.lang,
+		}
+	}
+}
+
+impl Citation<'_> {
+	fn bibtex(&self) -> String {
+		let key: String = self.family.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+		let author = match self.given {
+			Some(given) => format!("{}, {}", self.family, given),
+			None => self.family.to_string(),
+		};
+		let mut fields = vec![("author", author), ("title", self.title.to_string())];
+		fields.extend(self.pages().map(|pages| ("pages", pages.to_string())));
+		fields.extend(self.locator_note().map(|locator| ("note", locator.to_string())));
+		fields.push(("annote", self.quote.to_string()));
+
+		let fields: Vec<String> = fields.iter().map(|(name, value)| format!("  {} = {{{}}}", name, bibtex_escape(value))).collect();
+		format!("@misc{{{}{},\n{}\n}}\n", key, self.id, fields.join(",\n"))
+	}
+
+	fn csl_json(&self) -> Value {
+		let mut author = json!({ "family": self.family });
+		if let Some(given) = self.given {
+			author["given"] = json!(given);
+		}
+		let mut item = json!({
+			"id": format!("quote-{}", self.id),
+			"type": "document",
+			"title": self.title,
+			"author": [author],
+			"note": self.quote,
+		});
+		if let Some(pages) = self.pages() {
+			item["page"] = json!(pages);
+		}
+		if let Some(locator) = self.locator_note() {
+			item["locator"] = json!(locator);
+		}
+		if self.lang != "und" {
+			item["language"] = json!(self.lang);
+		}
+		item
+	}
+
+	/// The pages of a `p. 12` or `pp. 12-14` locator (`12` of the Kindle `p. 12, loc. 170-174`).
+	fn pages(&self) -> Option<&str> {
+		let locator = self.locator?.trim();
+		let pages = locator.strip_prefix("p. ").or_else(|| locator.strip_prefix("pp. "))?;
+		Some(pages.split(',').next().unwrap_or_default().trim()).filter(|pages| !pages.is_empty())
+	}
+
+	/// The whole locator, when it says more than the pages (e.g., a Kindle location).
+	fn locator_note(&self) -> Option<&str> {
+		self.locator.map(str::trim).filter(|locator| self.pages().is_none() || locator.contains(','))
+	}
+
+	/// `Einstein, A. (n.d.). On Science (p. 12).`
+	fn apa(&self) -> String {
+		let initials: Vec<String> = self
+			.given
+			.unwrap_or_default()
+			.split_whitespace()
+			.filter_map(|name| name.chars().next().map(|c| format!("{}.", c)))
+			.collect();
+		let author = match initials.is_empty() {
+			true => self.family.to_string(),
+			false => format!("{}, {}", self.family, initials.join(" ")),
+		};
+		let locator = self.locator.map(|l| format!(" ({})", l)).unwrap_or_default();
+		format!("{} (n.d.). {}{}.", author, self.title, locator)
+	}
+
+	/// `Einstein, Albert. On Science, p. 12.`
+	fn mla(&self) -> String {
+		let author = match self.given {
+			Some(given) => format!("{}, {}", self.family, given),
+			None => self.family.to_string(),
+		};
+		let locator = self.locator.map(|l| format!(", {}", l)).unwrap_or_default();
+		format!("{}. {}{}.", author.trim_end_matches('.'), self.title, locator)
+	}
+}
+
+/// Escape the BibTeX special characters.
+fn bibtex_escape(value: &str) -> String {
+	value.chars().fold(String::new(), |mut escaped, c| {
+		match c {
+			'{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+				escaped.push('\\');
+				escaped.push(c);
+			}
+			'\\' => escaped.push_str("\\textbackslash{}"),
+			'~' => escaped.push_str("\\textasciitilde{}"),
+			'^' => escaped.push_str("\\textasciicircum{}"),
+			c => escaped.push(c),
+		}
+		escaped
+	})
+}
@@ -0,0 +1,63 @@
+// BSD `fortune` files: the quotes separated by `%` lines, and the `strfile` .dat index of their offsets.
+
+use crate::model::{Quote, DEFAULT_AUTHOR};
+
+/// strfile header version.
+const STRFILE_VERSION: u32 = 2;
+pub const FORTUNE_DELIMITER: u8 = b'%';
+
+/// The fortune entry of a quote: the text, the `-- Author` line (when known), then the `%` line.
+pub fn fortune_entry(quote: &Quote) -> Vec<u8> {
+	let mut entry = String::new();
+	for line in quote.quote.trim_end().lines() {
+		// a line starting with `%` would end the entry (or be a strfile comment): one more leading space, removed by the import
+		if line.trim_start_matches(' ').starts_with('%') {
+			entry.push(' ');
+		}
+		entry.push_str(line);
+		entry.push('\n');
+	}
+	if quote.author != DEFAULT_AUTHOR {
+		entry.push_str(&format!("\t\t-- {}\n", quote.author));
+	}
+	entry.push_str("%\n");
+
+	entry.into_bytes()
+}
+
+/// The strfile index (what `strfile` writes to the .dat file), built while the entries are written.
+#[derive(Debug, Default)]
+pub struct StrfileIndex {
+	offsets: Vec<u32>, // start of each entry
+	end: u32,
+	longlen: u32,
+	shortlen: u32,
+}
+
+impl StrfileIndex {
+	/// Add the next entry, as returned by `fortune_entry` (ending with the `%` line).
+	pub fn add(&mut self, entry: &[u8]) {
+		let len = entry.len().saturating_sub(2) as u32; // without the `%\n`
+		if self.offsets.is_empty() || len < self.shortlen {
+			self.shortlen = len;
+		}
+		self.longlen = self.longlen.max(len);
+		self.offsets.push(self.end);
+		self.end += entry.len() as u32;
+	}
+
+	/// The big endian .dat content: version, number of entries, longest, shortest, flags, delimiter (padded to 4 bytes),
+	/// then the offset of each entry and the end of file offset.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(24 + 4 * (self.offsets.len() + 1));
+		for value in [STRFILE_VERSION, self.offsets.len() as u32, self.longlen, self.shortlen, 0] {
+			bytes.extend(value.to_be_bytes());
+		}
+		bytes.extend([FORTUNE_DELIMITER, 0, 0, 0]);
+		for offset in self.offsets.iter().chain(std::iter::once(&self.end)) {
+			bytes.extend(offset.to_be_bytes());
+		}
+
+		bytes
+	}
+}
@@ -0,0 +1,179 @@
+// Export of quotes: encode a stream of quotes as CSV, JSON, NDJSON or fortune chunks, one chunk per quote,
+// so the whole export never sits in memory.
+
+use crate::model::{self, Quote};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::str::FromStr;
+
+mod cite;
+mod fortune;
+
+pub use cite::{cite, CiteFormat};
+pub use fortune::StrfileIndex;
+
+/// CSV columns, importable back with the CSV import.
+const CSV_HEADER: &[&str] = &["id", "quote", "author", "source", "locator", "tags", "lang", "translator", "original_id"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+	Csv,
+	#[default]
+	Json,
+	Ndjson,
+	Fortune,
+	/// The strfile .dat index of the `Fortune` export
+	Strfile,
+}
+
+impl ExportFormat {
+	pub fn content_type(self) -> &'static str {
+		match self {
+			ExportFormat::Csv => "text/csv; charset=utf-8",
+			ExportFormat::Json => "application/json",
+			ExportFormat::Ndjson => "application/x-ndjson",
+			ExportFormat::Fortune => "text/plain; charset=utf-8",
+			ExportFormat::Strfile => "application/octet-stream",
+		}
+	}
+
+	/// Download file name (fortune files have no extension).
+	pub fn file_name(self) -> &'static str {
+		match self {
+			ExportFormat::Csv => "quotes.csv",
+			ExportFormat::Json => "quotes.json",
+			ExportFormat::Ndjson => "quotes.ndjson",
+			ExportFormat::Fortune => "quotes",
+			ExportFormat::Strfile => "quotes.dat",
+		}
+	}
+}
+
+impl FromStr for ExportFormat {
+	type Err = Error;
+
+	fn from_str(format: &str) -> Result<Self, Error> {
+		match format.to_ascii_lowercase().as_str() {
+			"csv" => Ok(ExportFormat::Csv),
+			"json" => Ok(ExportFormat::Json),
+			"ndjson" => Ok(ExportFormat::Ndjson),
+			"fortune" => Ok(ExportFormat::Fortune),
+			"strfile" | "dat" => Ok(ExportFormat::Strfile),
+			other => Err(Error::UnknownFormat(other.to_string())),
+		}
+	}
+}
+
+/// Encode the quotes in the format. The chunks are the header (if any), one per quote, then the footer (if any).
+/// `Strfile` is the exception, a single chunk once all the quotes are indexed.
+pub fn encode<S>(quotes: S, format: ExportFormat) -> BoxStream<'static, Result<Vec<u8>, Error>>
+where
+	S: Stream<Item = Result<Quote, model::Error2>> + Send + 'static,
+{
+	if format == ExportFormat::Strfile {
+		let index = quotes.fold(Ok(StrfileIndex::default()), |index: Result<StrfileIndex, Error>, quote| async move {
+			let mut index = index?;
+			index.add(&fortune::fortune_entry(&quote?));
+			Ok(index)
+		});
+		return stream::once(async move { Ok(index.await?.to_bytes()) }).boxed();
+	}
+
+	let body = quotes.enumerate().map(move |(idx, quote)| {
+		let quote = quote?;
+		match format {
+			ExportFormat::Csv => csv_row(csv_values(quote)),
+			ExportFormat::Json => {
+				let mut chunk = if idx == 0 { b"\n".to_vec() } else { b",\n".to_vec() };
+				chunk.extend(serde_json::to_vec(&quote)?);
+				Ok(chunk)
+			}
+			ExportFormat::Ndjson => {
+				let mut chunk = serde_json::to_vec(&quote)?;
+				chunk.push(b'\n');
+				Ok(chunk)
+			}
+			ExportFormat::Fortune | ExportFormat::Strfile => Ok(fortune::fortune_entry(&quote)),
+		}
+	});
+
+	stream::once(async move { header(format) })
+		.chain(body)
+		.chain(stream::once(async move { Ok(footer(format)) }))
+		.filter(|chunk| std::future::ready(!matches!(chunk, Ok(chunk) if chunk.is_empty())))
+		.boxed()
+}
+
+// region: Utils
+fn header(format: ExportFormat) -> Result<Vec<u8>, Error> {
+	match format {
+		ExportFormat::Csv => csv_row(CSV_HEADER.iter().map(|h| h.to_string())),
+		ExportFormat::Json => Ok(b"[".to_vec()),
+		ExportFormat::Ndjson | ExportFormat::Fortune | ExportFormat::Strfile => Ok(Vec::new()),
+	}
+}
+
+fn footer(format: ExportFormat) -> Vec<u8> {
+	match format {
+		ExportFormat::Json => b"\n]\n".to_vec(),
+		_ => Vec::new(),
+	}
+}
+
+fn csv_values(quote: Quote) -> Vec<String> {
+	vec![
+		quote.id.to_string(),
+		quote.quote,
+		quote.author,
+		quote.source.unwrap_or_default(),
+		quote.locator.unwrap_or_default(),
+		quote.tags.join(","),
+		quote.lang,
+		quote.translator.unwrap_or_default(),
+		quote.original_id.map(|id| id.to_string()).unwrap_or_default(),
+	]
+}
+
+fn csv_row(values: impl IntoIterator<Item = String>) -> Result<Vec<u8>, Error> {
+	let mut writer = csv::Writer::from_writer(Vec::new());
+	writer.write_record(values)?;
+	writer.into_inner().map_err(|ex| Error::Encode(ex.to_string()))
+}
+// endregion: Utils
+
+// region: Error
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Unknown export format '{0}' (expected csv, json, ndjson, fortune or strfile)")]
+	UnknownFormat(String),
+
+	#[error("Unknown citation format '{0}' (expected bibtex, csl-json, apa or mla)")]
+	UnknownCiteFormat(String),
+
+	#[error("Fail to encode the export - {0}")]
+	Encode(String),
+
+	#[error("Missing citation metadata - {0}")]
+	MissingCitationMetadata(String),
+
+	#[error(transparent)]
+	Model(#[from] model::Error2),
+}
+
+impl From<csv::Error> for Error {
+	fn from(ex: csv::Error) -> Self {
+		Error::Encode(ex.to_string())
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(ex: serde_json::Error) -> Self {
+		Error::Encode(ex.to_string())
+	}
+}
+// endregion: Error
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/export.rs"]
+mod tests;
+// endregion: Test
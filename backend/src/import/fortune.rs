@@ -0,0 +1,69 @@
+// Parsing of BSD `fortune` files: entries separated by `%` lines, with an optional `-- Author` last line.
+
+use super::ImportRecord;
+use crate::model::QuotePatch;
+
+// Attribution prefixes of the last line of an entry.
+const AUTHOR_PREFIXES: &[&str] = &["--", "\u{2014}", "\u{2015}"];
+
+/// Parse a fortune file. The line of a record is the first line of its entry.
+/// `%%` lines are strfile comments, and empty entries are ignored.
+/// The quote lines starting with `%` are exported with one more leading space (see `export::fortune_entry`), removed here.
+pub fn parse_fortune(content: &str) -> Vec<ImportRecord> {
+	let mut records = Vec::new();
+	let mut entry: Vec<&str> = Vec::new();
+	let mut start = 0;
+
+	for (idx, line) in content.lines().enumerate() {
+		if line.starts_with("%%") {
+			continue;
+		}
+		if line.trim_end() == "%" {
+			records.extend(entry_record(start, &entry));
+			entry.clear();
+		} else {
+			if entry.is_empty() {
+				start = idx + 1;
+			}
+			entry.push(unescape(line));
+		}
+	}
+	records.extend(entry_record(start, &entry));
+
+	records
+}
+
+/// The quote line of an escaped `%` line, e.g. ` %%` for `%%`.
+fn unescape(line: &str) -> &str {
+	match line.strip_prefix(' ') {
+		Some(unescaped) if unescaped.trim_start_matches(' ').starts_with('%') => unescaped,
+		_ => line,
+	}
+}
+
+fn entry_record(line: usize, entry: &[&str]) -> Option<ImportRecord> {
+	let mut lines = entry.to_vec();
+	while lines.last().is_some_and(|l| l.trim().is_empty()) {
+		lines.pop();
+	}
+
+	let author = lines.last().and_then(|last| {
+		let last = last.trim();
+		AUTHOR_PREFIXES.iter().find_map(|prefix| last.strip_prefix(prefix)).map(|author| author.trim().to_string())
+	});
+	if author.is_some() {
+		lines.pop();
+	}
+
+	let quote = lines.join("\n");
+	if quote.trim().is_empty() {
+		return None;
+	}
+
+	let data = QuotePatch {
+		quote: Some(quote),
+		author,
+		..Default::default()
+	};
+	Some(ImportRecord { line, data: Ok(data) })
+}
@@ -0,0 +1,231 @@
+// Parsing of the Kindle `My Clippings.txt` file, where each clipping is:
+//
+//   Book Title (Author)
+//   - Your Highlight on page 12 | Location 170-172 | Added on Sunday, May 3, 2020 10:15:42 PM
+//
+//   the highlighted text
+//   ==========
+
+use super::ImportRecord;
+use crate::model::{Nullable, QuotePatch, Tags};
+
+const CLIPPING_SEPARATOR: &str = "==========";
+/// Tag of the notes, when imported.
+const KINDLE_NOTE_TAG: &str = "kindle-note";
+
+const MONTHS: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClippingKind {
+	Highlight,
+	Note,
+	Bookmark,
+}
+
+#[derive(Debug)]
+struct Clipping {
+	line: usize,
+	title: String,
+	author: Option<String>,
+	kind: ClippingKind,
+	page: Option<String>,
+	location: Option<(u32, u32)>,
+	added_on: Option<ClippingDate>,
+	text: String,
+}
+
+/// (year, month, day, hour, minute, second), ordered by time.
+type ClippingDate = (u32, u32, u32, u32, u32, u32);
+
+/// Parse the clippings into quotes, with the book as source and the page/location as locator.
+/// Bookmarks are ignored, and notes too unless `include_notes` (they are then tagged `kindle-note`).
+/// A highlight overlapping another one of the same book (re-highlighted with more or less text)
+/// only keeps the most recent of the two.
+pub fn parse_kindle(content: &str, include_notes: bool) -> Vec<ImportRecord> {
+	let mut records: Vec<ImportRecord> = Vec::new();
+	let mut highlights: Vec<Clipping> = Vec::new();
+
+	for (line, lines) in split_clippings(content) {
+		let clipping = match parse_clipping(line, &lines) {
+			Ok(clipping) => clipping,
+			Err(reason) => {
+				records.push(ImportRecord { line, data: Err(reason) });
+				continue;
+			}
+		};
+
+		match clipping.kind {
+			ClippingKind::Bookmark => (),
+			ClippingKind::Note if include_notes => records.push(clipping.into_record()),
+			ClippingKind::Note => (),
+			ClippingKind::Highlight => match highlights.iter().position(|h| h.overlaps(&clipping)) {
+				Some(idx) if clipping.is_more_recent(&highlights[idx]) => highlights[idx] = clipping,
+				Some(_) => (),
+				None => highlights.push(clipping),
+			},
+		}
+	}
+
+	records.extend(highlights.into_iter().map(Clipping::into_record));
+	records.sort_by_key(|r| r.line);
+
+	records
+}
+
+impl Clipping {
+	/// Same book, and the same text range (or one text within the other when there is no location).
+	fn overlaps(&self, other: &Clipping) -> bool {
+		if self.title != other.title || self.author != other.author {
+			return false;
+		}
+		match (self.location, other.location) {
+			(Some((start, end)), Some((other_start, other_end))) => start <= other_end && other_start <= end,
+			_ => self.text.contains(&other.text) || other.text.contains(&self.text),
+		}
+	}
+
+	// The file is in clipping order, so a later clipping is as recent when the dates can't tell.
+	fn is_more_recent(&self, other: &Clipping) -> bool {
+		match (self.added_on, other.added_on) {
+			(Some(date), Some(other_date)) => date >= other_date,
+			_ => true,
+		}
+	}
+
+	fn locator(&self) -> Option<String> {
+		let page = self.page.as_ref().map(|page| format!("p. {}", page));
+		let location = self.location.map(|(start, end)| match start == end {
+			true => format!("loc. {}", start),
+			false => format!("loc. {}-{}", start, end),
+		});
+		let locator: Vec<String> = page.into_iter().chain(location).collect();
+		(!locator.is_empty()).then(|| locator.join(", "))
+	}
+
+	fn into_record(self) -> ImportRecord {
+		let is_note = self.kind == ClippingKind::Note;
+		let data = QuotePatch {
+			quote: Some(self.text.clone()),
+			// a note is the reader's, not the book author's
+			author: if is_note { None } else { self.author.clone() },
+			source: Some(Nullable::Value(self.title.clone())),
+			locator: self.locator().map(Nullable::Value),
+			tags: is_note.then(|| Tags(vec![KINDLE_NOTE_TAG.to_string()])),
+			..Default::default()
+		};
+		ImportRecord { line: self.line, data: Ok(data) }
+	}
+}
+
+// region: Utils
+/// The lines of each clipping, with the line number of its first line.
+fn split_clippings(content: &str) -> Vec<(usize, Vec<&str>)> {
+	let mut clippings = Vec::new();
+	let mut lines: Vec<&str> = Vec::new();
+	let mut start = 0;
+
+	for (idx, line) in content.lines().enumerate() {
+		// the file starts with a BOM, and so can each appended clipping
+		let line = line.trim_start_matches('\u{feff}');
+		if line.trim_end() == CLIPPING_SEPARATOR {
+			if lines.iter().any(|l| !l.trim().is_empty()) {
+				clippings.push((start, std::mem::take(&mut lines)));
+			}
+			lines.clear(); // blank lines only
+		} else {
+			if lines.is_empty() {
+				start = idx + 1;
+			}
+			lines.push(line);
+		}
+	}
+	if lines.iter().any(|l| !l.trim().is_empty()) {
+		clippings.push((start, lines));
+	}
+
+	clippings
+}
+
+fn parse_clipping(line: usize, lines: &[&str]) -> Result<Clipping, String> {
+	let mut lines = lines.iter().map(|l| l.trim_end()).skip_while(|l| l.trim().is_empty());
+	let (title, author) = parse_title(lines.next().unwrap_or_default());
+	let meta = lines.next().and_then(|l| l.trim().strip_prefix('-')).ok_or("unrecognized clipping")?;
+	let text = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+
+	let mut segments = meta.split('|').map(str::trim);
+	let kind = segments.next().unwrap_or_default().to_lowercase();
+	let kind = if kind.contains("highlight") {
+		ClippingKind::Highlight
+	} else if kind.contains("note") {
+		ClippingKind::Note
+	} else if kind.contains("bookmark") {
+		ClippingKind::Bookmark
+	} else {
+		return Err(format!("unrecognized clipping kind '{}'", kind.trim()));
+	};
+
+	// the page and location can be in the kind segment (`Your Highlight on page 12`) or their own
+	let mut clipping = Clipping { line, title, author, kind, page: None, location: None, added_on: None, text };
+	for segment in std::iter::once(meta.split('|').next().unwrap_or_default()).chain(segments) {
+		let lower = segment.to_lowercase();
+		if let Some(idx) = lower.find("page ") {
+			clipping.page = lower[idx + 5..].split_whitespace().next().map(str::to_string);
+		}
+		if let Some(idx) = lower.find("location ").map(|i| i + 9).or_else(|| lower.find("loc. ").map(|i| i + 5)) {
+			clipping.location = lower[idx..].split_whitespace().next().and_then(parse_location);
+		}
+		if let Some(idx) = lower.find("added on ") {
+			clipping.added_on = parse_date(&lower[idx + 9..]);
+		}
+	}
+
+	if clipping.kind != ClippingKind::Bookmark && clipping.text.is_empty() {
+		return Err("empty clipping".to_string());
+	}
+
+	Ok(clipping)
+}
+
+/// `Title (Author)`, the author being in the last parentheses.
+fn parse_title(line: &str) -> (String, Option<String>) {
+	let line = line.trim();
+	let split = line.strip_suffix(')').and_then(|rest| rest.rfind('(').map(|idx| (&rest[..idx], rest[idx + 1..].trim())));
+	match split {
+		Some((title, author)) if !author.is_empty() => (title.trim().to_string(), Some(author.to_string())),
+		_ => (line.to_string(), None),
+	}
+}
+
+/// `170-172`, `170-72` (the end abbreviated as the start), or `170`.
+fn parse_location(location: &str) -> Option<(u32, u32)> {
+	let (start, end) = location.split_once('-').unwrap_or((location, location));
+	let first: u32 = start.parse().ok()?;
+	let end = match end.len() < start.len() {
+		true => format!("{}{}", &start[..start.len() - end.len()], end),
+		false => end.to_string(),
+	};
+	let last: u32 = end.parse().ok()?;
+
+	Some((first, last.max(first)))
+}
+
+/// `Sunday, May 3, 2020 10:15:42 PM` (or the `Sunday, 3 May 2020 22:15:42` variant).
+fn parse_date(date: &str) -> Option<ClippingDate> {
+	let tokens: Vec<String> = date.replace(',', " ").split_whitespace().map(str::to_lowercase).collect();
+
+	let month = tokens.iter().find_map(|t| MONTHS.iter().position(|m| t.starts_with(m)))? as u32 + 1;
+	let numbers: Vec<u32> = tokens.iter().filter_map(|t| t.parse().ok()).collect();
+	let day = *numbers.iter().find(|n| (1..=31).contains(*n))?;
+	let year = *numbers.iter().find(|n| **n > 31)?;
+
+	let time: Vec<u32> = tokens.iter().find(|t| t.contains(':'))?.split(':').filter_map(|t| t.parse().ok()).collect();
+	let mut hour = *time.first()?;
+	match tokens.iter().any(|t| t == "pm") {
+		true if hour < 12 => hour += 12,
+		false if hour == 12 && tokens.iter().any(|t| t == "am") => hour = 0,
+		_ => (),
+	}
+
+	Some((year, month, day, hour, *time.get(1).unwrap_or(&0), *time.get(2).unwrap_or(&0)))
+}
+// endregion: Utils
@@ -0,0 +1,282 @@
+// Bulk import of quotes: parse a file (CSV, JSON, NDJSON, fortune or Kindle clippings) into QuotePatch records, validate them,
+// skip the duplicates, then create the quotes in batches (or only report what would happen with dry_run).
+
+use crate::model::{self, ensure, normalize_lang_tag, Action, Db, Nullable, QuoteMac, QuotePatch, Tags, DEFAULT_AUTHOR};
+use crate::security::UserCtx;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+mod fortune;
+mod kindle;
+mod records;
+mod wikiquote;
+
+pub use wikiquote::{import_wikiquote, WikiquoteOptions, WikiquoteReport};
+
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+// region: Import Types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+	Csv,
+	Json,
+	Ndjson,
+	Fortune,
+	/// Kindle `My Clippings.txt`
+	Kindle,
+}
+
+impl FromStr for ImportFormat {
+	type Err = Error;
+
+	fn from_str(format: &str) -> Result<Self, Error> {
+		match format.to_ascii_lowercase().as_str() {
+			"csv" | "text/csv" => Ok(ImportFormat::Csv),
+			"json" | "application/json" => Ok(ImportFormat::Json),
+			"ndjson" | "application/x-ndjson" => Ok(ImportFormat::Ndjson),
+			"fortune" => Ok(ImportFormat::Fortune),
+			"kindle" => Ok(ImportFormat::Kindle),
+			other => Err(Error::UnknownFormat(other.to_string())),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+	pub format: ImportFormat,
+	pub dry_run: bool,
+	pub batch_size: usize,
+	/// CSV only, quote field => column name
+	pub mapping: HashMap<String, String>,
+	/// Kindle only, import the notes too (not only the highlights)
+	pub include_notes: bool,
+}
+
+impl ImportOptions {
+	pub fn new(format: ImportFormat) -> Self {
+		ImportOptions {
+			format,
+			dry_run: false,
+			batch_size: DEFAULT_BATCH_SIZE,
+			mapping: HashMap::new(),
+			include_notes: false,
+		}
+	}
+}
+
+/// A parsed record, or why it could not be parsed.
+#[derive(Debug)]
+pub struct ImportRecord {
+	pub line: usize,
+	pub data: Result<QuotePatch, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+	pub dry_run: bool,
+	pub created: usize,
+	pub skipped: usize,
+	pub rejected: usize,
+	pub records: Vec<ImportLineReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportLineReport {
+	pub line: usize,
+	pub outcome: ImportOutcome,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub reason: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub id: Option<i64>, // id of the created quote
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportOutcome {
+	Created,
+	Skipped,
+	Rejected,
+}
+// endregion: Import Types
+
+/// Parse a `field:column,field:column` CSV column mapping (e.g., `quote:text,author:by`).
+pub fn parse_mapping(mapping: &str) -> Result<HashMap<String, String>, Error> {
+	mapping
+		.split(',')
+		.filter(|item| !item.trim().is_empty())
+		.map(|item| match item.split_once(':') {
+			Some((field, column)) if !field.trim().is_empty() && !column.trim().is_empty() => {
+				Ok((field.trim().to_string(), column.trim().to_string()))
+			}
+			_ => Err(Error::InvalidMapping(item.to_string())),
+		})
+		.collect()
+}
+
+pub async fn import_quotes(db: &Db, utx: &UserCtx, content: &str, options: &ImportOptions) -> Result<ImportReport, Error> {
+	let records = match options.format {
+		ImportFormat::Csv => records::parse_csv(content, &options.mapping)?,
+		ImportFormat::Json => records::parse_json(content)?,
+		ImportFormat::Ndjson => records::parse_ndjson(content)?,
+		ImportFormat::Fortune => fortune::parse_fortune(content),
+		ImportFormat::Kindle => kindle::parse_kindle(content, options.include_notes),
+	};
+
+	import_records(db, utx, records, options.dry_run, options.batch_size).await
+}
+
+/// Validate and deduplicate the records, then create them `batch_size` at a time (one transaction per batch).
+/// If a batch fails, the batches before it stay committed, and `Error::Interrupted` carries their report.
+pub async fn import_records(
+	db: &Db,
+	utx: &UserCtx,
+	records: Vec<ImportRecord>,
+	dry_run: bool,
+	batch_size: usize,
+) -> Result<ImportReport, Error> {
+	// a dry run too, as its report tells which quotes exist
+	ensure(utx, Action::CreateQuote).map_err(model::Error2::from)?;
+
+	let mut report = ImportReport {
+		dry_run,
+		created: 0,
+		skipped: 0,
+		rejected: 0,
+		records: Vec::with_capacity(records.len()),
+	};
+
+	// -- Validate
+	let mut valid: Vec<(usize, QuotePatch)> = Vec::new();
+	for record in records {
+		match record.data.and_then(validate) {
+			Ok(data) => valid.push((record.line, data)),
+			Err(reason) => report.push(record.line, ImportOutcome::Rejected, Some(reason), None),
+		}
+	}
+
+	// -- Skip the duplicates (already in the db, or earlier in the file)
+	let mut seen = existing_keys(db, &valid).await?;
+	let mut to_create: Vec<(usize, QuotePatch)> = Vec::new();
+	for (line, data) in valid {
+		if seen.insert(duplicate_key(&data)) {
+			to_create.push((line, data));
+		} else {
+			report.push(line, ImportOutcome::Skipped, Some("duplicate quote".to_string()), None);
+		}
+	}
+
+	// -- Create in batches
+	for batch in to_create.chunks(batch_size.max(1)) {
+		if dry_run {
+			for (line, _) in batch {
+				report.push(*line, ImportOutcome::Created, None, None);
+			}
+			continue;
+		}
+
+		let data = batch.iter().map(|(_, data)| data.clone()).collect();
+		let quotes = match QuoteMac::create_many(db, utx, data).await {
+			Ok(quotes) => quotes,
+			Err(source) => {
+				report.records.sort_by_key(|r| r.line);
+				return Err(Error::Interrupted { report: Box::new(report), source });
+			}
+		};
+		for ((line, _), quote) in batch.iter().zip(quotes) {
+			report.push(*line, ImportOutcome::Created, None, Some(quote.id));
+		}
+	}
+
+	report.records.sort_by_key(|r| r.line);
+	Ok(report)
+}
+
+impl ImportReport {
+	fn push(&mut self, line: usize, outcome: ImportOutcome, reason: Option<String>, id: Option<i64>) {
+		match outcome {
+			ImportOutcome::Created => self.created += 1,
+			ImportOutcome::Skipped => self.skipped += 1,
+			ImportOutcome::Rejected => self.rejected += 1,
+		}
+		self.records.push(ImportLineReport { line, outcome, reason, id });
+	}
+}
+
+// region: Utils
+/// Trim the values, require a quote, and check the lang.
+fn validate(mut data: QuotePatch) -> Result<QuotePatch, String> {
+	let non_empty = |val: Option<String>| val.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+	let non_empty_nullable = |val: Option<Nullable<String>>| match val {
+		Some(Nullable::Value(val)) => non_empty(Some(val)).map(Nullable::Value),
+		other => other,
+	};
+
+	data.quote = Some(non_empty(data.quote).ok_or("missing quote")?);
+	data.author = non_empty(data.author);
+	data.source = non_empty_nullable(data.source);
+	data.locator = non_empty_nullable(data.locator);
+	data.tags = data.tags.map(|Tags(tags)| Tags(tags.into_iter().filter_map(|t| non_empty(Some(t))).collect()));
+	data.lang = match non_empty(data.lang) {
+		Some(lang) => Some(normalize_lang_tag(&lang).map_err(|ex| ex.to_string())?),
+		None => None,
+	};
+
+	Ok(data)
+}
+
+/// Quotes are duplicates when they have the same text and author, ignoring case.
+fn duplicate_key(data: &QuotePatch) -> (String, String) {
+	let quote = data.quote.as_deref().unwrap_or_default().to_lowercase();
+	let author = data.author.as_deref().unwrap_or(DEFAULT_AUTHOR).to_lowercase();
+	(quote, author)
+}
+
+async fn existing_keys(db: &Db, records: &[(usize, QuotePatch)]) -> Result<HashSet<(String, String)>, Error> {
+	let quotes: Vec<String> = records.iter().map(|(_, data)| duplicate_key(data).0).collect();
+	let keys: Vec<(String, String)> =
+		sqlx::query_as("SELECT lower(quote), lower(author) FROM quote WHERE lower(quote) = ANY($1)")
+			.bind(quotes)
+			.fetch_all(db)
+			.await
+			.map_err(model::Error2::from)?;
+
+	Ok(keys.into_iter().collect())
+}
+// endregion: Utils
+
+// region: Error
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Unknown import format '{0}' (expected csv, json, ndjson, fortune or kindle)")]
+	UnknownFormat(String),
+
+	#[error("Invalid column mapping '{0}' (expected field:column)")]
+	InvalidMapping(String),
+
+	#[error("Unknown quote field '{0}' in the column mapping")]
+	UnknownField(String),
+
+	#[error("Missing column '{0}' in the CSV header")]
+	MissingColumn(String),
+
+	#[error("Invalid import file - {0}")]
+	InvalidFile(String),
+
+	#[error(transparent)]
+	IO(#[from] std::io::Error),
+
+	#[error(transparent)]
+	Model(#[from] model::Error2),
+
+	/// A batch failed, the report lists what the batches before it created (the later lines are not imported).
+	#[error("Import interrupted after {} created quotes - {source}", .report.created)]
+	Interrupted { report: Box<ImportReport>, source: model::Error2 },
+}
+// endregion: Error
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/import.rs"]
+mod tests;
+// endregion: Test
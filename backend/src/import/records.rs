@@ -0,0 +1,91 @@
+// Parsing of the import files into QuotePatch records (one per line / array element).
+
+use super::{Error, ImportRecord};
+use crate::model::{Nullable, QuotePatch, Tags};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Fields of QuotePatch that can be mapped from a CSV column.
+const CSV_FIELDS: &[&str] = &["quote", "author", "source", "locator", "tags", "lang"];
+
+/// Parse a CSV file with a header row. `mapping` maps a quote field to a CSV column name
+/// (fields not mapped are read from the column of the same name, if any).
+/// Tags are separated by `,` within their column.
+pub fn parse_csv(content: &str, mapping: &HashMap<String, String>) -> Result<Vec<ImportRecord>, Error> {
+	if let Some(field) = mapping.keys().find(|f| !CSV_FIELDS.contains(&f.as_str())) {
+		return Err(Error::UnknownField(field.to_string()));
+	}
+
+	let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(content.as_bytes());
+	let headers = reader.headers().map_err(|ex| Error::InvalidFile(ex.to_string()))?.clone();
+
+	// index of the csv column of each field
+	let mut columns: HashMap<&str, usize> = HashMap::new();
+	for field in CSV_FIELDS {
+		let column = mapping.get(*field).map(String::as_str).unwrap_or(field);
+		match headers.iter().position(|h| h.trim().eq_ignore_ascii_case(column)) {
+			Some(idx) => {
+				columns.insert(field, idx);
+			}
+			None if mapping.contains_key(*field) => return Err(Error::MissingColumn(column.to_string())),
+			None => (),
+		}
+	}
+	if !columns.contains_key("quote") {
+		return Err(Error::MissingColumn("quote".to_string()));
+	}
+
+	let mut records = Vec::new();
+	for row in reader.records() {
+		let row = row.map_err(|ex| Error::InvalidFile(ex.to_string()))?;
+		let line = row.position().map_or(0, |p| p.line() as usize);
+		let value = |field: &str| columns.get(field).and_then(|idx| row.get(*idx)).map(str::to_string);
+
+		let data = QuotePatch {
+			quote: value("quote"),
+			author: value("author"),
+			source: value("source").map(Nullable::Value),
+			locator: value("locator").map(Nullable::Value),
+			tags: value("tags").map(|tags| Tags(tags.split(',').map(str::to_string).collect())),
+			lang: value("lang"),
+		};
+		records.push(ImportRecord { line, data: Ok(data) });
+	}
+
+	Ok(records)
+}
+
+/// Parse a JSON array of quote objects. The line of a record is the one where its object starts.
+pub fn parse_json(content: &str) -> Result<Vec<ImportRecord>, Error> {
+	// raw values, borrowed from the content, so their offset gives their line
+	let items: Vec<&RawValue> = serde_json::from_str(content).map_err(|ex| Error::InvalidFile(ex.to_string()))?;
+
+	let records = items
+		.into_iter()
+		.map(|item| {
+			let offset = item.get().as_ptr() as usize - content.as_ptr() as usize;
+			ImportRecord {
+				line: content[..offset].matches('\n').count() + 1,
+				data: serde_json::from_str(item.get()).map_err(|ex| ex.to_string()),
+			}
+		})
+		.collect();
+
+	Ok(records)
+}
+
+/// Parse newline delimited JSON, one quote object per line (blank lines are ignored).
+pub fn parse_ndjson(content: &str) -> Result<Vec<ImportRecord>, Error> {
+	let records = content
+		.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.map(|(idx, line)| ImportRecord {
+			line: idx + 1,
+			data: serde_json::from_str(line).map_err(|ex| ex.to_string()),
+		})
+		.collect();
+
+	Ok(records)
+}
@@ -0,0 +1,264 @@
+// Import of an offline Wikiquote dump (MediaWiki XML export, decompressed).
+//
+// The dump is stream-parsed page by page. On the pages of people, the `* quote` lines of the "Sourced" (or "Quotes")
+// and "Disputed" sections become quotes of the person, with the `** ...` line below as source. "Misattributed" and
+// the other sections are skipped. After each batch, the last page imported is saved in `import_checkpoint`,
+// so a new run on the same dump resumes after it.
+
+use super::{import_records, Error, ImportOutcome, ImportRecord};
+use crate::model::{self, Db, Nullable, QuotePatch, Tags};
+use crate::security::UserCtx;
+use lazy_regex::regex;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::BufReader;
+
+/// Tag of all the quotes from wikiquote (with `sourced` or `disputed`).
+const WIKIQUOTE_TAG: &str = "wikiquote";
+
+#[derive(Debug, Clone)]
+pub struct WikiquoteOptions {
+	pub dry_run: bool,
+	/// Quotes per transaction, the checkpoint being saved after each
+	pub batch_size: usize,
+	/// Ignore the checkpoint, and start over from the first page
+	pub restart: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WikiquoteReport {
+	pub dry_run: bool,
+	pub resumed_after: Option<i64>, // page id
+	pub pages: usize,               // person pages read (after the checkpoint)
+	pub created: usize,
+	pub skipped: usize,
+	pub rejected: usize,
+	pub last_page_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+	Sourced,
+	Disputed,
+	Skipped,
+}
+
+#[derive(Debug, Default)]
+struct Page {
+	id: Option<i64>,
+	ns: String,
+	title: String,
+	text: String,
+	redirect: bool,
+}
+
+pub async fn import_wikiquote(db: &Db, utx: &UserCtx, dump: &Path, options: &WikiquoteOptions) -> Result<WikiquoteReport, Error> {
+	let checkpoint_name = dump.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+	let resume_after = match options.restart {
+		true => None,
+		false => get_checkpoint(db, &checkpoint_name).await?,
+	};
+	let mut report = WikiquoteReport {
+		dry_run: options.dry_run,
+		resumed_after: resume_after,
+		..Default::default()
+	};
+
+	let mut reader = Reader::from_reader(BufReader::new(File::open(dump).await?));
+	let mut buf = Vec::new();
+	let mut elements: Vec<String> = Vec::new();
+	let mut page = Page::default();
+	let mut batch: Vec<ImportRecord> = Vec::new();
+
+	loop {
+		let event = reader
+			.read_event_into_async(&mut buf)
+			.await
+			.map_err(|ex| Error::InvalidFile(format!("{} at byte {}", ex, reader.buffer_position())))?;
+		let page_end = match event {
+			Event::Start(e) => {
+				elements.push(String::from_utf8_lossy(e.local_name().as_ref()).to_string());
+				false
+			}
+			Event::Empty(e) => {
+				page.redirect |= e.local_name().as_ref() == b"redirect";
+				false
+			}
+			Event::Text(e) => {
+				let text = e.unescape().map_err(|ex| Error::InvalidFile(ex.to_string()))?;
+				page.push_text(&elements, &text);
+				false
+			}
+			Event::CData(e) => {
+				page.push_text(&elements, &String::from_utf8_lossy(&e));
+				false
+			}
+			Event::End(_) => elements.pop().as_deref() == Some("page"),
+			Event::Eof => break,
+			_ => false,
+		};
+		buf.clear();
+		if !page_end {
+			continue;
+		}
+
+		let page = std::mem::take(&mut page);
+		// pages up to the checkpoint were imported by a previous run
+		if let Some(id) = page.id.filter(|id| resume_after.is_none_or(|after| *id > after)) {
+			if page.ns == "0" && !page.redirect && is_person_page(&page.text) {
+				report.pages += 1;
+				batch.extend(page_quotes(&page.title, &page.text).into_iter().map(|data| ImportRecord {
+					line: id as usize, // the page id, for the report
+					data: Ok(data),
+				}));
+			}
+			report.last_page_id = Some(id);
+
+			if batch.len() >= options.batch_size {
+				import_batch(db, utx, &checkpoint_name, std::mem::take(&mut batch), options, &mut report).await?;
+			}
+		}
+	}
+	import_batch(db, utx, &checkpoint_name, batch, options, &mut report).await?;
+
+	Ok(report)
+}
+
+/// Import the quotes of whole pages, then move the checkpoint to the last page read.
+/// If interrupted in between, the next run reads those pages again, and skips their quotes as duplicates.
+async fn import_batch(
+	db: &Db,
+	utx: &UserCtx,
+	checkpoint_name: &str,
+	records: Vec<ImportRecord>,
+	options: &WikiquoteOptions,
+	report: &mut WikiquoteReport,
+) -> Result<(), Error> {
+	let batch = import_records(db, utx, records, options.dry_run, options.batch_size).await?;
+	report.created += batch.created;
+	report.skipped += batch.skipped;
+	report.rejected += batch.rejected;
+	for rejected in batch.records.iter().filter(|r| r.outcome == ImportOutcome::Rejected) {
+		println!("WARNING - wikiquote - page {} quote rejected: {:?}", rejected.line, rejected.reason);
+	}
+
+	if let (false, Some(page_id)) = (options.dry_run, report.last_page_id) {
+		save_checkpoint(db, checkpoint_name, page_id).await?;
+	}
+
+	Ok(())
+}
+
+impl Page {
+	fn push_text(&mut self, elements: &[String], text: &str) {
+		let parent = elements.len().checked_sub(2).map(|idx| elements[idx].as_str());
+		match (parent, elements.last().map(String::as_str)) {
+			(Some("page"), Some("title")) => self.title.push_str(text),
+			(Some("page"), Some("ns")) => self.ns.push_str(text.trim()),
+			(Some("page"), Some("id")) => self.id = text.trim().parse().ok(),
+			(Some("revision"), Some("text")) => self.text.push_str(text),
+			_ => (),
+		}
+	}
+}
+
+// region: Wikitext
+/// People pages are in a `births`/`deaths` category, or have a "Sourced" section
+/// (other pages, e.g., films or themes, quote several people).
+fn is_person_page(text: &str) -> bool {
+	regex!(r"(?i)\[\[Category:[^\]]*(births|deaths)\]\]").is_match(text) || regex!(r"(?im)^==\s*Sourced\s*==\s*$").is_match(text)
+}
+
+/// The quotes of a person page, in page order.
+fn page_quotes(author: &str, text: &str) -> Vec<QuotePatch> {
+	let mut quotes: Vec<QuotePatch> = Vec::new();
+	let mut section = Section::Skipped;
+	let mut has_source = false; // whether the last quote got its `**` line
+
+	for line in text.lines() {
+		if let Some((_, level, heading)) = lazy_regex::regex_captures!(r"^(=+)\s*(.*?)\s*=+\s*$", line) {
+			let heading = heading.to_lowercase();
+			section = match heading.as_str() {
+				h if h.starts_with("misattributed") => Section::Skipped,
+				h if h.starts_with("disputed") => Section::Disputed,
+				h if h.starts_with("sourced") || (h.starts_with("quotes") && !h.starts_with("quotes about")) => Section::Sourced,
+				// sub sections (e.g., years) stay in their section
+				_ if level.len() > 2 => section,
+				_ => Section::Skipped,
+			};
+			continue;
+		}
+		if section == Section::Skipped {
+			continue;
+		}
+
+		if let Some(quote) = line.strip_prefix('*').filter(|l| !l.starts_with(['*', ':'])) {
+			let quote = clean_wikitext(quote);
+			if !quote.is_empty() {
+				let tag = if section == Section::Disputed { "disputed" } else { "sourced" };
+				quotes.push(QuotePatch {
+					quote: Some(quote),
+					author: Some(author.trim().to_string()),
+					tags: Some(Tags(vec![WIKIQUOTE_TAG.to_string(), tag.to_string()])),
+					..Default::default()
+				});
+				has_source = false;
+			}
+		} else if let (Some(source), Some(quote)) = (line.strip_prefix("**"), quotes.last_mut()) {
+			let source = clean_wikitext(source);
+			if !has_source && !source.is_empty() {
+				quote.source = Some(Nullable::Value(source));
+				has_source = true;
+			}
+		}
+	}
+
+	quotes
+}
+
+/// Remove the wiki markup of a line: references, templates, links, formatting and html tags.
+fn clean_wikitext(text: &str) -> String {
+	let mut text = regex!(r"(?is)<ref[^>/]*/>|<ref[^>]*>.*?</ref>|<!--.*?-->").replace_all(text, "").to_string();
+	// templates can be nested, remove from the innermost
+	while regex!(r"\{\{[^{}]*\}\}").is_match(&text) {
+		text = regex!(r"\{\{[^{}]*\}\}").replace_all(&text, "").to_string();
+	}
+	let text = regex!(r"\[\[(?:File|Image|Category):[^\]]*\]\]").replace_all(&text, "");
+	let text = regex!(r"\[\[(?:[^\]|]*\|)?([^\]]*)\]\]").replace_all(&text, "$1");
+	let text = regex!(r"\[https?://[^\s\]]+\s*([^\]]*)\]").replace_all(&text, "$1");
+	let text = regex!(r"(?i)<br\s*/?>").replace_all(&text, " ");
+	let text = regex!(r"<[^>]+>").replace_all(&text, "");
+	let text = text.replace("'''", "").replace("''", "").replace("&nbsp;", " ");
+
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+// endregion: Wikitext
+
+// region: Checkpoint
+async fn get_checkpoint(db: &Db, name: &str) -> Result<Option<i64>, Error> {
+	let checkpoint: Option<(i64,)> = sqlx::query_as("SELECT page_id FROM import_checkpoint WHERE name = $1")
+		.bind(name)
+		.fetch_optional(db)
+		.await
+		.map_err(model::Error2::from)?;
+
+	Ok(checkpoint.map(|(page_id,)| page_id))
+}
+
+async fn save_checkpoint(db: &Db, name: &str, page_id: i64) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO import_checkpoint (name, page_id) VALUES ($1, $2) \
+		ON CONFLICT (name) DO UPDATE SET page_id = EXCLUDED.page_id, mtime = now()",
+	)
+	.bind(name)
+	.bind(page_id)
+	.execute(db)
+	.await
+	.map_err(model::Error2::from)?;
+
+	Ok(())
+}
+// endregion: Checkpoint
@@ -0,0 +1,76 @@
+// Emails of the accounts (password reset, email verification), rendered with the askama templates of `templates/email`,
+// and sent by a `Mailer`: SMTP in production, or else the outbox folder of .eml files (development and tests).
+
+use crate::config::MailConfig;
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::Message;
+use std::sync::Arc;
+
+mod outbox;
+mod smtp;
+mod templates;
+
+pub use outbox::OutboxMailer;
+pub use smtp::SmtpMailer;
+pub use templates::{email_verification, password_reset};
+
+/// An email, with its text and html versions.
+#[derive(Debug, Clone)]
+pub struct Email {
+	pub to: String,
+	pub subject: String,
+	pub text: String,
+	pub html: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+	async fn send(&self, email: Email) -> Result<(), Error>;
+}
+
+/// The mailer of the configuration, SMTP when a server is configured, or else the outbox folder.
+pub fn mailer(config: &MailConfig) -> Result<Arc<dyn Mailer>, Error> {
+	match &config.smtp_url {
+		Some(url) => Ok(Arc::new(SmtpMailer::new(url, &config.from)?)),
+		None => Ok(Arc::new(OutboxMailer::new(config.outbox.clone(), &config.from)?)),
+	}
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, Error> {
+	address.trim().parse().map_err(|ex| Error::InvalidAddress(format!("'{}' - {}", address, ex)))
+}
+
+/// The MIME message of the email (multipart/alternative).
+fn message(from: &Mailbox, email: Email) -> Result<Message, Error> {
+	Message::builder()
+		.from(from.clone())
+		.to(mailbox(&email.to)?)
+		.subject(email.subject)
+		.multipart(MultiPart::alternative_plain_html(email.text, email.html))
+		.map_err(|ex| Error::Message(ex.to_string()))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Invalid email address {0}")]
+	InvalidAddress(String),
+
+	#[error("Fail to build the email - {0}")]
+	Message(String),
+
+	#[error("Fail to render the email - {0}")]
+	Template(#[from] askama::Error),
+
+	#[error("Fail to send the email - {0}")]
+	Smtp(String),
+
+	#[error("Fail to write the email to the outbox - {0}")]
+	Outbox(#[from] std::io::Error),
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/mail.rs"]
+mod tests;
+// endregion: Test
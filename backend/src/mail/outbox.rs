@@ -0,0 +1,51 @@
+use super::{mailbox, message, Email, Error, Mailer};
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Writes the emails to the folder, one `[unix millis]-[random].eml` file each (openable by the mail clients),
+/// instead of sending them.
+pub struct OutboxMailer {
+	from: Mailbox,
+	folder: PathBuf,
+}
+
+impl OutboxMailer {
+	pub fn new(folder: PathBuf, from: &str) -> Result<OutboxMailer, Error> {
+		Ok(OutboxMailer {
+			from: mailbox(from)?,
+			folder,
+		})
+	}
+
+	/// The .eml files of the folder, oldest first.
+	pub fn emails(&self) -> Result<Vec<PathBuf>, Error> {
+		let mut emails: Vec<PathBuf> = match std::fs::read_dir(&self.folder) {
+			Ok(entries) => entries
+				.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+				.filter(|path| path.extension().is_some_and(|ext| ext == "eml"))
+				.collect(),
+			Err(ex) if ex.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+			Err(ex) => return Err(ex.into()),
+		};
+		emails.sort();
+		Ok(emails)
+	}
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+	async fn send(&self, email: Email) -> Result<(), Error> {
+		let message = message(&self.from, email)?;
+		let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+		let path = self.folder.join(format!("{:013}-{:08x}.eml", millis, OsRng.next_u32()));
+
+		tokio::fs::create_dir_all(&self.folder).await?;
+		tokio::fs::write(&path, message.formatted()).await?;
+		println!("Email to the outbox {}", path.display());
+		Ok(())
+	}
+}
@@ -0,0 +1,56 @@
+use super::{Email, Error};
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "email/password_reset.txt")]
+struct PasswordResetText<'a> {
+	username: &'a str,
+	link: &'a str,
+	valid_minutes: i64,
+}
+
+#[derive(Template)]
+#[template(path = "email/password_reset.html")]
+struct PasswordResetHtml<'a> {
+	username: &'a str,
+	link: &'a str,
+	valid_minutes: i64,
+}
+
+#[derive(Template)]
+#[template(path = "email/email_verification.txt")]
+struct EmailVerificationText<'a> {
+	username: &'a str,
+	link: &'a str,
+	valid_hours: i64,
+}
+
+#[derive(Template)]
+#[template(path = "email/email_verification.html")]
+struct EmailVerificationHtml<'a> {
+	username: &'a str,
+	link: &'a str,
+	valid_hours: i64,
+}
+
+/// The email with the link to reset the password, valid `valid_secs`.
+pub fn password_reset(to: &str, username: &str, link: &str, valid_secs: i64) -> Result<Email, Error> {
+	let valid_minutes = valid_secs / 60;
+	Ok(Email {
+		to: to.to_string(),
+		subject: "Reset your password".to_string(),
+		text: PasswordResetText { username, link, valid_minutes }.render()?,
+		html: PasswordResetHtml { username, link, valid_minutes }.render()?,
+	})
+}
+
+/// The email with the link to verify the email address, valid `valid_secs`.
+pub fn email_verification(to: &str, username: &str, link: &str, valid_secs: i64) -> Result<Email, Error> {
+	let valid_hours = valid_secs / 3600;
+	Ok(Email {
+		to: to.to_string(),
+		subject: "Verify your email address".to_string(),
+		text: EmailVerificationText { username, link, valid_hours }.render()?,
+		html: EmailVerificationHtml { username, link, valid_hours }.render()?,
+	})
+}
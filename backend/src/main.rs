@@ -26,6 +26,11 @@ use web::start_web;
 // mod ctx;
 // mod error;
 
+mod cli;
+mod config;
+mod export;
+mod import;
+mod mail;
 mod model;
 mod security;
 mod web;
@@ -37,6 +42,19 @@ const DEFAULT_WEB_PORT: u16 = 8080;
 async fn main() {
     // Compute the web folder
     let mut args: Vec<String> = env::args().collect();
+
+    // Fail fast on an invalid configuration (e.g., QUOTE_MODERATION)
+    config::config();
+
+    // Command line tools (e.g., `import quotes.csv --user 123`)
+    if args.get(1).is_some_and(|cmd| cli::COMMANDS.contains(&cmd.as_str())) {
+        if let Err(ex) = cli::run(&args[1..]).await {
+            eprintln!("ERROR - {}", ex);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let web_folder = args.pop().unwrap_or_else(|| DEFAULT_WEB_FOLDER.to_string());
     let web_port = DEFAULT_WEB_PORT;
 
@@ -46,6 +64,9 @@ async fn main() {
     let db = init_db().await.expect("Cannot init db");
     let db = Arc::new(db);
 
+    // Delete the expired login sessions in the background
+    tokio::spawn(security::cleanup_sessions(db.clone()));
+
     // Start the server
     match start_web(&web_folder, web_port, db).await {
         Ok(_) => println!("Server ended"),
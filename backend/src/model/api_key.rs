@@ -0,0 +1,144 @@
+use super::db::Db;
+use super::handle_fetch_one_result;
+use crate::model;
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// region: ApiKey Types
+/// An API key, as listed (the key itself is only given at its creation, see security::create_api_key).
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+	pub id: i64,
+	pub user_id: i64,
+	pub name: String,
+	pub prefix: String,
+	pub scopes: Vec<ApiScope>,
+	pub ctime: i64,             // unix seconds
+	pub expires: Option<i64>,   // unix seconds, None when it does not expire
+	pub last_used: Option<i64>, // unix seconds
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKeyForCreate {
+	pub name: String,
+	pub scopes: Vec<ApiScope>,
+	pub expires: Option<i64>, // unix seconds
+}
+
+/// What an API key gives access to. `admin` allows everything, `quotes:write` allows `quotes:read` too.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "api_scope_enum")]
+pub enum ApiScope {
+	#[sqlx(rename = "quotes:read")]
+	#[serde(rename = "quotes:read")]
+	QuotesRead,
+	#[sqlx(rename = "quotes:write")]
+	#[serde(rename = "quotes:write")]
+	QuotesWrite,
+	#[sqlx(rename = "admin")]
+	#[serde(rename = "admin")]
+	Admin,
+}
+
+impl ApiScope {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			ApiScope::QuotesRead => "quotes:read",
+			ApiScope::QuotesWrite => "quotes:write",
+			ApiScope::Admin => "admin",
+		}
+	}
+
+	/// Whether a key with this scope can call a route requiring `required`.
+	pub fn allows(self, required: ApiScope) -> bool {
+		match self {
+			ApiScope::Admin => true,
+			ApiScope::QuotesWrite => matches!(required, ApiScope::QuotesRead | ApiScope::QuotesWrite),
+			ApiScope::QuotesRead => required == ApiScope::QuotesRead,
+		}
+	}
+}
+
+impl PgHasArrayType for ApiScope {
+	fn array_type_info() -> PgTypeInfo {
+		PgTypeInfo::with_name("_api_scope_enum")
+	}
+}
+// endregion: ApiKey Types
+
+// region: ApiKeyModelAccessController
+pub struct ApiKeyMac;
+
+impl ApiKeyMac {
+	const TABLE: &'static str = "api_key";
+	const COLUMNS: &'static str = "id, user_id, name, prefix, scopes, extract(epoch FROM ctime)::bigint AS ctime, \
+		extract(epoch FROM expires)::bigint AS expires, extract(epoch FROM last_used)::bigint AS last_used";
+
+	/// Store the key of the user by its hash (see security::create_api_key).
+	pub async fn create(db: &Db, utx: &UserCtx, data: ApiKeyForCreate, prefix: &str, key_hash: &str) -> Result<ApiKey, model::Error2> {
+		let name = data.name.trim();
+		if name.is_empty() {
+			return Err(model::Error2::InvalidData("an API key needs a name".to_string()));
+		}
+		if data.scopes.is_empty() {
+			return Err(model::Error2::InvalidData("an API key needs at least one scope".to_string()));
+		}
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default();
+		if data.expires.is_some_and(|expires| expires <= now) {
+			return Err(model::Error2::InvalidData("the expiration of an API key must be in the future".to_string()));
+		}
+
+		let sql = format!(
+			"INSERT INTO api_key (user_id, name, prefix, key_hash, scopes, expires) VALUES ($1, $2, $3, $4, $5, to_timestamp($6)) \
+			RETURNING {}",
+			Self::COLUMNS
+		);
+		let result = sqlx::query_as::<_, ApiKey>(&sql)
+			.bind(utx.user_id)
+			.bind(name)
+			.bind(prefix)
+			.bind(key_hash)
+			.bind(&data.scopes)
+			.bind(data.expires.map(|e| e as f64))
+			.fetch_one(db)
+			.await?;
+
+		Ok(result)
+	}
+
+	/// The unexpired key of the hash, marked as used now.
+	pub async fn use_key(db: &Db, key_hash: &str) -> Result<Option<ApiKey>, model::Error2> {
+		let sql = format!(
+			"UPDATE api_key SET last_used = now() WHERE key_hash = $1 AND (expires IS NULL OR expires > now()) RETURNING {}",
+			Self::COLUMNS
+		);
+		let api_key = sqlx::query_as::<_, ApiKey>(&sql).bind(key_hash).fetch_optional(db).await?;
+
+		Ok(api_key)
+	}
+
+	/// The keys of the user, the expired ones included.
+	pub async fn list(db: &Db, utx: &UserCtx) -> Result<Vec<ApiKey>, model::Error2> {
+		let sql = format!("SELECT {} FROM api_key WHERE user_id = $1 ORDER BY id", Self::COLUMNS);
+		let api_keys = sqlx::query_as::<_, ApiKey>(&sql).bind(utx.user_id).fetch_all(db).await?;
+
+		Ok(api_keys)
+	}
+
+	/// Revoke a key of the user.
+	pub async fn delete(db: &Db, utx: &UserCtx, id: i64) -> Result<ApiKey, model::Error2> {
+		let sql = format!("DELETE FROM api_key WHERE id = $1 AND user_id = $2 RETURNING {}", Self::COLUMNS);
+		let result = sqlx::query_as::<_, ApiKey>(&sql).bind(id).bind(utx.user_id).fetch_one(db).await;
+
+		handle_fetch_one_result(result, Self::TABLE, id)
+	}
+}
+// endregion: ApiKeyModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_api_key.rs"]
+mod tests;
+// endregion: Test
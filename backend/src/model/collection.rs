@@ -0,0 +1,250 @@
+use super::db::Db;
+use super::patch::{deserialize_nullable, Nullable};
+use super::{handle_fetch_one_result, ListOptions, Quote, QuoteMac};
+use crate::model;
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use sqlb::HasFields;
+
+// region: Collection Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+	pub id: i64,
+	pub cid: i64, // owner id
+	pub title: String,
+	pub description: Option<String>,
+	pub visibility: CollectionVisibility,
+	pub quote_ids: Vec<i64>, // in collection order
+}
+
+#[derive(sqlb::Fields, Default, Debug, Clone, Deserialize)]
+pub struct CollectionPatch {
+	pub title: Option<String>,
+	#[serde(default, deserialize_with = "deserialize_nullable")]
+	pub description: Option<Nullable<String>>,
+	pub visibility: Option<CollectionVisibility>,
+}
+
+/// `private` collections are only seen by their owner, `unlisted` ones by anyone with the id,
+/// and `public` ones are also listed to everybody.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "collection_visibility_enum")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionVisibility {
+	Private,
+	Unlisted,
+	Public,
+}
+sqlb::bindable!(CollectionVisibility);
+// endregion: Collection Types
+
+// region: CollectionModelAccessController
+pub struct CollectionMac;
+
+impl CollectionMac {
+	const TABLE: &'static str = "collection";
+	const SELECT: &'static str = "SELECT id, cid, title, description, visibility, \
+		ARRAY(SELECT quote_id FROM collection_quote WHERE collection_id = collection.id ORDER BY position) AS quote_ids \
+		FROM collection";
+}
+
+impl CollectionMac {
+	pub async fn create(db: &Db, utx: &UserCtx, data: CollectionPatch) -> Result<Collection, model::Error2> {
+		if data.title.is_none() {
+			return Err(model::Error2::InvalidData("collection title is required".to_string()));
+		}
+
+		let mut fields = data.fields();
+		fields.push(("cid", utx.user_id).into());
+		let sb = sqlb::insert().table(Self::TABLE).data(fields).returning(&["id"]);
+		let (id,): (i64,) = sb.fetch_one(db).await?;
+
+		Self::get(db, utx, id).await
+	}
+
+	/// Get a collection owned by the user, or shared with everybody (unlisted or public).
+	pub async fn get(db: &Db, utx: &UserCtx, id: i64) -> Result<Collection, model::Error2> {
+		let sql = format!("{} WHERE id = $1 AND (cid = $2 OR visibility <> 'private')", Self::SELECT);
+		let result = sqlx::query_as::<_, Collection>(&sql).bind(id).bind(utx.user_id).fetch_one(db).await;
+
+		handle_fetch_one_result(result, Self::TABLE, id)
+	}
+
+	/// List the user's own collections and the public ones.
+	pub async fn list(db: &Db, utx: &UserCtx, list_options: &ListOptions) -> Result<Vec<Collection>, model::Error2> {
+		let sql = format!(
+			"{} WHERE cid = $1 OR visibility = 'public' ORDER BY id DESC LIMIT $2 OFFSET $3",
+			Self::SELECT
+		);
+		let query = sqlx::query_as::<_, Collection>(&sql)
+			.bind(utx.user_id)
+			.bind(list_options.limit())
+			.bind(list_options.offset());
+
+		Ok(query.fetch_all(db).await?)
+	}
+
+	pub async fn update(db: &Db, utx: &UserCtx, id: i64, data: CollectionPatch) -> Result<Collection, model::Error2> {
+		let fields = data.fields();
+		if !fields.is_empty() {
+			let sb = sqlb::update()
+				.table(Self::TABLE)
+				.data(fields)
+				.and_where_eq("id", id)
+				.and_where_eq("cid", utx.user_id)
+				.returning(&["id"]);
+			let result: Result<(i64,), _> = sb.fetch_one(db).await;
+			handle_fetch_one_result(result, Self::TABLE, id)?;
+		}
+
+		Self::get_owned(db, utx, id).await
+	}
+
+	pub async fn delete(db: &Db, utx: &UserCtx, id: i64) -> Result<Collection, model::Error2> {
+		let collection = Self::get_owned(db, utx, id).await?;
+
+		let sb = sqlb::delete().table(Self::TABLE).and_where_eq("id", id).and_where_eq("cid", utx.user_id);
+		sb.exec(db).await?;
+
+		Ok(collection)
+	}
+
+	/// The quotes of the collection, in the collection order.
+	pub async fn list_quotes(db: &Db, utx: &UserCtx, id: i64, list_options: &ListOptions) -> Result<Vec<Quote>, model::Error2> {
+		// visibility check
+		Self::get(db, utx, id).await?;
+
+		let columns = QuoteMac::COLUMNS.iter().map(|c| format!("q.{}", c)).collect::<Vec<_>>().join(", ");
+		let sql = format!(
+			"SELECT {} FROM collection_quote cq JOIN quote q ON q.id = cq.quote_id \
+			WHERE cq.collection_id = $1 AND (q.status = 'approved' OR q.cid = $4 OR $5) \
+			ORDER BY cq.position LIMIT $2 OFFSET $3",
+			columns
+		);
+		let query = sqlx::query_as::<_, Quote>(&sql)
+			.bind(id)
+			.bind(list_options.limit())
+			.bind(list_options.offset())
+			.bind(utx.user_id)
+			.bind(utx.is_moderator());
+
+		Ok(query.fetch_all(db).await?)
+	}
+
+	/// Add a quote at `position` (0 based), or at the end when None. Adding a quote already in the collection is a no-op.
+	pub async fn add_quote(
+		db: &Db,
+		utx: &UserCtx,
+		id: i64,
+		quote_id: i64,
+		position: Option<i32>,
+	) -> Result<Collection, model::Error2> {
+		Self::get_owned(db, utx, id).await?;
+		QuoteMac::get(db, utx, quote_id).await?;
+
+		let mut tx = db.begin().await?;
+		let quote_ids = Self::lock_quote_ids(&mut tx, utx, id).await?;
+		if quote_ids.contains(&quote_id) {
+			tx.rollback().await?;
+			return Self::get(db, utx, id).await;
+		}
+		let len = quote_ids.len() as i32;
+		let position = position.unwrap_or(len).clamp(0, len);
+
+		sqlx::query("UPDATE collection_quote SET position = position + 1 WHERE collection_id = $1 AND position >= $2")
+			.bind(id)
+			.bind(position)
+			.execute(&mut tx)
+			.await?;
+		sqlx::query("INSERT INTO collection_quote (collection_id, quote_id, position) VALUES ($1, $2, $3)")
+			.bind(id)
+			.bind(quote_id)
+			.bind(position)
+			.execute(&mut tx)
+			.await?;
+		tx.commit().await?;
+
+		Self::get(db, utx, id).await
+	}
+
+	pub async fn remove_quote(db: &Db, utx: &UserCtx, id: i64, quote_id: i64) -> Result<Collection, model::Error2> {
+		let mut tx = db.begin().await?;
+		Self::lock_quote_ids(&mut tx, utx, id).await?;
+		let removed: Option<(i32,)> =
+			sqlx::query_as("DELETE FROM collection_quote WHERE collection_id = $1 AND quote_id = $2 RETURNING position")
+				.bind(id)
+				.bind(quote_id)
+				.fetch_optional(&mut tx)
+				.await?;
+		let (position,) = removed.ok_or_else(|| model::Error2::EntityNotFound("collection_quote", quote_id.to_string()))?;
+		sqlx::query("UPDATE collection_quote SET position = position - 1 WHERE collection_id = $1 AND position > $2")
+			.bind(id)
+			.bind(position)
+			.execute(&mut tx)
+			.await?;
+		tx.commit().await?;
+
+		Self::get(db, utx, id).await
+	}
+
+	/// Set the order of the quotes. `quote_ids` must hold exactly the quotes of the collection.
+	pub async fn reorder(db: &Db, utx: &UserCtx, id: i64, quote_ids: &[i64]) -> Result<Collection, model::Error2> {
+		let mut tx = db.begin().await?;
+		let mut current = Self::lock_quote_ids(&mut tx, utx, id).await?;
+		let mut requested = quote_ids.to_vec();
+		current.sort_unstable();
+		requested.sort_unstable();
+		if current != requested {
+			return Err(model::Error2::InvalidData(
+				"reorder must list each quote of the collection exactly once".to_string(),
+			));
+		}
+
+		for (position, quote_id) in quote_ids.iter().enumerate() {
+			sqlx::query("UPDATE collection_quote SET position = $3 WHERE collection_id = $1 AND quote_id = $2")
+				.bind(id)
+				.bind(quote_id)
+				.bind(position as i32)
+				.execute(&mut tx)
+				.await?;
+		}
+		tx.commit().await?;
+
+		Self::get(db, utx, id).await
+	}
+
+	/// Lock the owned collection until the end of the transaction, so its positions are changed by one request at a time,
+	/// and give its quotes in order.
+	async fn lock_quote_ids(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, utx: &UserCtx, id: i64) -> Result<Vec<i64>, model::Error2> {
+		let locked: Option<(i64,)> = sqlx::query_as("SELECT id FROM collection WHERE id = $1 AND cid = $2 FOR UPDATE")
+			.bind(id)
+			.bind(utx.user_id)
+			.fetch_optional(&mut **tx)
+			.await?;
+		if locked.is_none() {
+			return Err(model::Error2::EntityNotFound(Self::TABLE, id.to_string()));
+		}
+		let quote_ids: Vec<(i64,)> = sqlx::query_as("SELECT quote_id FROM collection_quote WHERE collection_id = $1 ORDER BY position")
+			.bind(id)
+			.fetch_all(&mut **tx)
+			.await?;
+
+		Ok(quote_ids.into_iter().map(|(quote_id,)| quote_id).collect())
+	}
+
+	/// Only the owner can change a collection, others get a not found.
+	async fn get_owned(db: &Db, utx: &UserCtx, id: i64) -> Result<Collection, model::Error2> {
+		let sql = format!("{} WHERE id = $1 AND cid = $2", Self::SELECT);
+		let result = sqlx::query_as::<_, Collection>(&sql).bind(id).bind(utx.user_id).fetch_one(db).await;
+
+		handle_fetch_one_result(result, Self::TABLE, id)
+	}
+}
+// endregion: CollectionModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_collection.rs"]
+mod tests;
+// endregion: Test
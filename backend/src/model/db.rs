@@ -45,6 +45,11 @@ pub async fn init_db() -> Result<Db, sqlx::Error> {
     new_db_pool(PG_HOST, PG_APP_DB, PG_APP_USER, PG_APP_PWD, PG_APP_MAX_CON).await
 }
 
+/// Connect to the existing application database (no recreate, e.g., for the command line tools).
+pub async fn connect_db() -> Result<Db, sqlx::Error> {
+    new_db_pool(PG_HOST, PG_APP_DB, PG_APP_USER, PG_APP_PWD, PG_APP_MAX_CON).await
+}
+
 async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
     // Read the file
     let content = fs::read_to_string(file).map_err(|ex| {
@@ -52,8 +57,7 @@ async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
         ex
     })?;
 
-    //TODO: Make the split more sql proof 
-    let sqls: Vec<&str> = content.split(";").collect();
+    let sqls = split_sql(&content);
 
     for sql in sqls {
         match sqlx::query(&sql).execute(db).await {
@@ -65,6 +69,41 @@ async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
+/// Split a sql file into statements on `;`, except inside quotes, comments and `$$` bodies (e.g., plpgsql functions).
+fn split_sql(content: &str) -> Vec<&str> {
+    let mut sqls = Vec::new();
+    let mut start = 0;
+    let mut in_quote = false;
+    let mut in_dollar = false;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\'' if !in_dollar => in_quote = !in_quote,
+            // skip `-- comments` to the end of the line
+            '-' if !in_quote && !in_dollar && chars.peek().is_some_and(|(_, next)| *next == '-') => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '$' if !in_quote && chars.peek().is_some_and(|(_, next)| *next == '$') => {
+                chars.next();
+                in_dollar = !in_dollar;
+            }
+            ';' if !in_quote && !in_dollar => {
+                sqls.push(&content[start..idx]);
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    sqls.push(&content[start..]);
+
+    sqls
+}
+
 async fn new_db_pool(host: &str, db: &str, user: &str, pwd: &str, max_con: u32) -> Result<Db, sqlx::Error> {
     let con_string = format!("postgres://{}:{}@{}/{}", user, pwd, host, db);
     PgPoolOptions::new()
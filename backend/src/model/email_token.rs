@@ -0,0 +1,109 @@
+use super::db::Db;
+use crate::model;
+use serde::{Deserialize, Serialize};
+
+// region: EmailToken Types
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "email_token_purpose_enum")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EmailTokenPurpose {
+	PasswordReset,
+	EmailVerification,
+}
+
+/// The user and the address of an email token (see security::email).
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct EmailToken {
+	pub user_id: i64,
+	pub email: String,
+}
+// endregion: EmailToken Types
+
+// region: EmailTokenModelAccessController
+// A user has one token of each purpose at most: a new one replaces the previous one.
+pub struct EmailTokenMac;
+
+impl EmailTokenMac {
+	/// Store the token by its hash for `ttl_secs`, and forget the expired ones.
+	pub async fn create(
+		db: &Db,
+		purpose: EmailTokenPurpose,
+		token: &EmailToken,
+		token_hash: &str,
+		ttl_secs: i64,
+	) -> Result<(), model::Error2> {
+		Self::create_unless_recent(db, purpose, token, token_hash, ttl_secs, 0).await?;
+
+		Ok(())
+	}
+
+	/// As `create`, unless the unexpired token of the user and purpose was created in the last `min_interval_secs` (false),
+	/// so its email cannot be sent over and over.
+	pub async fn create_unless_recent(
+		db: &Db,
+		purpose: EmailTokenPurpose,
+		token: &EmailToken,
+		token_hash: &str,
+		ttl_secs: i64,
+		min_interval_secs: i64,
+	) -> Result<bool, model::Error2> {
+		// the user row lock makes the check and the insert atomic (concurrent requests wait their turn)
+		let mut tx = db.begin().await?;
+		sqlx::query("SELECT id FROM user_account WHERE id = $1 FOR UPDATE")
+			.bind(token.user_id)
+			.execute(&mut tx)
+			.await?;
+		let (recent,): (bool,) = sqlx::query_as(
+			"SELECT EXISTS(SELECT 1 FROM email_token WHERE user_id = $1 AND purpose = $2 AND expires > now() \
+			AND ctime > now() - $3 * interval '1 second')",
+		)
+		.bind(token.user_id)
+		.bind(purpose)
+		.bind(min_interval_secs as f64)
+		.fetch_one(&mut tx)
+		.await?;
+		if recent {
+			return Ok(false);
+		}
+
+		sqlx::query("DELETE FROM email_token WHERE (user_id = $1 AND purpose = $2) OR expires <= now()")
+			.bind(token.user_id)
+			.bind(purpose)
+			.execute(&mut tx)
+			.await?;
+		sqlx::query(
+			"INSERT INTO email_token (token_hash, user_id, purpose, email, expires) VALUES ($1, $2, $3, $4, now() + $5 * interval '1 second')",
+		)
+		.bind(token_hash)
+		.bind(token.user_id)
+		.bind(purpose)
+		.bind(&token.email)
+		.bind(ttl_secs as f64)
+		.execute(&mut tx)
+		.await?;
+		tx.commit().await?;
+
+		Ok(true)
+	}
+
+	/// The unexpired token of the hash and purpose, deleted so it is only used once.
+	pub async fn take(db: &Db, purpose: EmailTokenPurpose, token_hash: &str) -> Result<Option<EmailToken>, model::Error2> {
+		let token: Option<(i64, String, bool)> = sqlx::query_as(
+			"DELETE FROM email_token WHERE token_hash = $1 AND purpose = $2 RETURNING user_id, email, expires > now() AS valid",
+		)
+		.bind(token_hash)
+		.bind(purpose)
+		.fetch_optional(db)
+		.await?;
+
+		Ok(token.filter(|(_, _, valid)| *valid).map(|(user_id, email, _)| EmailToken { user_id, email }))
+	}
+}
+// endregion: EmailTokenModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_email_token.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,61 @@
+use super::db::Db;
+use super::{ListOptions, Quote, QuoteMac};
+use crate::model;
+use crate::security::UserCtx;
+
+// region: FavoriteModelAccessController
+// Note: quote.favorite_count is kept in sync by the favorite_count_trigger (see sql/01-create-schema.sql).
+pub struct FavoriteMac;
+
+impl FavoriteMac {
+	/// Favorite the quote for the user (no-op if already a favorite).
+	pub async fn add(db: &Db, utx: &UserCtx, quote_id: i64) -> Result<Quote, model::Error2> {
+		QuoteMac::get(db, utx, quote_id).await?;
+
+		sqlx::query("INSERT INTO favorite (user_id, quote_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+			.bind(utx.user_id)
+			.bind(quote_id)
+			.execute(db)
+			.await?;
+
+		let mut quote = QuoteMac::get(db, utx, quote_id).await?;
+		quote.is_favorite = Some(true);
+		Ok(quote)
+	}
+
+	/// Remove the quote from the user favorites (no-op if not a favorite).
+	pub async fn remove(db: &Db, utx: &UserCtx, quote_id: i64) -> Result<Quote, model::Error2> {
+		sqlx::query("DELETE FROM favorite WHERE user_id = $1 AND quote_id = $2")
+			.bind(utx.user_id)
+			.bind(quote_id)
+			.execute(db)
+			.await?;
+
+		let mut quote = QuoteMac::get(db, utx, quote_id).await?;
+		quote.is_favorite = Some(false);
+		Ok(quote)
+	}
+
+	/// The user favorite quotes, most recently favorited first.
+	pub async fn list(db: &Db, utx: &UserCtx, list_options: &ListOptions) -> Result<Vec<Quote>, model::Error2> {
+		let columns = QuoteMac::COLUMNS.iter().map(|c| format!("q.{}", c)).collect::<Vec<_>>().join(", ");
+		let sql = format!(
+			"SELECT {}, true AS is_favorite FROM favorite f JOIN quote q ON q.id = f.quote_id \
+			WHERE f.user_id = $1 ORDER BY f.ctime DESC, q.id DESC LIMIT $2 OFFSET $3",
+			columns
+		);
+		let query = sqlx::query_as::<_, Quote>(&sql)
+			.bind(utx.user_id)
+			.bind(list_options.limit())
+			.bind(list_options.offset());
+
+		Ok(query.fetch_all(db).await?)
+	}
+}
+// endregion: FavoriteModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_favorite.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,128 @@
+use super::db::Db;
+use super::handle_fetch_one_result;
+use super::permission::{ensure, Action};
+use super::Role;
+use crate::model;
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_INVITE_TTL_SECS: i64 = 7 * 24 * 3600;
+
+// region: Invite Types
+/// An invite, as listed (the code itself is only given at its creation, see security::create_invite).
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct Invite {
+	pub id: i64,
+	pub cid: i64,
+	pub prefix: String,
+	pub role: Role, // of the users registering with it
+	pub max_uses: i32,
+	pub uses: i32,
+	pub ctime: i64,   // unix seconds
+	pub expires: i64, // unix seconds
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InviteForCreate {
+	pub role: Option<Role>, // contributor by default
+	pub max_uses: Option<i32>, // 1 by default (single-use)
+	pub expires: Option<i64>, // unix seconds, in 7 days by default
+}
+
+/// A registration with an invite.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct InviteUse {
+	pub invite_id: i64,
+	pub user_id: i64,
+	pub username: String,
+	pub ctime: i64, // unix seconds
+}
+
+/// An invite with the users who registered with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteListing {
+	#[serde(flatten)]
+	pub invite: Invite,
+	pub used_by: Vec<InviteUse>,
+}
+// endregion: Invite Types
+
+// region: InviteModelAccessController
+// Only admins manage the invites (see model::permission).
+pub struct InviteMac;
+
+impl InviteMac {
+	const TABLE: &'static str = "invite";
+	const COLUMNS: &'static str = "id, cid, prefix, role, max_uses, uses, extract(epoch FROM ctime)::bigint AS ctime, \
+		extract(epoch FROM expires)::bigint AS expires";
+
+	/// Store the invite by the hash of its code (see security::create_invite).
+	pub async fn create(db: &Db, utx: &UserCtx, data: InviteForCreate, prefix: &str, code_hash: &str) -> Result<Invite, model::Error2> {
+		ensure(utx, Action::ManageInvites)?;
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default();
+		let max_uses = data.max_uses.unwrap_or(1);
+		if max_uses < 1 {
+			return Err(model::Error2::InvalidData("an invite is for one use at least".to_string()));
+		}
+		let expires = data.expires.unwrap_or(now + DEFAULT_INVITE_TTL_SECS);
+		if expires <= now {
+			return Err(model::Error2::InvalidData("the expiration of an invite must be in the future".to_string()));
+		}
+
+		let sql = format!(
+			"INSERT INTO invite (cid, prefix, code_hash, role, max_uses, expires) VALUES ($1, $2, $3, $4, $5, to_timestamp($6)) \
+			RETURNING {}",
+			Self::COLUMNS
+		);
+		let invite = sqlx::query_as::<_, Invite>(&sql)
+			.bind(utx.user_id)
+			.bind(prefix)
+			.bind(code_hash)
+			.bind(data.role.unwrap_or_default())
+			.bind(max_uses)
+			.bind(expires as f64)
+			.fetch_one(db)
+			.await?;
+
+		Ok(invite)
+	}
+
+	/// The invites, last created first, with who used them.
+	pub async fn list(db: &Db, utx: &UserCtx) -> Result<Vec<InviteListing>, model::Error2> {
+		ensure(utx, Action::ManageInvites)?;
+		let sql = format!("SELECT {} FROM invite ORDER BY id DESC", Self::COLUMNS);
+		let invites = sqlx::query_as::<_, Invite>(&sql).fetch_all(db).await?;
+		let uses = sqlx::query_as::<_, InviteUse>(
+			"SELECT iu.invite_id, iu.user_id, u.username, extract(epoch FROM iu.ctime)::bigint AS ctime \
+			FROM invite_use iu JOIN user_account u ON u.id = iu.user_id ORDER BY iu.ctime, iu.user_id",
+		)
+		.fetch_all(db)
+		.await?;
+
+		let listings = invites
+			.into_iter()
+			.map(|invite| InviteListing {
+				used_by: uses.iter().filter(|u| u.invite_id == invite.id).cloned().collect(),
+				invite,
+			})
+			.collect();
+		Ok(listings)
+	}
+
+	/// Revoke the invite, by expiring it now (its uses stay listed).
+	pub async fn revoke(db: &Db, utx: &UserCtx, id: i64) -> Result<Invite, model::Error2> {
+		ensure(utx, Action::ManageInvites)?;
+		let sql = format!("UPDATE invite SET expires = least(expires, now()) WHERE id = $1 RETURNING {}", Self::COLUMNS);
+		let result = sqlx::query_as::<_, Invite>(&sql).bind(id).fetch_one(db).await;
+
+		handle_fetch_one_result(result, Self::TABLE, id)
+	}
+}
+// endregion: InviteModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_invite.rs"]
+mod tests;
+// endregion: Test
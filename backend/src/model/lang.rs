@@ -0,0 +1,116 @@
+// BCP 47 language tags: validation, detection and negotiation.
+
+use crate::model;
+use whatlang::Lang;
+
+/// Tag stored when the language cannot be determined.
+pub const UNDETERMINED_LANG: &str = "und";
+
+// Below this confidence, the detected language is too much of a guess to be stored.
+const DETECT_MIN_CONFIDENCE: f64 = 0.5;
+
+/// Validate a BCP 47 tag (`language[-subtag]*`) and normalize its case, e.g. `es-mx` => `es-MX`.
+pub fn normalize_lang_tag(tag: &str) -> Result<String, model::Error2> {
+	let invalid = || model::Error2::InvalidData(format!("'{}' is not a BCP 47 language tag", tag));
+
+	let mut subtags = tag.trim().split(['-', '_']);
+	let language = subtags.next().filter(|l| (2..=8).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+	let mut normalized = language.ok_or_else(invalid)?.to_ascii_lowercase();
+
+	for subtag in subtags {
+		if subtag.is_empty() || subtag.len() > 8 || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+			return Err(invalid());
+		}
+		normalized.push('-');
+		match subtag.len() {
+			// region (e.g., MX)
+			2 => normalized.push_str(&subtag.to_ascii_uppercase()),
+			// script (e.g., Latn)
+			4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+				normalized.push_str(&subtag[..1].to_ascii_uppercase());
+				normalized.push_str(&subtag[1..].to_ascii_lowercase());
+			}
+			_ => normalized.push_str(&subtag.to_ascii_lowercase()),
+		}
+	}
+
+	Ok(normalized)
+}
+
+/// Detect the language of a text, as a BCP 47 tag. Returns `und` when unsure.
+pub fn detect_lang(text: &str) -> String {
+	match whatlang::detect(text) {
+		Some(info) if info.confidence() >= DETECT_MIN_CONFIDENCE => bcp47_of(info.lang()).to_string(),
+		_ => UNDETERMINED_LANG.to_string(),
+	}
+}
+
+// BCP 47 uses the ISO 639-1 code when there is one, otherwise the ISO 639-3 code whatlang gives.
+fn bcp47_of(lang: Lang) -> &'static str {
+	match lang {
+		Lang::Eng => "en",
+		Lang::Spa => "es",
+		Lang::Deu => "de",
+		Lang::Fra => "fr",
+		Lang::Ita => "it",
+		Lang::Por => "pt",
+		Lang::Nld => "nl",
+		Lang::Rus => "ru",
+		Lang::Pol => "pl",
+		Lang::Swe => "sv",
+		Lang::Dan => "da",
+		Lang::Fin => "fi",
+		Lang::Tur => "tr",
+		Lang::Cmn => "zh",
+		Lang::Jpn => "ja",
+		Lang::Kor => "ko",
+		Lang::Ara => "ar",
+		other => other.code(),
+	}
+}
+
+/// Parse an `Accept-Language` header into the language ranges, by decreasing preference.
+/// Ranges with `q=0` are dropped.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+	let mut ranges: Vec<(String, f32)> = header
+		.split(',')
+		.filter_map(|item| {
+			let mut parts = item.split(';');
+			let range = parts.next()?.trim();
+			let q = parts
+				.find_map(|p| p.trim().strip_prefix("q="))
+				.map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+			(!range.is_empty() && q > 0.0).then(|| (range.to_string(), q))
+		})
+		.collect();
+	// stable sort, so equal q values keep the header order
+	ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+	ranges.into_iter().map(|(range, _)| range).collect()
+}
+
+/// Index of the best available tag for the preferred ranges (in order), if any.
+/// A range matches a tag exactly, or by primary language (`es-MX` matches `es`, and `es` matches `es-ES`).
+pub fn best_lang_match(available: &[&str], preferred: &[String]) -> Option<usize> {
+	let primary = |tag: &str| tag.split('-').next().unwrap_or_default().to_ascii_lowercase();
+
+	for range in preferred {
+		if range == "*" {
+			return (!available.is_empty()).then_some(0);
+		}
+		if let Some(idx) = available.iter().position(|tag| tag.eq_ignore_ascii_case(range)) {
+			return Some(idx);
+		}
+		if let Some(idx) = available.iter().position(|tag| primary(tag) == primary(range)) {
+			return Some(idx);
+		}
+	}
+
+	None
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_lang.rs"]
+mod tests;
+// endregion: Test
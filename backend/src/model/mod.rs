@@ -7,13 +7,43 @@ use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 //Aboe unnecessary
 */
+mod api_key;
+mod collection;
 mod db;
+mod email_token;
+mod favorite;
+mod invite;
+mod lang;
+mod moderation;
+mod oidc;
 //My own two additions here:
+mod patch;
+mod permission;
 mod quote;
-pub use quote::{Quote, QuoteMac, QuotePatch};
+mod report;
+mod session;
+mod stats;
+mod totp;
+mod user;
+pub use api_key::{ApiKey, ApiKeyForCreate, ApiKeyMac, ApiScope};
+pub use collection::{Collection, CollectionMac, CollectionPatch, CollectionVisibility};
+pub use email_token::{EmailToken, EmailTokenMac, EmailTokenPurpose};
+pub use favorite::FavoriteMac;
+pub use invite::{Invite, InviteForCreate, InviteListing, InviteMac, InviteUse};
+pub use permission::{ensure, Action, PermissionDenied};
+pub use patch::{parse_json_patch, Nullable, PatchOp, JSON_PATCH_MIME, MERGE_PATCH_MIME};
+pub use lang::{normalize_lang_tag, parse_accept_language};
+pub use moderation::ModerationMac;
+pub use oidc::{OidcLogin, OidcLoginMac};
+pub use session::{Session, SessionForCreate, SessionMac};
+pub use totp::{TotpMac, UserTotp};
+pub use user::{IdentityForCreate, Role, UserAccount, UserForAuth, UserForCreate, UserMac};
+pub use stats::{Growth, PeriodCount, QuoteStats, StatCount, StatsCache, StatsInterval, StatsMac, StatsOptions};
+pub use report::{Report, ReportForCreate, ReportMac, ReportOutcome, ReportReason, ReportResolution, ReportSummary};
+pub use quote::{Quote, QuoteFilter, QuoteMac, QuotePatch, QuoteSort, QuoteStatus, Tags, TranslationForCreate, DEFAULT_AUTHOR};
 
 // re-export
-pub use db::init_db;
+pub use db::{connect_db, init_db};
 pub use db::Db;
 
 // region:    Error
@@ -22,6 +52,21 @@ pub enum Error2 {
 	#[error("Entity Not Found - {0}[{1}] ")]
 	EntityNotFound(&'static str, String),
 
+	#[error("Invalid Data - {0}")]
+	InvalidData(String),
+
+	#[error(transparent)]
+	PermissionDenied(#[from] PermissionDenied),
+
+	#[error("Invalid Patch - {0}")]
+	InvalidPatch(String),
+
+	#[error("Version Mismatch - quote[{}] is at version {}", .0.id, .0.version)]
+	QuoteVersionMismatch(Box<Quote>),
+
+	#[error("Rate Limited - {0}")]
+	RateLimited(String),
+
 	#[error(transparent)]
 	SqlxError(#[from] sqlx::Error),
 
@@ -31,6 +76,40 @@ pub enum Error2 {
 
 // endregion: Error
 
+// region:    List Options
+const LIST_LIMIT_MAX: i64 = 1000;
+
+/// Pagination of the list apis (`?limit=20&offset=40`). No limit means everything (up to LIST_LIMIT_MAX).
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ListOptions {
+	pub limit: Option<i64>,
+	pub offset: Option<i64>,
+}
+
+impl ListOptions {
+	pub fn limit(&self) -> i64 {
+		self.limit.unwrap_or(LIST_LIMIT_MAX).clamp(0, LIST_LIMIT_MAX)
+	}
+
+	pub fn offset(&self) -> i64 {
+		self.offset.unwrap_or(0).max(0)
+	}
+}
+// endregion: List Options
+
+// region:    Utils
+pub(crate) fn handle_fetch_one_result<T>(
+	result: Result<T, sqlx::Error>,
+	typ: &'static str,
+	id: i64,
+) -> Result<T, Error2> {
+	result.map_err(|sqlx_error| match sqlx_error {
+		sqlx::Error::RowNotFound => Error2::EntityNotFound(typ, id.to_string()),
+		other => Error2::SqlxError(other),
+	})
+}
+// endregion: Utils
+
 
 //Unnecessary things after this?
 /*
@@ -0,0 +1,74 @@
+use super::db::Db;
+use super::permission::{ensure, Action};
+use super::{ListOptions, Quote, QuoteMac, QuoteStatus};
+use crate::model;
+use crate::security::UserCtx;
+
+// region: ModerationModelAccessController
+// Quotes of non-trusted users are created `pending` (see config QUOTE_MODERATION) until a moderator
+// approves or rejects them. The reason is kept on the quote, for its creator to see.
+pub struct ModerationMac;
+
+impl ModerationMac {
+	/// The pending quotes, oldest first.
+	pub async fn list_pending(db: &Db, utx: &UserCtx, list_options: &ListOptions) -> Result<Vec<Quote>, model::Error2> {
+		ensure(utx, Action::Moderate)?;
+
+		let sql = format!(
+			"SELECT {} FROM quote WHERE status = 'pending' ORDER BY id LIMIT $1 OFFSET $2",
+			QuoteMac::COLUMNS.join(", ")
+		);
+		let query = sqlx::query_as::<_, Quote>(&sql).bind(list_options.limit()).bind(list_options.offset());
+
+		Ok(query.fetch_all(db).await?)
+	}
+
+	/// Publish the pending quote.
+	pub async fn approve(db: &Db, utx: &UserCtx, id: i64, reason: Option<String>) -> Result<Quote, model::Error2> {
+		let reason = reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+		Self::moderate(db, utx, id, QuoteStatus::Approved, reason).await
+	}
+
+	/// Reject the pending quote, with the reason told to its creator.
+	pub async fn reject(db: &Db, utx: &UserCtx, id: i64, reason: String) -> Result<Quote, model::Error2> {
+		let reason = reason.trim();
+		if reason.is_empty() {
+			return Err(model::Error2::InvalidData("a rejection needs a reason".to_string()));
+		}
+		Self::moderate(db, utx, id, QuoteStatus::Rejected, Some(reason.to_string())).await
+	}
+
+	async fn moderate(db: &Db, utx: &UserCtx, id: i64, status: QuoteStatus, reason: Option<String>) -> Result<Quote, model::Error2> {
+		ensure(utx, Action::Moderate)?;
+
+		let sql = format!(
+			"UPDATE quote SET status = $2, moderation_reason = $3, moderated_by = $4, moderated_at = now(), version = version + 1 \
+			WHERE id = $1 AND status = 'pending' RETURNING {}",
+			QuoteMac::COLUMNS.join(", ")
+		);
+		let result = sqlx::query_as::<_, Quote>(&sql)
+			.bind(id)
+			.bind(status)
+			.bind(reason)
+			.bind(utx.user_id)
+			.fetch_one(db)
+			.await;
+
+		match result {
+			// tell apart an unknown quote from an already moderated one
+			Err(sqlx::Error::RowNotFound) => {
+				let quote = QuoteMac::get(db, utx, id).await?;
+				Err(model::Error2::InvalidData(format!("quote {} is not pending (status {:?})", quote.id, quote.status)))
+			}
+			result => Ok(result?),
+		}
+	}
+}
+
+// endregion: ModerationModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_moderation.rs"]
+mod tests;
+// endregion: Test
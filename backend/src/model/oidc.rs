@@ -0,0 +1,74 @@
+use super::db::Db;
+use crate::model;
+
+// region: OidcLogin Types
+/// The secrets of an OpenID Connect login, kept from the redirect to the provider until its callback.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct OidcLogin {
+	pub state: String,
+	pub nonce: String,
+	pub code_verifier: String,
+}
+// endregion: OidcLogin Types
+
+// region: OidcLoginModelAccessController
+pub struct OidcLoginMac;
+
+impl OidcLoginMac {
+	/// Keep the login for `ttl_secs`, and forget the expired ones.
+	pub async fn create(db: &Db, login: &OidcLogin, ttl_secs: i64) -> Result<(), model::Error2> {
+		sqlx::query("DELETE FROM oidc_login WHERE expires <= now()").execute(db).await?;
+		sqlx::query("INSERT INTO oidc_login (state, nonce, code_verifier, expires) VALUES ($1, $2, $3, now() + $4 * interval '1 second')")
+			.bind(&login.state)
+			.bind(&login.nonce)
+			.bind(&login.code_verifier)
+			.bind(ttl_secs as f64)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// The unexpired login of the state, deleted so its callback is only accepted once.
+	pub async fn take(db: &Db, state: &str) -> Result<Option<OidcLogin>, model::Error2> {
+		let login: Option<(String, String, String, bool)> = sqlx::query_as(
+			"DELETE FROM oidc_login WHERE state = $1 RETURNING state, nonce, code_verifier, expires > now() AS valid",
+		)
+		.bind(state)
+		.fetch_optional(db)
+		.await?;
+
+		Ok(login.filter(|(_, _, _, valid)| *valid).map(|(state, nonce, code_verifier, _)| OidcLogin { state, nonce, code_verifier }))
+	}
+
+	/// Keep the SSO login of the user waiting for its TOTP code for `ttl_secs`, and forget the expired ones.
+	pub async fn create_totp_pending(db: &Db, ticket_hash: &str, user_id: i64, ttl_secs: i64) -> Result<(), model::Error2> {
+		sqlx::query("DELETE FROM oidc_totp_login WHERE expires <= now()").execute(db).await?;
+		sqlx::query("INSERT INTO oidc_totp_login (ticket_hash, user_id, expires) VALUES ($1, $2, now() + $3 * interval '1 second')")
+			.bind(ticket_hash)
+			.bind(user_id)
+			.bind(ttl_secs as f64)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// The user of the unexpired waiting login, deleted so its ticket is only accepted once (whatever the code).
+	pub async fn take_totp_pending(db: &Db, ticket_hash: &str) -> Result<Option<i64>, model::Error2> {
+		let login: Option<(i64, bool)> =
+			sqlx::query_as("DELETE FROM oidc_totp_login WHERE ticket_hash = $1 RETURNING user_id, expires > now() AS valid")
+				.bind(ticket_hash)
+				.fetch_optional(db)
+				.await?;
+
+		Ok(login.filter(|(_, valid)| *valid).map(|(user_id, _)| user_id))
+	}
+}
+// endregion: OidcLoginModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_oidc.rs"]
+mod tests;
+// endregion: Test
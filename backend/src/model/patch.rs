@@ -0,0 +1,186 @@
+// RFC 7396 (JSON Merge Patch) and RFC 6902 (JSON Patch) support for the model entities.
+
+use crate::model;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use sqlb::SqlxBindable;
+
+// region: Nullable
+/// A field value that can also be explicitly cleared to NULL.
+/// In a patch, `None` means "leave unchanged" and `Some(Nullable::Null)` means "clear".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Nullable<T> {
+	Null,
+	Value(T),
+}
+
+impl<T> From<Option<T>> for Nullable<T> {
+	fn from(val: Option<T>) -> Self {
+		val.map_or(Nullable::Null, Nullable::Value)
+	}
+}
+
+impl<T> Nullable<T> {
+	pub fn into_option(self) -> Option<T> {
+		match self {
+			Nullable::Null => None,
+			Nullable::Value(val) => Some(val),
+		}
+	}
+}
+
+impl SqlxBindable for Nullable<String> {
+	fn bind_query<'q>(
+		&self,
+		query: sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments>,
+	) -> sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments> {
+		query.bind(self.clone().into_option())
+	}
+}
+
+/// serde `deserialize_with` for `Option<Nullable<T>>` fields (use with `#[serde(default)]`),
+/// so that a present `null` becomes `Some(Nullable::Null)` rather than `None`.
+pub fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Nullable<T>>, D::Error>
+where
+	D: Deserializer<'de>,
+	T: Deserialize<'de>,
+{
+	Option::<T>::deserialize(deserializer).map(|val| Some(val.into()))
+}
+// endregion: Nullable
+
+// region: JSON Patch
+pub const MERGE_PATCH_MIME: &str = "application/merge-patch+json";
+pub const JSON_PATCH_MIME: &str = "application/json-patch+json";
+
+/// One RFC 6902 operation. Only add, remove, replace and test are supported.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase", deny_unknown_fields)]
+pub enum PatchOp {
+	Add { path: String, value: Value },
+	Remove { path: String },
+	Replace { path: String, value: Value },
+	Test { path: String, value: Value },
+}
+
+/// Parse a JSON Patch document (an array of operations).
+pub fn parse_json_patch(patch: Value) -> Result<Vec<PatchOp>, model::Error2> {
+	if !patch.is_array() {
+		return Err(model::Error2::InvalidPatch("JSON Patch must be an array of operations".to_string()));
+	}
+	serde_json::from_value(patch).map_err(|ex| model::Error2::InvalidPatch(format!("Invalid JSON Patch operation ({})", ex)))
+}
+
+/// Apply the operations in order on `doc`. `allowed` lists the top level members that may be patched.
+/// Array members can be addressed by index or by `-` (end of the array, add only).
+pub fn apply_json_patch(doc: &mut Value, ops: &[PatchOp], allowed: &[&str]) -> Result<(), model::Error2> {
+	for op in ops {
+		match op {
+			PatchOp::Add { path, value } => {
+				let (name, idx) = parse_path(path, allowed)?;
+				match idx {
+					None => {
+						doc[name] = value.clone();
+					}
+					Some(idx) => {
+						let arr = array_at(doc, name, path)?;
+						let idx = match idx {
+							PathIdx::End => arr.len(),
+							PathIdx::At(idx) if idx <= arr.len() => idx,
+							PathIdx::At(_) => return Err(out_of_range(path)),
+						};
+						arr.insert(idx, value.clone());
+					}
+				}
+			}
+			PatchOp::Remove { path } => {
+				let (name, idx) = parse_path(path, allowed)?;
+				match idx {
+					None => {
+						doc.as_object_mut()
+							.and_then(|obj| obj.remove(name))
+							.ok_or_else(|| not_found(path))?;
+					}
+					Some(idx) => {
+						let arr = array_at(doc, name, path)?;
+						let idx = existing_idx(idx, arr.len(), path)?;
+						arr.remove(idx);
+					}
+				}
+			}
+			PatchOp::Replace { path, value } => {
+				let target = pointer_mut(doc, path, allowed)?;
+				*target = value.clone();
+			}
+			PatchOp::Test { path, value } => {
+				let target = pointer_mut(doc, path, allowed)?;
+				if target != value {
+					return Err(model::Error2::InvalidPatch(format!("test failed at '{}'", path)));
+				}
+			}
+		}
+	}
+	Ok(())
+}
+
+enum PathIdx {
+	At(usize),
+	End,
+}
+
+/// Split `/name` or `/name/idx` and check that the member is patchable.
+fn parse_path<'a>(path: &'a str, allowed: &[&str]) -> Result<(&'a str, Option<PathIdx>), model::Error2> {
+	let unsupported = || model::Error2::InvalidPatch(format!("unsupported path '{}'", path));
+
+	let mut parts = path.strip_prefix('/').ok_or_else(unsupported)?.splitn(2, '/');
+	let name = parts.next().filter(|name| allowed.contains(name)).ok_or_else(unsupported)?;
+	let idx = match parts.next() {
+		None => None,
+		Some("-") => Some(PathIdx::End),
+		Some(idx) if idx == "0" || !idx.starts_with('0') => Some(PathIdx::At(idx.parse().map_err(|_| unsupported())?)),
+		Some(_) => return Err(unsupported()),
+	};
+
+	Ok((name, idx))
+}
+
+fn pointer_mut<'a>(doc: &'a mut Value, path: &str, allowed: &[&str]) -> Result<&'a mut Value, model::Error2> {
+	let (name, idx) = parse_path(path, allowed)?;
+	match idx {
+		None => doc.get_mut(name).ok_or_else(|| not_found(path)),
+		Some(idx) => {
+			let arr = array_at(doc, name, path)?;
+			let idx = existing_idx(idx, arr.len(), path)?;
+			Ok(&mut arr[idx])
+		}
+	}
+}
+
+fn array_at<'a>(doc: &'a mut Value, name: &str, path: &str) -> Result<&'a mut Vec<Value>, model::Error2> {
+	doc.get_mut(name)
+		.and_then(Value::as_array_mut)
+		.ok_or_else(|| model::Error2::InvalidPatch(format!("'{}' is not an array element", path)))
+}
+
+fn existing_idx(idx: PathIdx, len: usize, path: &str) -> Result<usize, model::Error2> {
+	match idx {
+		PathIdx::At(idx) if idx < len => Ok(idx),
+		_ => Err(out_of_range(path)),
+	}
+}
+
+fn not_found(path: &str) -> model::Error2 {
+	model::Error2::InvalidPatch(format!("nothing to patch at '{}'", path))
+}
+
+fn out_of_range(path: &str) -> model::Error2 {
+	model::Error2::InvalidPatch(format!("index out of range at '{}'", path))
+}
+// endregion: JSON Patch
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_patch.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,78 @@
+use super::Role;
+use crate::security::UserCtx;
+use std::fmt;
+
+// region: Permission Types
+/// What a user does, checked against its role by the model access controllers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+	ReadQuotes,
+	CreateQuote,
+	UpdateQuote, // of another user, see `ensure_owned`
+	DeleteQuote, // of another user, see `ensure_owned`
+	Moderate,
+	ManageInvites,
+}
+
+impl fmt::Display for Action {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let action = match self {
+			Action::ReadQuotes => "read quotes",
+			Action::CreateQuote => "create quotes",
+			Action::UpdateQuote => "update the quotes of others",
+			Action::DeleteQuote => "delete the quotes of others",
+			Action::Moderate => "moderate",
+			Action::ManageInvites => "manage invites",
+		};
+		f.write_str(action)
+	}
+}
+
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("Permission Denied - user {user_id} ({role:?}) cannot {action}")]
+pub struct PermissionDenied {
+	pub user_id: i64,
+	pub role: Role,
+	pub action: Action,
+}
+// endregion: Permission Types
+
+// region: Permission Checks
+impl Action {
+	/// The lowest role allowed to do the action.
+	fn min_role(self) -> Role {
+		match self {
+			Action::ReadQuotes => Role::Viewer,
+			Action::CreateQuote => Role::Contributor,
+			Action::UpdateQuote | Action::DeleteQuote => Role::Editor,
+			Action::Moderate => Role::Moderator,
+			Action::ManageInvites => Role::Admin,
+		}
+	}
+}
+
+pub fn ensure(utx: &UserCtx, action: Action) -> Result<(), PermissionDenied> {
+	match utx.role >= action.min_role() {
+		true => Ok(()),
+		false => Err(PermissionDenied {
+			user_id: utx.user_id,
+			role: utx.role,
+			action,
+		}),
+	}
+}
+
+/// Contributors change their own quotes, editors and above those of everyone.
+pub fn ensure_owned(utx: &UserCtx, action: Action, owner_id: i64) -> Result<(), PermissionDenied> {
+	match owner_id == utx.user_id {
+		true => ensure(utx, Action::CreateQuote),
+		false => ensure(utx, action),
+	}
+}
+// endregion: Permission Checks
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_permission.rs"]
+mod tests;
+// endregion: Test
@@ -1,10 +1,20 @@
 // *** Replaces todo.rs from same folder ***
 
 use super::db::Db;
+use super::lang::{best_lang_match, detect_lang, normalize_lang_tag, UNDETERMINED_LANG};
+use super::{handle_fetch_one_result, ListOptions};
+use super::patch::{apply_json_patch, deserialize_nullable, Nullable, PatchOp};
+use super::permission::{ensure, ensure_owned, Action};
 use crate::model;
 use crate::security::UserCtx;
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use sqlb::{HasFields, Raw};
+use serde_json::{json, Value};
+use sqlb::{HasFields, Raw, SqlxBindable};
+use tokio::sync::mpsc;
+
+// Rows buffered ahead of a slow stream consumer.
+const STREAM_BUFFER_SIZE: usize = 64;
 
 // region: Quote Types
 #[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +23,20 @@ pub struct Quote {
     pub cid: i64,  //creator id
     pub quote: String,
     pub author: String,
+    pub source: Option<String>,
+    pub locator: Option<String>, // page, location, ... in the source
+    pub tags: Vec<String>,
+    pub lang: String, // BCP 47 tag, `und` when unknown
+    pub original_id: Option<i64>, // set when the quote is a translation
+    pub translator: Option<String>,
+    pub version: i64,
+    pub favorite_count: i64,
+    pub status: QuoteStatus,
+    pub moderation_reason: Option<String>,
+    // whether the caller favorited the quote (only computed by the list apis)
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_favorite: Option<bool>,
 }
 
 #[derive(sqlb::Fields, Default, Debug, Clone, Deserialize)]
@@ -20,35 +44,165 @@ pub struct QuotePatch {
     //pub cid: Option<i64>,
     pub quote: Option<String>,
     pub author: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub source: Option<Nullable<String>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub locator: Option<Nullable<String>>,
+    pub tags: Option<Tags>,
+    pub lang: Option<String>,
 }
 
-// *** This may be completely unnecessary ***
-#[derive(sqlx::Type, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A translation of an existing quote. Author, source and locator are taken from the original.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranslationForCreate {
+    pub quote: String,
+    pub lang: String,
+    pub translator: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tags(pub Vec<String>);
+
+impl SqlxBindable for Tags {
+	fn bind_query<'q>(
+		&self,
+		query: sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments>,
+	) -> sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments> {
+		query.bind(self.0.clone())
+	}
+}
+
+/// Filters and sort of the quote list (`?sort=popular&lang=es&collection_id=100&status=pending`).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuoteFilter {
+    pub sort: Option<QuoteSort>, // newest by default, the collection order with collection_id
+    pub lang: Option<String>, // primary language match, `es` lists `es` and `es-MX`
+    pub collection_id: Option<i64>, // quotes of a collection the user can see
+    pub status: Option<QuoteStatus>, // approved by default, others only list the user quotes (all for moderators)
+}
+
+impl QuoteFilter {
+    /// The filter with its lang as a normalized BCP 47 tag (what FILTER_WHERE expects).
+    pub fn normalized(&self) -> Result<QuoteFilter, model::Error2> {
+        let lang = self.lang.as_deref().map(normalize_lang_tag).transpose()?;
+        Ok(QuoteFilter { lang, ..self.clone() })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteSort {
+    #[default]
+    Newest,
+    Popular, // most favorited first
+}
+
+impl QuoteSort {
+    fn order_by(self) -> &'static str {
+        match self {
+            QuoteSort::Newest => "id DESC",
+            QuoteSort::Popular => "favorite_count DESC, id DESC",
+        }
+    }
+}
+
+/// Moderation state. Quotes not approved are only visible to their creator and the moderators.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[sqlx(type_name = "quote_status_enum")]
 #[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum QuoteStatus {
-    Open,
-    Close,
+    Pending,
+    Approved,
+    Rejected,
+    Hidden, // reported too many times (see ReportMac)
 }
 sqlb::bindable!(QuoteStatus);
-// *** ***
 // endregion: Quote Types
 
+// region: Quote Patch Documents
+/// Author stored when none is given (matches the column default).
+pub const DEFAULT_AUTHOR: &str = "unknown";
+
+impl QuotePatch {
+	/// Members of the quote representation that can be patched.
+	pub const PATCHABLE: &'static [&'static str] = &["quote", "author", "source", "locator", "tags", "lang"];
+
+	/// Build a patch from an RFC 7396 merge patch, where `null` clears a member
+	/// (`source` and `locator` to NULL, `tags` to empty, `author` back to "unknown", `lang` to "und").
+	pub fn from_merge_patch(patch: Value) -> Result<QuotePatch, model::Error2> {
+		let Value::Object(members) = patch else {
+			return Err(model::Error2::InvalidPatch("Merge patch must be a JSON object".to_string()));
+		};
+
+		let mut data = QuotePatch::default();
+		for (name, value) in members {
+			match (name.as_str(), value) {
+				("quote", Value::Null) => return Err(model::Error2::InvalidPatch("'quote' cannot be cleared".to_string())),
+				("quote", value) => data.quote = Some(string_member(&name, value)?),
+				("author", Value::Null) => data.author = Some(DEFAULT_AUTHOR.to_string()),
+				("author", value) => data.author = Some(string_member(&name, value)?),
+				("source", Value::Null) => data.source = Some(Nullable::Null),
+				("source", value) => data.source = Some(Nullable::Value(string_member(&name, value)?)),
+				("locator", Value::Null) => data.locator = Some(Nullable::Null),
+				("locator", value) => data.locator = Some(Nullable::Value(string_member(&name, value)?)),
+				("tags", Value::Null) => data.tags = Some(Tags::default()),
+				("tags", Value::Array(tags)) => {
+					let tags = tags.into_iter().map(|tag| string_member("tags", tag)).collect::<Result<_, _>>()?;
+					data.tags = Some(Tags(tags));
+				}
+				("tags", _) => return Err(model::Error2::InvalidPatch("'tags' must be an array of strings".to_string())),
+				("lang", Value::Null) => data.lang = Some(UNDETERMINED_LANG.to_string()),
+				("lang", value) => data.lang = Some(string_member(&name, value)?),
+				(name, _) => return Err(model::Error2::InvalidPatch(format!("unsupported path '/{}'", name))),
+			}
+		}
+
+		Ok(data)
+	}
+
+	/// Build a full replacement patch from a patched quote document. Missing members are cleared.
+	fn from_doc(mut doc: Value) -> Result<QuotePatch, model::Error2> {
+		for name in Self::PATCHABLE {
+			if doc.get(name).is_none() {
+				doc[*name] = Value::Null;
+			}
+		}
+		Self::from_merge_patch(doc)
+	}
+}
+
+fn string_member(name: &str, value: Value) -> Result<String, model::Error2> {
+	match value {
+		Value::String(val) => Ok(val),
+		_ => Err(model::Error2::InvalidPatch(format!("'{}' must be a string", name))),
+	}
+}
+// endregion: Quote Patch Documents
+
 
 // region: QuoteModelAccessController
+// Every operation checks the role of the user (model::permission), whatever the caller (web, command line).
 pub struct QuoteMac;
 
 impl QuoteMac {
-    const TABLE: &'static str = "quote";
-    const COLUMNS: &'static [&'static str] = &["id", "cid", "quote", "author"];
+    pub(super) const TABLE: &'static str = "quote";
+    pub(super) const COLUMNS: &'static [&'static str] = &["id", "cid", "quote", "author", "source", "locator", "tags", "lang", "original_id", "translator", "version", "favorite_count", "status", "moderation_reason"];
+    // `is_favorite` column for the user bound at $1
+    pub(super) const IS_FAVORITE: &'static str =
+        "EXISTS(SELECT 1 FROM favorite f WHERE f.quote_id = quote.id AND f.user_id = $1) AS is_favorite";
 }
 
 impl QuoteMac {
     pub async fn create(db: &Db, utx: &UserCtx, data: QuotePatch) -> Result<Quote, model::Error2> {
+        ensure(utx, Action::CreateQuote)?;
         //let sql = "INSERT INTO quote (cid, title) VALUES ($1, $2) returning id, cid, quote, author";
         //let query = sqlx::query_as::<_, Quote>(&sql).bind(123 as i64).bind(data.title.unwrap_or_else(|| "untitled".to_string()));
+        let data = with_lang(data)?;
         let mut fields = data.fields();
-        fields.push(("cid", 123).into());
+        fields.push(("cid", utx.user_id).into());
+        fields.push(("status", initial_status(utx)).into());
         let sb = sqlb::insert().table(Self::TABLE).data(fields).returning(Self::COLUMNS);
 
         // Execute the query
@@ -58,13 +212,37 @@ impl QuoteMac {
         Ok(quote)
     }
 
-    pub async fn get(db: &Db, _utx: &UserCtx, id: i64) -> Result<Quote, model::Error2> {
-        
+    /// Create the quotes in one transaction (all or nothing).
+    pub async fn create_many(db: &Db, utx: &UserCtx, data: Vec<QuotePatch>) -> Result<Vec<Quote>, model::Error2> {
+        ensure(utx, Action::CreateQuote)?;
+        let mut tx = db.begin().await?;
+        let mut quotes = Vec::with_capacity(data.len());
+        for data in data {
+            let data = with_lang(data)?;
+            let mut fields = data.fields();
+            fields.push(("cid", utx.user_id).into());
+            fields.push(("status", initial_status(utx)).into());
+            let sb = sqlb::insert().table(Self::TABLE).data(fields).returning(Self::COLUMNS);
+            quotes.push(sb.fetch_one(&mut tx).await?);
+        }
+        tx.commit().await?;
+
+        Ok(quotes)
+    }
+
+    pub async fn get(db: &Db, utx: &UserCtx, id: i64) -> Result<Quote, model::Error2> {
+        ensure(utx, Action::ReadQuotes)?;
         let sb = sqlb::select().table(Self::TABLE).columns(Self::COLUMNS).and_where_eq("id", id);
 
         let result = sb.fetch_one(db).await;
 
-        handle_fetch_one_result(result, Self::TABLE, id)
+        let quote: Quote = handle_fetch_one_result(result, Self::TABLE, id)?;
+        // not published yet (or rejected), as if it did not exist for the others
+        if quote.status != QuoteStatus::Approved && quote.cid != utx.user_id && !utx.is_moderator() {
+            return Err(model::Error2::EntityNotFound(Self::TABLE, id.to_string()));
+        }
+
+        Ok(quote)
         //let quote = sb.fetch_one(db).await.map_err(|sqlx_error| match sqlx_error {
         //    sqlx::Error::RowNotFound =>model::Error2::EntityNotFound(Self::TABLE, id.to_string()),
         //    other => model::Error2::SqlxError(other)
@@ -73,60 +251,263 @@ impl QuoteMac {
         //Ok(quote)
     }
 
-    pub async fn update(db: &Db, utx: &UserCtx, id: i64, data: QuotePatch) -> Result<Quote, model::Error2> {
+    /// Update the quote. When `expected_version` is given, the update only applies if the
+    /// stored version still matches, otherwise `Error2::QuoteVersionMismatch` carries the current quote.
+    pub async fn update(db: &Db, utx: &UserCtx, id: i64, mut data: QuotePatch, expected_version: Option<i64>) -> Result<Quote, model::Error2> {
+		let current = Self::get(db, utx, id).await?;
+		ensure_owned(utx, Action::UpdateQuote, current.cid)?;
+		if let Some(lang) = data.lang.take() {
+			data.lang = Some(normalize_lang_tag(&lang)?);
+		}
 		let mut fields = data.fields();
-		// augment the fields with the cid/ctime
+		// an untrusted edit goes back to moderation, as a new quote would,
+		// except a quote hidden by its reports, which stays hidden until a moderator resolves them
+		if !utx.trusted && !fields.is_empty() {
+			fields.push(("status", Raw("CASE WHEN status = 'hidden' THEN status ELSE 'pending' END")).into());
+			fields.push(("moderation_reason", Raw("CASE WHEN status = 'hidden' THEN moderation_reason END")).into());
+		}
+		// augment the fields with the mid/mtime (ctime stays the creation time)
 		fields.push(("mid", utx.user_id).into()); //Why does this give a column not found error when pushing to the Database?
-		fields.push(("ctime", Raw("now()")).into());
+		fields.push(("mtime", Raw("now()")).into());
+		fields.push(("version", Raw("version + 1")).into());
 
-		let sb = sqlb::update()
+		let mut sb = sqlb::update()
 			.table(Self::TABLE)
 			.data(fields)
 			.and_where_eq("id", id)
 			.returning(Self::COLUMNS);
+		if let Some(version) = expected_version {
+			sb = sb.and_where_eq("version", version);
+		}
 
 		let result = sb.fetch_one(db).await;
 
-		handle_fetch_one_result(result, Self::TABLE, id)
+		handle_versioned_result(db, utx, result, id, expected_version).await
 	}
 
-    pub async fn list(db: &Db, _utx: &UserCtx) -> Result<Vec<Quote>, model::Error2> {
+    /// Apply RFC 6902 operations on the quote representation, then update it at the version it was read.
+    pub async fn patch(db: &Db, utx: &UserCtx, id: i64, ops: &[PatchOp], expected_version: Option<i64>) -> Result<Quote, model::Error2> {
+		let current = Self::get(db, utx, id).await?;
+		if expected_version.is_some_and(|version| version != current.version) {
+			return Err(model::Error2::QuoteVersionMismatch(Box::new(current)));
+		}
+
+		let mut doc = json!({
+			"quote": current.quote,
+			"author": current.author,
+			"source": current.source,
+			"locator": current.locator,
+			"tags": current.tags,
+			"lang": current.lang,
+		});
+		apply_json_patch(&mut doc, ops, QuotePatch::PATCHABLE)?;
+		let data = QuotePatch::from_doc(doc)?;
+
+		Self::update(db, utx, id, data, Some(current.version)).await
+	}
+
+    pub async fn list(db: &Db, utx: &UserCtx, filter: &QuoteFilter, list_options: &ListOptions) -> Result<Vec<Quote>, model::Error2> {
+        ensure(utx, Action::ReadQuotes)?;
         //let sql = "SELECT id, cid, title, status FROM quote ORDER BY id DESC";
-        let sb = sqlb::select().table(Self::TABLE).columns(Self::COLUMNS).order_by("!id");
+        let filter = &filter.normalized()?;
+        let sql = Self::list_sql(filter);
 
         // Build the sqlx-querey
-        //let query = sqlx::query_as(&sql);
+        let query = sqlx::query_as::<_, Quote>(&sql)
+            .bind(utx.user_id)
+            .bind(filter.lang.as_deref())
+            .bind(filter.collection_id)
+            .bind(filter.status)
+            .bind(utx.is_moderator())
+            .bind(Some(list_options.limit()))
+            .bind(list_options.offset());
         // Execute the query
-        //let quotess = query.fetch_all(db).await?;
-        let quotes = sb.fetch_all(db).await?;
+        let quotes = query.fetch_all(db).await?;
 
         Ok(quotes)
     }
 
-    pub async fn delete(db: &Db, _utx: &UserCtx, id: i64) -> Result<Quote, model::Error2> {
-		let sb = sqlb::delete()
+    /// Stream all the quotes matching the filter (no pagination), row by row as the db sends them.
+    /// The permission and the filter are checked before, so the stream items only carry the db errors.
+    /// The query runs in its own task, and waits for the consumer when the channel is full.
+    pub fn stream(
+        db: &Db,
+        utx: &UserCtx,
+        filter: &QuoteFilter,
+    ) -> Result<impl Stream<Item = Result<Quote, model::Error2>> + Send + 'static, model::Error2> {
+        ensure(utx, Action::ReadQuotes)?;
+        let filter = filter.normalized()?;
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let (db, user_id, moderator) = (db.clone(), utx.user_id, utx.is_moderator());
+
+        tokio::spawn(async move {
+            let sql = Self::list_sql(&filter);
+            let mut rows = sqlx::query_as::<_, Quote>(&sql)
+                .bind(user_id)
+                .bind(filter.lang.as_deref())
+                .bind(filter.collection_id)
+                .bind(filter.status)
+                .bind(moderator)
+                .bind(None::<i64>) // LIMIT NULL, all rows
+                .bind(0_i64)
+                .fetch(&db);
+            while let Some(row) = rows.next().await {
+                // stop when the consumer is gone (e.g., client disconnected)
+                if sender.send(row.map_err(model::Error2::from)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(stream::unfold(receiver, |mut receiver| async move { receiver.recv().await.map(|row| (row, receiver)) }))
+    }
+
+    /// Where clause of the filter, on the quotes the user can see (shared with the stats).
+    /// Binds: $1 user id, $2 lang (normalized, see `QuoteFilter::normalized`), $3 collection id, $4 status, $5 is moderator
+    pub(super) const FILTER_WHERE: &'static str = "status = COALESCE($4::quote_status_enum, 'approved') \
+        AND (status = 'approved' OR cid = $1 OR $5) \
+        AND ($2::text IS NULL OR lang = $2 OR left(lang, length($2) + 1) = $2 || '-') \
+        AND ($3::bigint IS NULL OR id IN (SELECT cq.quote_id FROM collection_quote cq \
+            JOIN collection c ON c.id = cq.collection_id \
+            WHERE c.id = $3 AND (c.cid = $1 OR c.visibility <> 'private')))";
+
+    /// Collection order, when listing a collection without an explicit sort.
+    const COLLECTION_ORDER_BY: &'static str =
+        "(SELECT cq.position FROM collection_quote cq WHERE cq.collection_id = $3 AND cq.quote_id = quote.id), id DESC";
+
+    // Binds: FILTER_WHERE ones, then $6 limit (NULL for all), $7 offset
+    fn list_sql(filter: &QuoteFilter) -> String {
+        let order_by = match (filter.sort, filter.collection_id) {
+            (None, Some(_)) => Self::COLLECTION_ORDER_BY,
+            (sort, _) => sort.unwrap_or_default().order_by(),
+        };
+        format!(
+            "SELECT {}, {} FROM quote WHERE {} ORDER BY {} LIMIT $6 OFFSET $7",
+            Self::COLUMNS.join(", "),
+            Self::IS_FAVORITE,
+            Self::FILTER_WHERE,
+            order_by
+        )
+    }
+
+    /// Add a translation to a quote. Translations always link to the first original quote.
+    pub async fn create_translation(db: &Db, utx: &UserCtx, id: i64, data: TranslationForCreate) -> Result<Quote, model::Error2> {
+        ensure(utx, Action::CreateQuote)?;
+        let original = Self::get(db, utx, id).await?;
+        let original = match original.original_id {
+            Some(original_id) => Self::get(db, utx, original_id).await?,
+            None => original,
+        };
+        let lang = normalize_lang_tag(&data.lang)?;
+        if original.lang == lang || Self::list_translations(db, utx, original.id).await?.iter().any(|t| t.lang == lang) {
+            return Err(model::Error2::InvalidData(format!("quote {} already exists in '{}'", original.id, lang)));
+        }
+
+        let patch = QuotePatch {
+            quote: Some(data.quote),
+            author: Some(original.author),
+            source: original.source.map(Nullable::Value),
+            locator: original.locator.map(Nullable::Value),
+            tags: Some(Tags(original.tags)),
+            lang: Some(lang),
+        };
+        let mut fields = patch.fields();
+        fields.push(("cid", utx.user_id).into());
+        fields.push(("status", initial_status(utx)).into());
+        fields.push(("original_id", original.id).into());
+        if let Some(translator) = data.translator {
+            fields.push(("translator", translator).into());
+        }
+        let sb = sqlb::insert().table(Self::TABLE).data(fields).returning(Self::COLUMNS);
+
+        Ok(sb.fetch_one(db).await?)
+    }
+
+    /// The translations of the quote (or of its original when the quote is itself a translation).
+    pub async fn list_translations(db: &Db, utx: &UserCtx, id: i64) -> Result<Vec<Quote>, model::Error2> {
+        let quote = Self::get(db, utx, id).await?;
+        let original_id = quote.original_id.unwrap_or(quote.id);
+
+        let sql = format!(
+            "SELECT {} FROM quote WHERE original_id = $1 AND (status = 'approved' OR cid = $2 OR $3) ORDER BY id",
+            Self::COLUMNS.join(", ")
+        );
+        let query = sqlx::query_as::<_, Quote>(&sql).bind(original_id).bind(utx.user_id).bind(utx.is_moderator());
+
+        Ok(query.fetch_all(db).await?)
+    }
+
+    /// Get the quote in the best language of `preferred` (BCP 47 ranges, by decreasing preference),
+    /// among the original and its translations. Falls back to the requested quote.
+    pub async fn get_in_lang(db: &Db, utx: &UserCtx, id: i64, preferred: &[String]) -> Result<Quote, model::Error2> {
+        let quote = Self::get(db, utx, id).await?;
+        let mut family = Self::list_translations(db, utx, id).await?;
+        match quote.original_id {
+            Some(original_id) => family.insert(0, Self::get(db, utx, original_id).await?),
+            None => family.insert(0, quote.clone()),
+        }
+
+        // the requested quote wins when its language is as good as the others
+        let langs: Vec<&str> = std::iter::once(quote.lang.as_str()).chain(family.iter().map(|q| q.lang.as_str())).collect();
+        match best_lang_match(&langs, preferred) {
+            None | Some(0) => Ok(quote),
+            Some(idx) => Ok(family.swap_remove(idx - 1)),
+        }
+    }
+
+    pub async fn delete(db: &Db, utx: &UserCtx, id: i64, expected_version: Option<i64>) -> Result<Quote, model::Error2> {
+		let current = Self::get(db, utx, id).await?;
+		ensure_owned(utx, Action::DeleteQuote, current.cid)?;
+		let mut sb = sqlb::delete()
 			.table(Self::TABLE)
 			.returning(Self::COLUMNS)
 			.and_where_eq("id", id);
+		if let Some(version) = expected_version {
+			sb = sb.and_where_eq("version", version);
+		}
 
 		let result = sb.fetch_one(db).await;
 
-		handle_fetch_one_result(result, Self::TABLE, id)
+		handle_versioned_result(db, utx, result, id, expected_version).await
 	}
 
 }
 // endregion: QuoteModelAccessController
 
 // region:    Utils
-fn handle_fetch_one_result(
+/// Quotes of trusted users are published right away, the others wait for a moderator.
+fn initial_status(utx: &UserCtx) -> QuoteStatus {
+	match utx.trusted {
+		true => QuoteStatus::Approved,
+		false => QuoteStatus::Pending,
+	}
+}
+
+/// Normalize the lang of a quote to create, or detect it when not given.
+fn with_lang(mut data: QuotePatch) -> Result<QuotePatch, model::Error2> {
+	data.lang = match (data.lang.take(), &data.quote) {
+		(Some(lang), _) => Some(normalize_lang_tag(&lang)?),
+		(None, Some(quote)) => Some(detect_lang(quote)),
+		(None, None) => None,
+	};
+	Ok(data)
+}
+
+/// When a versioned statement matched no row, tell apart a missing quote from a stale version.
+async fn handle_versioned_result(
+	db: &Db,
+	utx: &UserCtx,
 	result: Result<Quote, sqlx::Error>,
-	typ: &'static str,
 	id: i64,
+	expected_version: Option<i64>,
 ) -> Result<Quote, model::Error2> {
-	result.map_err(|sqlx_error| match sqlx_error {
-		sqlx::Error::RowNotFound => model::Error2::EntityNotFound(typ, id.to_string()),
-		other => model::Error2::SqlxError(other),
-	})
+	match (result, expected_version) {
+		(Err(sqlx::Error::RowNotFound), Some(_)) => {
+			let current = QuoteMac::get(db, utx, id).await?;
+			Err(model::Error2::QuoteVersionMismatch(Box::new(current)))
+		}
+		(result, _) => handle_fetch_one_result(result, QuoteMac::TABLE, id),
+	}
 }
 // endregion: Utils
 
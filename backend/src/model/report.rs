@@ -0,0 +1,208 @@
+use super::db::Db;
+use super::permission::{ensure, Action};
+use super::{ListOptions, QuoteMac, QuoteStatus};
+use crate::config::config;
+use crate::model;
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+
+// region: Report Types
+/// A report of a quote, with its times in unix seconds.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+	pub id: i64,
+	pub quote_id: i64,
+	pub cid: i64, // reporter id
+	pub ctime: i64,
+	pub reason: ReportReason,
+	pub comment: Option<String>,
+	// resolution, None while open
+	pub outcome: Option<ReportOutcome>,
+	pub resolution_note: Option<String>,
+	pub resolved_by: Option<i64>,
+	pub resolved_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportForCreate {
+	pub reason: ReportReason,
+	pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportResolution {
+	pub outcome: ReportOutcome,
+	pub note: Option<String>, // required to remove the quote, shown to its creator
+}
+
+/// The open reports of a quote, for the moderators.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSummary {
+	pub quote_id: i64,
+	pub quote: String,
+	pub status: QuoteStatus,
+	pub report_count: i64,
+	pub reasons: Vec<String>,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "report_reason_enum")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportReason {
+	Offensive,
+	Misattributed,
+	Duplicate,
+	Other,
+}
+
+/// `dismissed` and `corrected` publish the quote again (when hidden), `removed` rejects it.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "report_outcome_enum")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportOutcome {
+	Dismissed,
+	Corrected,
+	Removed,
+}
+// endregion: Report Types
+
+// region: ReportModelAccessController
+// Reports are resolved per quote (all its open reports at once). Resolved reports are kept, with the
+// outcome, note and moderator, as the audit trail of the quote.
+pub struct ReportMac;
+
+impl ReportMac {
+	const COLUMNS: &'static str = "id, quote_id, cid, extract(epoch FROM ctime)::bigint AS ctime, reason, comment, outcome, \
+		resolution_note, resolved_by, extract(epoch FROM resolved_at)::bigint AS resolved_at";
+
+	/// Report the quote. A user has at most one open report per quote, and `report_rate_limit` reports per hour.
+	/// When the quote reaches `report_hide_threshold` open reports, it is hidden until resolved.
+	pub async fn create(db: &Db, utx: &UserCtx, quote_id: i64, data: ReportForCreate) -> Result<Report, model::Error2> {
+		// visibility check
+		QuoteMac::get(db, utx, quote_id).await?;
+		let config = config();
+		let comment = data.comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+
+		// the reporter row lock makes the checks and the insert atomic (concurrent reports wait their turn)
+		let mut tx = db.begin().await?;
+		sqlx::query("SELECT id FROM user_account WHERE id = $1 FOR UPDATE")
+			.bind(utx.user_id)
+			.execute(&mut tx)
+			.await?;
+		let (last_hour,): (i64,) = sqlx::query_as("SELECT count(*) FROM report WHERE cid = $1 AND ctime > now() - interval '1 hour'")
+			.bind(utx.user_id)
+			.fetch_one(&mut tx)
+			.await?;
+		if last_hour >= config.report_rate_limit {
+			return Err(model::Error2::RateLimited(format!("{} reports per hour", config.report_rate_limit)));
+		}
+		let (open,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM report WHERE quote_id = $1 AND cid = $2 AND outcome IS NULL)")
+			.bind(quote_id)
+			.bind(utx.user_id)
+			.fetch_one(&mut tx)
+			.await?;
+		if open {
+			return Err(model::Error2::InvalidData(format!("quote {} is already reported by user {}", quote_id, utx.user_id)));
+		}
+
+		let sql = format!("INSERT INTO report (quote_id, cid, reason, comment) VALUES ($1, $2, $3, $4) RETURNING {}", Self::COLUMNS);
+		let report = sqlx::query_as::<_, Report>(&sql)
+			.bind(quote_id)
+			.bind(utx.user_id)
+			.bind(data.reason)
+			.bind(comment)
+			.fetch_one(&mut tx)
+			.await?;
+
+		let (open_count,): (i64,) = sqlx::query_as("SELECT count(*) FROM report WHERE quote_id = $1 AND outcome IS NULL")
+			.bind(quote_id)
+			.fetch_one(&mut tx)
+			.await?;
+		if config.report_hide_threshold > 0 && open_count >= config.report_hide_threshold {
+			sqlx::query("UPDATE quote SET status = 'hidden', version = version + 1 WHERE id = $1 AND status = 'approved'")
+				.bind(quote_id)
+				.execute(&mut tx)
+				.await?;
+		}
+		tx.commit().await?;
+
+		Ok(report)
+	}
+
+	/// The quotes with open reports, most reported first.
+	pub async fn list_open(db: &Db, utx: &UserCtx, list_options: &ListOptions) -> Result<Vec<ReportSummary>, model::Error2> {
+		ensure(utx, Action::Moderate)?;
+
+		let sql = "SELECT r.quote_id, q.quote, q.status, count(*) AS report_count, array_agg(DISTINCT r.reason::text) AS reasons \
+			FROM report r JOIN quote q ON q.id = r.quote_id WHERE r.outcome IS NULL \
+			GROUP BY r.quote_id, q.quote, q.status ORDER BY report_count DESC, min(r.id) LIMIT $1 OFFSET $2";
+		let query = sqlx::query_as::<_, ReportSummary>(sql).bind(list_options.limit()).bind(list_options.offset());
+
+		Ok(query.fetch_all(db).await?)
+	}
+
+	/// All the reports of the quote, open and resolved, oldest first.
+	pub async fn list_for_quote(db: &Db, utx: &UserCtx, quote_id: i64) -> Result<Vec<Report>, model::Error2> {
+		ensure(utx, Action::Moderate)?;
+
+		let sql = format!("SELECT {} FROM report WHERE quote_id = $1 ORDER BY id", Self::COLUMNS);
+		Ok(sqlx::query_as::<_, Report>(&sql).bind(quote_id).fetch_all(db).await?)
+	}
+
+	/// Resolve the open reports of the quote, and publish it again or reject it, per the outcome.
+	pub async fn resolve(db: &Db, utx: &UserCtx, quote_id: i64, data: ReportResolution) -> Result<Vec<Report>, model::Error2> {
+		ensure(utx, Action::Moderate)?;
+		let note = data.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+		if data.outcome == ReportOutcome::Removed && note.is_none() {
+			return Err(model::Error2::InvalidData("removing a quote needs a note".to_string()));
+		}
+
+		let mut tx = db.begin().await?;
+		let sql = format!(
+			"UPDATE report SET outcome = $2, resolution_note = $3, resolved_by = $4, resolved_at = now() \
+			WHERE quote_id = $1 AND outcome IS NULL RETURNING {}",
+			Self::COLUMNS
+		);
+		let mut reports = sqlx::query_as::<_, Report>(&sql)
+			.bind(quote_id)
+			.bind(data.outcome)
+			.bind(note.as_deref())
+			.bind(utx.user_id)
+			.fetch_all(&mut tx)
+			.await?;
+		if reports.is_empty() {
+			return Err(model::Error2::InvalidData(format!("quote {} has no open report", quote_id)));
+		}
+
+		let query = match data.outcome {
+			ReportOutcome::Removed => sqlx::query(
+				"UPDATE quote SET status = 'rejected', moderation_reason = $3, moderated_by = $2, moderated_at = now(), \
+				version = version + 1 WHERE id = $1",
+			)
+			.bind(quote_id)
+			.bind(utx.user_id)
+			.bind(note.as_deref()),
+			// publish again when hidden by the reports
+			ReportOutcome::Dismissed | ReportOutcome::Corrected => sqlx::query(
+				"UPDATE quote SET status = 'approved', moderated_by = $2, moderated_at = now(), version = version + 1 \
+				WHERE id = $1 AND status = 'hidden'",
+			)
+			.bind(quote_id)
+			.bind(utx.user_id),
+		};
+		query.execute(&mut tx).await?;
+		tx.commit().await?;
+
+		reports.sort_by_key(|r| r.id);
+		Ok(reports)
+	}
+}
+// endregion: ReportModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_report.rs"]
+mod tests;
+// endregion: Test
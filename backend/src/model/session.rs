@@ -0,0 +1,109 @@
+use super::db::Db;
+use super::handle_fetch_one_result;
+use crate::config::config;
+use crate::model;
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+
+// region: Session Types
+/// A login session (a device), with its times in unix seconds.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+	pub id: i64,
+	pub user_id: i64,
+	pub ctime: i64,
+	pub last_seen: i64,
+	pub ip: Option<String>,
+	pub user_agent: Option<String>,
+	pub expires: i64,
+	/// The session of the request (only set by the list)
+	#[sqlx(default)]
+	pub current: bool,
+}
+
+/// Where the login comes from.
+#[derive(Debug, Clone, Default)]
+pub struct SessionForCreate {
+	pub ip: Option<String>,
+	pub user_agent: Option<String>,
+}
+// endregion: Session Types
+
+// region: SessionModelAccessController
+// The auth tokens reference their session, valid until it expires (QUOTE_SESSION_TTL_SECS) or is deleted.
+pub struct SessionMac;
+
+impl SessionMac {
+	const TABLE: &'static str = "session";
+	const COLUMNS: &'static str = "id, user_id, extract(epoch FROM ctime)::bigint AS ctime, \
+		extract(epoch FROM last_seen)::bigint AS last_seen, ip, user_agent, extract(epoch FROM expires)::bigint AS expires";
+
+	pub async fn create(db: &Db, user_id: i64, data: SessionForCreate) -> Result<Session, model::Error2> {
+		let sql = format!(
+			"INSERT INTO session (user_id, ip, user_agent, expires) VALUES ($1, $2, $3, now() + $4 * interval '1 second') \
+			RETURNING {}",
+			Self::COLUMNS
+		);
+		let session = sqlx::query_as::<_, Session>(&sql)
+			.bind(user_id)
+			.bind(data.ip)
+			.bind(data.user_agent)
+			.bind(config().session_ttl_secs as f64)
+			.fetch_one(db)
+			.await?;
+
+		Ok(session)
+	}
+
+	/// Mark the session as seen, false when it expired or was deleted.
+	pub async fn touch(db: &Db, user_id: i64, id: i64) -> Result<bool, model::Error2> {
+		let result = sqlx::query("UPDATE session SET last_seen = now() WHERE id = $1 AND user_id = $2 AND expires > now()")
+			.bind(id)
+			.bind(user_id)
+			.execute(db)
+			.await?;
+
+		Ok(result.rows_affected() == 1)
+	}
+
+	/// The active sessions of the user, last seen first.
+	pub async fn list(db: &Db, utx: &UserCtx) -> Result<Vec<Session>, model::Error2> {
+		let sql = format!(
+			"SELECT {}, id = $2 AS current FROM session WHERE user_id = $1 AND expires > now() ORDER BY last_seen DESC, id DESC",
+			Self::COLUMNS
+		);
+		let sessions = sqlx::query_as::<_, Session>(&sql).bind(utx.user_id).bind(utx.session_id).fetch_all(db).await?;
+
+		Ok(sessions)
+	}
+
+	/// Revoke a session of the user, its tokens are refused from now on.
+	pub async fn delete(db: &Db, utx: &UserCtx, id: i64) -> Result<Session, model::Error2> {
+		let sql = format!("DELETE FROM session WHERE id = $1 AND user_id = $2 RETURNING {}", Self::COLUMNS);
+		let result = sqlx::query_as::<_, Session>(&sql).bind(id).bind(utx.user_id).fetch_one(db).await;
+
+		handle_fetch_one_result(result, Self::TABLE, id)
+	}
+
+	/// Revoke all the sessions of the user (log out everywhere).
+	pub async fn delete_all(db: &Db, utx: &UserCtx) -> Result<Vec<Session>, model::Error2> {
+		let sql = format!("DELETE FROM session WHERE user_id = $1 RETURNING {}", Self::COLUMNS);
+		let sessions = sqlx::query_as::<_, Session>(&sql).bind(utx.user_id).fetch_all(db).await?;
+
+		Ok(sessions)
+	}
+
+	/// Delete the expired sessions, returns their number.
+	pub async fn delete_expired(db: &Db) -> Result<u64, model::Error2> {
+		let result = sqlx::query("DELETE FROM session WHERE expires <= now()").execute(db).await?;
+
+		Ok(result.rows_affected())
+	}
+}
+// endregion: SessionModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_session.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,207 @@
+use super::db::Db;
+use super::{QuoteFilter, QuoteMac};
+use crate::model;
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const STATS_TOP_DEFAULT: i64 = 10;
+const STATS_TOP_MAX: i64 = 100;
+/// Window of the most active contributors.
+const CONTRIBUTORS_WINDOW_DAYS: i32 = 30;
+
+// region: Stats Types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteStats {
+	pub total: i64,
+	pub by_author: Vec<StatCount>,
+	pub by_creator: Vec<StatCount>, // key is the creator id
+	pub by_tag: Vec<StatCount>,
+	pub by_period: Vec<PeriodCount>, // oldest first, the last `top` periods with quotes
+	pub top_contributors: Vec<StatCount>, // creators of the last 30 days
+	pub growth: Growth,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct StatCount {
+	pub key: String,
+	pub count: i64,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodCount {
+	pub period: String, // start of the period, `2024-05-01`
+	pub count: i64,
+	pub total: i64, // running total at the end of the period
+}
+
+/// Quotes created in the current period so far, against the whole previous one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Growth {
+	pub current: i64,
+	pub previous: i64,
+	pub rate: Option<f64>, // (current - previous) / previous, None when previous is 0
+}
+
+/// Options of the stats (`?interval=week&top=5`), with the quote list filters.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StatsOptions {
+	pub interval: Option<StatsInterval>,
+	pub top: Option<i64>, // number of authors, creators, tags and periods
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsInterval {
+	#[default]
+	Day,
+	Week,
+	Month,
+}
+
+impl StatsInterval {
+	fn date_trunc(self) -> &'static str {
+		match self {
+			StatsInterval::Day => "day",
+			StatsInterval::Week => "week",
+			StatsInterval::Month => "month",
+		}
+	}
+}
+
+impl StatsOptions {
+	fn top(&self) -> i64 {
+		self.top.unwrap_or(STATS_TOP_DEFAULT).clamp(1, STATS_TOP_MAX)
+	}
+}
+// endregion: Stats Types
+
+// region: StatsModelAccessController
+// All the aggregates are on the quotes of the list filter (QuoteMac::FILTER_WHERE, binds $1 to $5).
+pub struct StatsMac;
+
+impl StatsMac {
+	pub async fn stats(db: &Db, utx: &UserCtx, filter: &QuoteFilter, options: &StatsOptions) -> Result<QuoteStats, model::Error2> {
+		let filter = &filter.normalized()?;
+		let top = options.top();
+		let interval = options.interval.unwrap_or_default().date_trunc();
+
+		let (total,): (i64,) = Self::query_as(&format!("SELECT count(*) FROM quote WHERE {}", QuoteMac::FILTER_WHERE), utx, filter)
+			.fetch_one(db)
+			.await?;
+
+		let by_author = Self::counts(db, utx, filter, "author", "", "", top).await?;
+		let by_creator = Self::counts(db, utx, filter, "cid::text", "", "", top).await?;
+		let by_tag = Self::counts(db, utx, filter, "tag", ", unnest(tags) AS tag", "", top).await?;
+		let window = format!(" AND ctime > now() - interval '{} days'", CONTRIBUTORS_WINDOW_DAYS);
+		let top_contributors = Self::counts(db, utx, filter, "cid::text", "", &window, top).await?;
+
+		// running totals over all the periods, then the last ones
+		let sql = format!(
+			"SELECT period, count, total FROM ( \
+				SELECT to_char(date_trunc('{interval}', ctime), 'YYYY-MM-DD') AS period, count(*) AS count, \
+				(sum(count(*)) OVER (ORDER BY date_trunc('{interval}', ctime)))::bigint AS total \
+				FROM quote WHERE {} GROUP BY date_trunc('{interval}', ctime) \
+				ORDER BY date_trunc('{interval}', ctime) DESC LIMIT $6 \
+			) periods ORDER BY period",
+			QuoteMac::FILTER_WHERE
+		);
+		let by_period = Self::query_as::<PeriodCount>(&sql, utx, filter).bind(top).fetch_all(db).await?;
+
+		let sql = format!(
+			"SELECT count(*) FILTER (WHERE ctime >= date_trunc('{interval}', now())), \
+			count(*) FILTER (WHERE ctime >= date_trunc('{interval}', now()) - interval '1 {interval}' \
+				AND ctime < date_trunc('{interval}', now())) \
+			FROM quote WHERE {}",
+			QuoteMac::FILTER_WHERE
+		);
+		let (current, previous): (i64, i64) = Self::query_as(&sql, utx, filter).fetch_one(db).await?;
+		let rate = (previous > 0).then(|| (current - previous) as f64 / previous as f64);
+
+		Ok(QuoteStats {
+			total,
+			by_author,
+			by_creator,
+			by_tag,
+			by_period,
+			top_contributors,
+			growth: Growth { current, previous, rate },
+		})
+	}
+
+	/// Top counts per `key`, with `from` and `and_where` extending the from and where clauses.
+	async fn counts(
+		db: &Db,
+		utx: &UserCtx,
+		filter: &QuoteFilter,
+		key: &str,
+		from: &str,
+		and_where: &str,
+		top: i64,
+	) -> Result<Vec<StatCount>, model::Error2> {
+		let sql = format!(
+			"SELECT {key} AS key, count(*) AS count FROM quote{from} WHERE {}{and_where} \
+			GROUP BY 1 ORDER BY count DESC, key LIMIT $6",
+			QuoteMac::FILTER_WHERE
+		);
+		Ok(Self::query_as::<StatCount>(&sql, utx, filter).bind(top).fetch_all(db).await?)
+	}
+
+	fn query_as<'q, O>(
+		sql: &'q str,
+		utx: &UserCtx,
+		filter: &'q QuoteFilter,
+	) -> sqlx::query::QueryAs<'q, sqlx::Postgres, O, sqlx::postgres::PgArguments>
+	where
+		O: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>,
+	{
+		sqlx::query_as::<_, O>(sql)
+			.bind(utx.user_id)
+			.bind(filter.lang.as_deref())
+			.bind(filter.collection_id)
+			.bind(filter.status)
+			.bind(utx.is_moderator())
+	}
+}
+// endregion: StatsModelAccessController
+
+// region: Stats Cache
+/// Stats computed less than `ttl` ago, per user and options, so a dashboard polling them stays cheap.
+pub struct StatsCache {
+	ttl: Duration,
+	entries: Mutex<HashMap<String, (Instant, QuoteStats)>>,
+}
+
+impl StatsCache {
+	pub fn new(ttl: Duration) -> StatsCache {
+		StatsCache { ttl, entries: Mutex::default() }
+	}
+
+	pub async fn stats(&self, db: &Db, utx: &UserCtx, filter: &QuoteFilter, options: &StatsOptions) -> Result<QuoteStats, model::Error2> {
+		// the visible quotes depend on the user (own quotes, private collections, moderator)
+		let filter = &filter.normalized()?;
+		let key = format!(
+			"{}|{}|{:?}|{:?}|{:?}|{:?}|{}",
+			utx.user_id, utx.is_moderator(), filter.lang, filter.collection_id, filter.status, options.interval, options.top()
+		);
+		if let Some((_, stats)) = self.entries.lock().unwrap().get(&key).filter(|(time, _)| time.elapsed() < self.ttl) {
+			return Ok(stats.clone());
+		}
+
+		let stats = StatsMac::stats(db, utx, filter, options).await?;
+		let mut entries = self.entries.lock().unwrap();
+		entries.retain(|_, (time, _)| time.elapsed() < self.ttl);
+		entries.insert(key, (Instant::now(), stats.clone()));
+
+		Ok(stats)
+	}
+}
+// endregion: Stats Cache
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_stats.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,130 @@
+use super::db::Db;
+use crate::model;
+
+// region: Totp Types
+/// The TOTP of a user, pending until a first code confirms its enrolment.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct UserTotp {
+	pub user_id: i64,
+	pub secret: String, // base32
+	pub enabled: bool,
+	pub last_step: Option<i64>,
+}
+// endregion: Totp Types
+
+// region: TotpModelAccessController
+// The codes are checked by security::totp, this only keeps the secrets and the used codes.
+pub struct TotpMac;
+
+impl TotpMac {
+	const COLUMNS: &'static str = "user_id, secret, enabled, last_step";
+
+	pub async fn get(db: &Db, user_id: i64) -> Result<Option<UserTotp>, model::Error2> {
+		let sql = format!("SELECT {} FROM user_totp WHERE user_id = $1", Self::COLUMNS);
+		let totp = sqlx::query_as::<_, UserTotp>(&sql).bind(user_id).fetch_optional(db).await?;
+
+		Ok(totp)
+	}
+
+	/// Start (or restart) the enrolment with a new secret, None when the TOTP is already enabled.
+	pub async fn create_pending(db: &Db, user_id: i64, secret: &str) -> Result<Option<UserTotp>, model::Error2> {
+		let sql = format!(
+			"INSERT INTO user_totp (user_id, secret) VALUES ($1, $2) \
+			ON CONFLICT (user_id) DO UPDATE SET secret = excluded.secret, last_step = NULL, ctime = now() WHERE NOT user_totp.enabled \
+			RETURNING {}",
+			Self::COLUMNS
+		);
+		let totp = sqlx::query_as::<_, UserTotp>(&sql).bind(user_id).bind(secret).fetch_optional(db).await?;
+
+		Ok(totp)
+	}
+
+	/// Record the time step of an accepted code, false when a code of this step (or a later one) was already used.
+	pub async fn use_step(db: &Db, user_id: i64, step: i64) -> Result<bool, model::Error2> {
+		let result = sqlx::query("UPDATE user_totp SET last_step = $2 WHERE user_id = $1 AND (last_step IS NULL OR last_step < $2)")
+			.bind(user_id)
+			.bind(step)
+			.execute(db)
+			.await?;
+
+		Ok(result.rows_affected() == 1)
+	}
+
+	/// Count a code to check, refused (RateLimited) while the user is locked out. Reaching `max_failures` codes without
+	/// an accepted one (see `reset_attempts`) locks the user out for `lockout_secs`. The count comes before the check,
+	/// under the row lock, so concurrent guesses cannot go beyond it.
+	pub async fn count_attempt(db: &Db, user_id: i64, max_failures: i64, lockout_secs: i64) -> Result<(), model::Error2> {
+		let mut tx = db.begin().await?;
+		let locked: Option<(bool,)> =
+			sqlx::query_as("SELECT coalesce(locked_until > now(), false) FROM user_totp WHERE user_id = $1 FOR UPDATE")
+				.bind(user_id)
+				.fetch_optional(&mut tx)
+				.await?;
+		if let Some((true,)) = locked {
+			return Err(model::Error2::RateLimited(format!("{} failed two-factor codes in a row, locked for {} seconds", max_failures, lockout_secs)));
+		}
+		// after a lockout, the count starts again
+		sqlx::query(
+			"UPDATE user_totp SET failed_attempts = CASE WHEN locked_until IS NULL THEN failed_attempts + 1 ELSE 1 END, \
+			locked_until = CASE WHEN locked_until IS NULL AND failed_attempts + 1 >= $2 THEN now() + $3 * interval '1 second' END \
+			WHERE user_id = $1",
+		)
+		.bind(user_id)
+		.bind(max_failures)
+		.bind(lockout_secs as f64)
+		.execute(&mut tx)
+		.await?;
+		tx.commit().await?;
+
+		Ok(())
+	}
+
+	/// Clear the count of the codes checked, once one is accepted.
+	pub async fn reset_attempts(db: &Db, user_id: i64) -> Result<(), model::Error2> {
+		sqlx::query("UPDATE user_totp SET failed_attempts = 0, locked_until = NULL WHERE user_id = $1")
+			.bind(user_id)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// Enable the pending TOTP, with new recovery codes (replacing the previous ones).
+	pub async fn enable(db: &Db, user_id: i64, recovery_code_hashes: &[String]) -> Result<(), model::Error2> {
+		let mut tx = db.begin().await?;
+		sqlx::query("UPDATE user_totp SET enabled = true WHERE user_id = $1").bind(user_id).execute(&mut tx).await?;
+		sqlx::query("DELETE FROM totp_recovery_code WHERE user_id = $1").bind(user_id).execute(&mut tx).await?;
+		for code_hash in recovery_code_hashes {
+			sqlx::query("INSERT INTO totp_recovery_code (user_id, code_hash) VALUES ($1, $2)")
+				.bind(user_id)
+				.bind(code_hash)
+				.execute(&mut tx)
+				.await?;
+		}
+		tx.commit().await?;
+
+		Ok(())
+	}
+
+	/// Remove the TOTP of the user, and its recovery codes.
+	pub async fn delete(db: &Db, user_id: i64) -> Result<(), model::Error2> {
+		let mut tx = db.begin().await?;
+		sqlx::query("DELETE FROM user_totp WHERE user_id = $1").bind(user_id).execute(&mut tx).await?;
+		sqlx::query("DELETE FROM totp_recovery_code WHERE user_id = $1").bind(user_id).execute(&mut tx).await?;
+		tx.commit().await?;
+
+		Ok(())
+	}
+
+	/// Mark the unused recovery code of the hash as used, false when there is none.
+	pub async fn use_recovery_code(db: &Db, user_id: i64, code_hash: &str) -> Result<bool, model::Error2> {
+		let result = sqlx::query("UPDATE totp_recovery_code SET used = now() WHERE user_id = $1 AND code_hash = $2 AND used IS NULL")
+			.bind(user_id)
+			.bind(code_hash)
+			.execute(db)
+			.await?;
+
+		Ok(result.rows_affected() > 0)
+	}
+}
+// endregion: TotpModelAccessController
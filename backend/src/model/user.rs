@@ -0,0 +1,269 @@
+use super::db::Db;
+use super::handle_fetch_one_result;
+use crate::model;
+use serde::{Deserialize, Serialize};
+
+// region: User Types
+/// A user account, as shown to the apis (never with the password hash).
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct UserAccount {
+	pub id: i64,
+	pub username: String,
+	pub email: String,
+	pub role: Role,
+	pub disabled: bool,
+	pub email_verified: bool,
+}
+
+/// What the login checks. Only for the security module.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct UserForAuth {
+	pub id: i64,
+	pub pwd_hash: String,
+	pub disabled: bool,
+}
+
+/// Roles by increasing rights, each one having the rights of the previous ones (see model::permission).
+#[derive(sqlx::Type, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[sqlx(type_name = "user_role_enum")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+	Viewer,
+	#[default]
+	Contributor,
+	Editor,
+	Moderator,
+	Admin,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserForCreate {
+	pub username: String,
+	pub email: String,
+	pub pwd_hash: String,
+}
+
+/// A user known by an external identity (JWT), provisioned the first time it is seen.
+#[derive(Debug, Clone)]
+pub struct IdentityForCreate {
+	pub issuer: String,
+	pub subject: String,
+	pub username: Option<String>, // taken when valid and free, else `user[id]`
+	pub email: Option<String>,    // taken when free, else `user[id]@users.invalid`
+	pub role: Role,
+}
+// endregion: User Types
+
+// region: UserModelAccessController
+// Usernames and emails are stored lowercase, so they are unique regardless of the case.
+pub struct UserMac;
+
+impl UserMac {
+	const TABLE: &'static str = "user_account";
+	const COLUMNS: &'static str = "id, username, email, role, disabled, email_verified";
+
+	pub async fn create(db: &Db, data: UserForCreate) -> Result<UserAccount, model::Error2> {
+		let sql = format!("INSERT INTO user_account (username, email, pwd_hash) VALUES ($1, $2, $3) RETURNING {}", Self::COLUMNS);
+		let user = sqlx::query_as::<_, UserAccount>(&sql)
+			.bind(data.username.to_lowercase())
+			.bind(data.email.to_lowercase())
+			.bind(data.pwd_hash)
+			.fetch_one(db)
+			.await?;
+
+		Ok(user)
+	}
+
+	/// Create the user with an unexpired and not used up invite, with the role of the invite. None when the invite is not valid.
+	pub async fn create_invited(db: &Db, data: UserForCreate, invite_code_hash: &str) -> Result<Option<UserAccount>, model::Error2> {
+		let mut tx = db.begin().await?;
+		let invite: Option<(i64, Role)> = sqlx::query_as(
+			"UPDATE invite SET uses = uses + 1 WHERE code_hash = $1 AND expires > now() AND uses < max_uses RETURNING id, role",
+		)
+		.bind(invite_code_hash)
+		.fetch_optional(&mut tx)
+		.await?;
+		let (invite_id, role) = match invite {
+			Some(invite) => invite,
+			None => return Ok(None),
+		};
+
+		let sql = format!(
+			"INSERT INTO user_account (username, email, pwd_hash, role) VALUES ($1, $2, $3, $4) RETURNING {}",
+			Self::COLUMNS
+		);
+		let user = sqlx::query_as::<_, UserAccount>(&sql)
+			.bind(data.username.to_lowercase())
+			.bind(data.email.to_lowercase())
+			.bind(data.pwd_hash)
+			.bind(role)
+			.fetch_one(&mut tx)
+			.await?;
+		sqlx::query("INSERT INTO invite_use (invite_id, user_id) VALUES ($1, $2)")
+			.bind(invite_id)
+			.bind(user.id)
+			.execute(&mut tx)
+			.await?;
+		tx.commit().await?;
+
+		Ok(Some(user))
+	}
+
+	pub async fn get(db: &Db, id: i64) -> Result<UserAccount, model::Error2> {
+		let sql = format!("SELECT {} FROM user_account WHERE id = $1", Self::COLUMNS);
+		let result = sqlx::query_as::<_, UserAccount>(&sql).bind(id).fetch_one(db).await;
+
+		handle_fetch_one_result(result, Self::TABLE, id)
+	}
+
+	/// Whether the username or the email is already used (case insensitive).
+	pub async fn exists(db: &Db, username: &str, email: &str) -> Result<bool, model::Error2> {
+		let (exists,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM user_account WHERE username = $1 OR email = $2)")
+			.bind(username.to_lowercase())
+			.bind(email.to_lowercase())
+			.fetch_one(db)
+			.await?;
+
+		Ok(exists)
+	}
+
+	pub async fn get_for_auth(db: &Db, username: &str) -> Result<Option<UserForAuth>, model::Error2> {
+		let user = sqlx::query_as::<_, UserForAuth>("SELECT id, pwd_hash, disabled FROM user_account WHERE username = $1")
+			.bind(username.to_lowercase())
+			.fetch_optional(db)
+			.await?;
+
+		Ok(user)
+	}
+
+	/// The user of the external identity, None when not provisioned yet.
+	pub async fn get_by_identity(db: &Db, issuer: &str, subject: &str) -> Result<Option<i64>, model::Error2> {
+		let user_id: Option<(i64,)> = sqlx::query_as("SELECT user_id FROM user_identity WHERE issuer = $1 AND subject = $2")
+			.bind(issuer)
+			.bind(subject)
+			.fetch_optional(db)
+			.await?;
+
+		Ok(user_id.map(|(id,)| id))
+	}
+
+	/// The user of the email (case insensitive), None when there is none.
+	pub async fn get_id_by_email(db: &Db, email: &str) -> Result<Option<i64>, model::Error2> {
+		let user_id: Option<(i64,)> = sqlx::query_as("SELECT id FROM user_account WHERE email = $1")
+			.bind(email.to_lowercase())
+			.fetch_optional(db)
+			.await?;
+
+		Ok(user_id.map(|(id,)| id))
+	}
+
+	/// The user of the email (case insensitive) when it verified it, None otherwise.
+	pub async fn get_id_by_verified_email(db: &Db, email: &str) -> Result<Option<i64>, model::Error2> {
+		let user_id: Option<(i64,)> = sqlx::query_as("SELECT id FROM user_account WHERE email = $1 AND email_verified")
+			.bind(email.to_lowercase())
+			.fetch_optional(db)
+			.await?;
+
+		Ok(user_id.map(|(id,)| id))
+	}
+
+	/// Link the external identity to an existing user.
+	pub async fn link_identity(db: &Db, issuer: &str, subject: &str, user_id: i64) -> Result<(), model::Error2> {
+		sqlx::query("INSERT INTO user_identity (issuer, subject, user_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+			.bind(issuer)
+			.bind(subject)
+			.bind(user_id)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// Create the user of the external identity, without a usable password (it only logs in with its tokens).
+	/// When another request provisioned the identity meanwhile, its user is returned.
+	pub async fn create_from_identity(db: &Db, data: IdentityForCreate) -> Result<i64, model::Error2> {
+		let mut tx = db.begin().await?;
+		let (id,): (i64,) = sqlx::query_as("SELECT nextval('user_account_id_seq')").fetch_one(&mut tx).await?;
+
+		let username = match data.username.map(|u| u.to_lowercase()) {
+			Some(username) if !Self::is_used(&mut tx, "username", &username).await? => username,
+			_ => format!("user{}", id),
+		};
+		let email = match data.email.map(|e| e.to_lowercase()) {
+			Some(email) if !Self::is_used(&mut tx, "email", &email).await? => email,
+			_ => format!("user{}@users.invalid", id),
+		};
+		sqlx::query("INSERT INTO user_account (id, username, email, pwd_hash, role) VALUES ($1, $2, $3, '!', $4)")
+			.bind(id)
+			.bind(username)
+			.bind(email)
+			.bind(data.role)
+			.execute(&mut tx)
+			.await?;
+		let linked = sqlx::query("INSERT INTO user_identity (issuer, subject, user_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+			.bind(&data.issuer)
+			.bind(&data.subject)
+			.bind(id)
+			.execute(&mut tx)
+			.await?;
+
+		if linked.rows_affected() == 0 {
+			tx.rollback().await?;
+			return Self::get_by_identity(db, &data.issuer, &data.subject)
+				.await?
+				.ok_or(model::Error2::EntityNotFound("user_identity", data.subject));
+		}
+		tx.commit().await?;
+
+		Ok(id)
+	}
+
+	/// Set the password hash of the user (password reset), and revoke all its sessions.
+	pub async fn set_pwd_hash(db: &Db, id: i64, pwd_hash: &str) -> Result<(), model::Error2> {
+		let mut tx = db.begin().await?;
+		sqlx::query("UPDATE user_account SET pwd_hash = $2 WHERE id = $1").bind(id).bind(pwd_hash).execute(&mut tx).await?;
+		sqlx::query("DELETE FROM session WHERE user_id = $1").bind(id).execute(&mut tx).await?;
+		tx.commit().await?;
+
+		Ok(())
+	}
+
+	/// Mark the email of the user as verified, when it is still the one the verification was sent to.
+	pub async fn set_email_verified(db: &Db, id: i64, email: &str) -> Result<bool, model::Error2> {
+		let result = sqlx::query("UPDATE user_account SET email_verified = true WHERE id = $1 AND email = $2")
+			.bind(id)
+			.bind(email.to_lowercase())
+			.execute(db)
+			.await?;
+
+		Ok(result.rows_affected() > 0)
+	}
+
+	/// Set the role of the user (from the claims of its tokens), unless its current role is above `max_role`
+	/// (given in the server, so not for the claims to change).
+	pub async fn set_role(db: &Db, id: i64, role: Role, max_role: Role) -> Result<(), model::Error2> {
+		sqlx::query("UPDATE user_account SET role = $2 WHERE id = $1 AND role <> $2 AND role <= $3")
+			.bind(id)
+			.bind(role.min(max_role))
+			.bind(max_role)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn is_used(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, column: &str, value: &str) -> Result<bool, model::Error2> {
+		let sql = format!("SELECT EXISTS(SELECT 1 FROM user_account WHERE {} = $1)", column);
+		let (used,): (bool,) = sqlx::query_as(&sql).bind(value).fetch_one(&mut **tx).await?;
+
+		Ok(used)
+	}
+}
+// endregion: UserModelAccessController
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/model_user.rs"]
+mod tests;
+// endregion: Test
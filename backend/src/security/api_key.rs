@@ -0,0 +1,50 @@
+// API keys `qapi_[64 hex]`, random, the prefix making them easy to spot for secret scanners.
+// Only their SHA-256 is stored (random keys do not need a slow hash), with their first characters to recognize them.
+
+use super::{to_hex, user_ctx, Error, UserCtx};
+use crate::model::{ApiKey, ApiKeyForCreate, ApiKeyMac, ApiScope, Db};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const API_KEY_PREFIX: &str = "qapi_";
+const API_KEY_BYTES: usize = 32;
+const API_KEY_SHOWN_LEN: usize = API_KEY_PREFIX.len() + 8;
+
+/// The new key, given only once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyCreated {
+	pub key: String,
+	pub api_key: ApiKey,
+}
+
+pub async fn create_api_key(db: &Db, utx: &UserCtx, data: ApiKeyForCreate) -> Result<ApiKeyCreated, Error> {
+	let mut bytes = [0u8; API_KEY_BYTES];
+	OsRng.fill_bytes(&mut bytes);
+	let key = format!("{}{}", API_KEY_PREFIX, to_hex(&bytes));
+
+	let api_key = ApiKeyMac::create(db, utx, data, &key[..API_KEY_SHOWN_LEN], &hash_api_key(&key)).await?;
+	Ok(ApiKeyCreated { key, api_key })
+}
+
+/// The user of an unexpired key, with the scopes of the key.
+pub async fn utx_from_api_key(db: &Db, key: &str) -> Result<(UserCtx, Vec<ApiScope>), Error> {
+	if !key.starts_with(API_KEY_PREFIX) {
+		return Err(Error::InvalidApiKey);
+	}
+	let api_key = ApiKeyMac::use_key(db, &hash_api_key(key)).await?.ok_or(Error::InvalidApiKey)?;
+	let utx = user_ctx(db, api_key.user_id, None).await?.ok_or(Error::InvalidApiKey)?;
+
+	Ok((utx, api_key.scopes))
+}
+
+fn hash_api_key(key: &str) -> String {
+	to_hex(&Sha256::digest(key.as_bytes()))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/security_api_key.rs"]
+mod tests;
+// endregion: Test
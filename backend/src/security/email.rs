@@ -0,0 +1,141 @@
+// Password reset and email verification by the links of emails (see mail), with random tokens `[64 hex]`,
+// valid a limited time and used once. As the API keys, only their SHA-256 is stored.
+
+use super::{hash_pwd, to_hex, Error, PWD_MIN_LEN};
+use crate::config::config;
+use crate::mail::{self, Mailer};
+use crate::model::{Db, EmailToken, EmailTokenMac, EmailTokenPurpose, UserAccount, UserMac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+const PASSWORD_RESET_TTL_SECS: i64 = 3600;
+/// No new reset link of a user within this time of the previous one (while valid), against the flooding of the inbox.
+const PASSWORD_RESET_INTERVAL_SECS: i64 = 5 * 60;
+const EMAIL_VERIFICATION_TTL_SECS: i64 = 2 * 24 * 3600;
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordResetRequest {
+	pub email: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordReset {
+	pub token: String,
+	pub password: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailVerification {
+	pub token: String,
+}
+
+/// Send the password reset link to the email, when it is the one of an enabled user (and not already sent in the last minutes).
+/// Done in its own task, so the answer (and its time) does not tell who has an account,
+/// and the errors (e.g., of the mail server) are only logged.
+pub fn request_password_reset(db: &Db, mailer: Arc<dyn Mailer>, email: &str) -> JoinHandle<()> {
+	let (db, email) = (db.clone(), email.trim().to_string());
+	tokio::spawn(async move {
+		if let Err(ex) = send_password_reset(&db, &*mailer, &email).await {
+			println!("WARNING - password reset - email not sent cause: {:?}", ex);
+		}
+	})
+}
+
+async fn send_password_reset(db: &Db, mailer: &dyn Mailer, email: &str) -> Result<(), Error> {
+	let user = match UserMac::get_id_by_email(db, email).await? {
+		Some(user_id) => UserMac::get(db, user_id).await?,
+		None => return Ok(()),
+	};
+	if user.disabled {
+		return Ok(());
+	}
+
+	let token = new_email_token(db, EmailTokenPurpose::PasswordReset, &user, PASSWORD_RESET_TTL_SECS, PASSWORD_RESET_INTERVAL_SECS).await?;
+	let Some(token) = token else {
+		return Ok(()); // the previous link is recent enough
+	};
+	let link = format!("{}/#/reset-password?token={}", config().mail.public_url, token);
+	mailer.send(mail::password_reset(&user.email, &user.username, &link, PASSWORD_RESET_TTL_SECS)?).await?;
+	Ok(())
+}
+
+/// Set the new password of the user of the reset token, and revoke its sessions.
+pub async fn reset_password(db: &Db, data: PasswordReset) -> Result<(), Error> {
+	// checked before using the token, so the user can try again
+	if data.password.chars().count() < PWD_MIN_LEN {
+		return Err(Error::InvalidPassword(format!("password must have at least {} characters", PWD_MIN_LEN)));
+	}
+	let token = EmailTokenMac::take(db, EmailTokenPurpose::PasswordReset, &hash_email_token(&data.token))
+		.await?
+		.ok_or(Error::InvalidEmailToken)?;
+	if UserMac::get(db, token.user_id).await?.disabled {
+		return Err(Error::UserDisabled);
+	}
+
+	UserMac::set_pwd_hash(db, token.user_id, &hash_pwd(&data.password)?).await?;
+	Ok(())
+}
+
+/// Send the verification link to the email of the user, unless already verified.
+pub async fn send_email_verification(db: &Db, mailer: &dyn Mailer, user_id: i64) -> Result<(), Error> {
+	let user = UserMac::get(db, user_id).await?;
+	if user.email_verified {
+		return Ok(());
+	}
+
+	let token = new_email_token(db, EmailTokenPurpose::EmailVerification, &user, EMAIL_VERIFICATION_TTL_SECS, 0).await?;
+	let Some(token) = token else {
+		return Ok(());
+	};
+	let link = format!("{}/#/verify-email?token={}", config().mail.public_url, token);
+	mailer.send(mail::email_verification(&user.email, &user.username, &link, EMAIL_VERIFICATION_TTL_SECS)?).await?;
+	Ok(())
+}
+
+/// Verify the email of the verification token, when it is still the email of its user.
+pub async fn verify_email(db: &Db, data: EmailVerification) -> Result<UserAccount, Error> {
+	let token = EmailTokenMac::take(db, EmailTokenPurpose::EmailVerification, &hash_email_token(&data.token))
+		.await?
+		.ok_or(Error::InvalidEmailToken)?;
+	if !UserMac::set_email_verified(db, token.user_id, &token.email).await? {
+		return Err(Error::InvalidEmailToken); // the email changed since
+	}
+
+	Ok(UserMac::get(db, token.user_id).await?)
+}
+
+/// A new token of the user for the purpose, replacing the previous one, None when the previous one is still valid
+/// and not older than `min_interval_secs`.
+async fn new_email_token(
+	db: &Db,
+	purpose: EmailTokenPurpose,
+	user: &UserAccount,
+	ttl_secs: i64,
+	min_interval_secs: i64,
+) -> Result<Option<String>, Error> {
+	let mut bytes = [0u8; TOKEN_BYTES];
+	OsRng.fill_bytes(&mut bytes);
+	let token = to_hex(&bytes);
+
+	let email_token = EmailToken {
+		user_id: user.id,
+		email: user.email.clone(),
+	};
+	let created = EmailTokenMac::create_unless_recent(db, purpose, &email_token, &hash_email_token(&token), ttl_secs, min_interval_secs).await?;
+	Ok(created.then_some(token))
+}
+
+fn hash_email_token(token: &str) -> String {
+	to_hex(&Sha256::digest(token.trim().as_bytes()))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/security_email.rs"]
+mod tests;
+// endregion: Test
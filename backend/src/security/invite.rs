@@ -0,0 +1,33 @@
+// Invite codes `inv_[32 hex]`, random, given once to the admin who creates them, to register (see security::register).
+// As the API keys, only their SHA-256 is stored, with their first characters to recognize them.
+
+use super::{to_hex, Error, UserCtx};
+use crate::model::{Db, Invite, InviteForCreate, InviteMac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const INVITE_PREFIX: &str = "inv_";
+const INVITE_BYTES: usize = 16;
+const INVITE_SHOWN_LEN: usize = INVITE_PREFIX.len() + 8;
+
+/// The new invite code, given only once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteCreated {
+	pub code: String,
+	pub invite: Invite,
+}
+
+pub async fn create_invite(db: &Db, utx: &UserCtx, data: InviteForCreate) -> Result<InviteCreated, Error> {
+	let mut bytes = [0u8; INVITE_BYTES];
+	OsRng.fill_bytes(&mut bytes);
+	let code = format!("{}{}", INVITE_PREFIX, to_hex(&bytes));
+
+	let invite = InviteMac::create(db, utx, data, &code[..INVITE_SHOWN_LEN], &hash_invite_code(&code)).await?;
+	Ok(InviteCreated { code, invite })
+}
+
+pub(super) fn hash_invite_code(code: &str) -> String {
+	to_hex(&Sha256::digest(code.trim().as_bytes()))
+}
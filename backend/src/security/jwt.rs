@@ -0,0 +1,209 @@
+// Bearer JWTs of an external identity provider (`Authorization: Bearer [jwt]`), signed with the HS256 shared secret,
+// or with the RS256/ES256 keys of a local JWKS file, reloaded when it changes (see config::JwtConfig).
+// Their `sub` (and `iss`) identify the user, provisioned the first time it is seen, and the roles claim sets its role.
+
+use super::{user_ctx, Error, UserCtx};
+use crate::config::{config, JwtConfig};
+use crate::model::{Db, IdentityForCreate, Role, UserMac};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm};
+use jsonwebtoken::{decode, decode_header, errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use lazy_regex::regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// The claims used from a verified JWT.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtClaims {
+	pub sub: String,
+	pub iss: Option<String>,
+	pub email: Option<String>,
+	pub preferred_username: Option<String>,
+	#[serde(flatten)]
+	pub other: Map<String, Value>,
+}
+
+/// The user of a valid bearer JWT, provisioned on first sight, with the role of its claims.
+pub async fn utx_from_jwt(db: &Db, jwt: &str) -> Result<UserCtx, Error> {
+	let jwt_config = &config().jwt;
+	if jwt_config.secret.is_none() && jwt_config.jwks_path.is_none() {
+		return Err(Error::InvalidJwt("bearer tokens are not enabled".to_string()));
+	}
+	let keys = jwks().map(|jwks| jwks.keys()).unwrap_or_default();
+	let claims = verify_jwt(jwt, jwt_config, &keys)?;
+	utx_from_claims(db, claims, jwt_config).await
+}
+
+/// Check the signature, `exp` and `nbf`, and `iss` and `aud` when configured.
+pub fn verify_jwt(jwt: &str, jwt_config: &JwtConfig, keys: &[Jwk]) -> Result<JwtClaims, Error> {
+	let header = decode_header(jwt).map_err(jwt_error)?;
+	let decoding_keys = match header.alg {
+		Algorithm::HS256 => {
+			let secret = jwt_config.secret.as_ref().ok_or(Error::InvalidJwt("HS256 tokens are not enabled".to_string()))?;
+			vec![DecodingKey::from_secret(secret.as_bytes())]
+		}
+		Algorithm::RS256 | Algorithm::ES256 => keys
+			.iter()
+			.filter(|key| header.kid.is_none() || key.common.key_id == header.kid)
+			.filter(|key| key_matches(key, header.alg))
+			.filter_map(|key| DecodingKey::from_jwk(key).ok())
+			.collect(),
+		other => return Err(Error::InvalidJwt(format!("unsupported algorithm {:?}", other))),
+	};
+	if decoding_keys.is_empty() {
+		return Err(Error::InvalidJwt("no key for the token".to_string()));
+	}
+
+	let mut validation = Validation::new(header.alg);
+	let mut required = vec!["exp", "sub"];
+	validation.validate_nbf = true;
+	match &jwt_config.issuer {
+		Some(issuer) => {
+			validation.set_issuer(&[issuer]);
+			required.push("iss");
+		}
+		None => validation.iss = None,
+	}
+	match &jwt_config.audience {
+		Some(audience) => {
+			validation.set_audience(&[audience]);
+			required.push("aud");
+		}
+		None => validation.validate_aud = false,
+	}
+	validation.set_required_spec_claims(&required);
+
+	// with a JWKS, the key of the token is the one that verifies it (when it has no `kid`)
+	let mut result = Err(Error::InvalidJwt("no key for the token".to_string()));
+	for key in &decoding_keys {
+		result = decode::<JwtClaims>(jwt, key, &validation).map(|data| data.claims).map_err(jwt_error);
+		if !matches!(result, Err(Error::InvalidJwt(_))) {
+			break;
+		}
+	}
+	result
+}
+
+/// The user of the claims, created when its identity is new, and its role updated from the roles claim,
+/// capped at `max_role` so that the issuer cannot make admins.
+pub(super) async fn utx_from_claims(db: &Db, claims: JwtClaims, jwt_config: &JwtConfig) -> Result<UserCtx, Error> {
+	let role = claims_role(&claims, &jwt_config.roles_claim).map(|role| role.min(jwt_config.max_role));
+	let issuer = claims.iss.clone().unwrap_or_default();
+
+	let user_id = match UserMac::get_by_identity(db, &issuer, &claims.sub).await? {
+		Some(user_id) => {
+			if let Some(role) = role {
+				UserMac::set_role(db, user_id, role, jwt_config.max_role).await?;
+			}
+			user_id
+		}
+		None => {
+			let data = IdentityForCreate {
+				issuer,
+				subject: claims.sub,
+				username: claims.preferred_username.filter(|u| regex!(r"^[A-Za-z0-9_.-]{3,32}$").is_match(u)),
+				email: claims.email.filter(|e| regex!(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").is_match(e)),
+				role: role.unwrap_or_default(),
+			};
+			UserMac::create_from_identity(db, data).await?
+		}
+	};
+
+	user_ctx(db, user_id, None).await?.ok_or(Error::InvalidToken)
+}
+
+/// The highest known role of the roles claim (a role or a list of roles, `a.b` for a nested claim), None without any.
+pub(super) fn claims_role(claims: &JwtClaims, roles_claim: &str) -> Option<Role> {
+	let mut path = roles_claim.split('.');
+	let mut value = claims.other.get(path.next()?)?;
+	for name in path {
+		value = value.get(name)?;
+	}
+	let role = |value: &Value| value.as_str().and_then(|name| serde_json::from_value::<Role>(Value::String(name.to_lowercase())).ok());
+
+	match value {
+		Value::Array(values) => values.iter().filter_map(role).max(),
+		value => role(value),
+	}
+}
+
+fn key_matches(key: &Jwk, alg: Algorithm) -> bool {
+	let declared = match key.common.key_algorithm {
+		Some(KeyAlgorithm::RS256) => Some(Algorithm::RS256),
+		Some(KeyAlgorithm::ES256) => Some(Algorithm::ES256),
+		Some(_) => return false,
+		None => None,
+	};
+	let family = match &key.algorithm {
+		AlgorithmParameters::RSA(_) => alg == Algorithm::RS256,
+		AlgorithmParameters::EllipticCurve(params) => alg == Algorithm::ES256 && params.curve == EllipticCurve::P256,
+		_ => false,
+	};
+	family && declared.is_none_or(|declared| declared == alg)
+}
+
+fn jwt_error(ex: jsonwebtoken::errors::Error) -> Error {
+	match ex.kind() {
+		ErrorKind::ExpiredSignature => Error::TokenExpired,
+		_ => Error::InvalidJwt(ex.to_string()),
+	}
+}
+
+// region: JWKS
+/// The JWKS file of the configuration, None without one.
+fn jwks() -> Option<&'static JwksFile> {
+	static JWKS: OnceLock<Option<JwksFile>> = OnceLock::new();
+	JWKS.get_or_init(|| config().jwt.jwks_path.clone().map(JwksFile::new)).as_ref()
+}
+
+/// A JWKS file, read again when its modification time or size changes.
+/// When the new content is not a valid JWKS, the previous keys are kept.
+pub struct JwksFile {
+	path: PathBuf,
+	state: Mutex<Option<LoadedKeys>>,
+}
+
+type FileVersion = (Option<SystemTime>, u64); // modification time and size
+type LoadedKeys = (Option<FileVersion>, Arc<Vec<Jwk>>);
+
+impl JwksFile {
+	pub fn new(path: PathBuf) -> JwksFile {
+		JwksFile { path, state: Mutex::new(None) }
+	}
+
+	pub fn keys(&self) -> Arc<Vec<Jwk>> {
+		let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		let version = fs::metadata(&self.path).map(|meta| (meta.modified().ok(), meta.len())).ok();
+		let previous = match state.as_ref() {
+			Some((loaded, keys)) if *loaded == version => return keys.clone(),
+			Some((_, keys)) => keys.clone(),
+			None => Arc::default(),
+		};
+
+		let keys = match self.read() {
+			Ok(keys) => Arc::new(keys),
+			Err(ex) => {
+				println!("ERROR - JWKS {} not loaded, keeping its previous keys. Cause {}", self.path.display(), ex);
+				previous
+			}
+		};
+		*state = Some((version, keys.clone()));
+		keys
+	}
+
+	fn read(&self) -> Result<Vec<Jwk>, String> {
+		let content = fs::read_to_string(&self.path).map_err(|ex| ex.to_string())?;
+		let set: JwkSet = serde_json::from_str(&content).map_err(|ex| ex.to_string())?;
+		Ok(set.keys)
+	}
+}
+// endregion: JWKS
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/security_jwt.rs"]
+mod tests;
+// endregion: Test
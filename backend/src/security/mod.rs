@@ -1,19 +1,281 @@
 use thiserror::Error as ThisError;
-use crate::model::Db;
+use crate::config::{config, RegistrationMode};
+use crate::mail;
+use crate::model::{self, Db, Role, SessionForCreate, SessionMac, UserAccount, UserForCreate, UserMac};
+use lazy_regex::regex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+mod api_key;
+mod email;
+mod invite;
+mod jwt;
+mod oidc;
+mod pwd;
+mod token;
+mod totp;
+
+pub use api_key::{create_api_key, utx_from_api_key, ApiKeyCreated, API_KEY_PREFIX};
+pub use email::{
+    request_password_reset, reset_password, send_email_verification, verify_email, EmailVerification, PasswordReset, PasswordResetRequest,
+};
+pub use invite::{create_invite, InviteCreated, INVITE_PREFIX};
+pub use jwt::{utx_from_jwt, verify_jwt, JwksFile, JwtClaims};
+pub use oidc::{Oidc, OidcLoginResult};
+pub use pwd::{hash_pwd, verify_pwd};
+pub use token::{new_token, refresh_token, verify_token, Token};
+pub use totp::{check_totp, confirm_totp, disable_totp, enrol_totp, totp_now, TotpCode, TotpEnrollment, TotpRecoveryCodes};
+
+const PWD_MIN_LEN: usize = 8;
+// Verified when the username is unknown, so the login takes as long as with a wrong password.
+const DUMMY_PWD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$SjSpaOzxAwdkipkXzzGLTg$CJauZI/fLucKc4vupvyFAD+VJHjYJ0018BVhaif76SM";
 
 pub struct UserCtx {
     pub user_id: i64,
+    pub role: Role, // checked by the model (see model::permission)
+    pub trusted: bool, // quotes published without moderation
+    pub session_id: Option<i64>, // None outside of a login session (e.g., command line tools)
+    pub totp_enabled: bool, // two-factor authentication, required for admins (see security::totp)
+}
+
+impl UserCtx {
+    /// The context of the user with the role, editors and above being trusted, as the users of the configuration.
+    pub fn new(user_id: i64, role: Role) -> UserCtx {
+        UserCtx {
+            user_id,
+            role,
+            trusted: role >= Role::Editor || config().is_trusted(user_id),
+            session_id: None,
+            totp_enabled: false,
+        }
+    }
+
+    /// Moderators see the quotes not published (yet), approve and reject them.
+    pub fn is_moderator(&self) -> bool {
+        self.role >= Role::Moderator
+    }
+
+    /// An admin without two-factor authentication, only allowed to enrol.
+    pub fn must_enrol_totp(&self) -> bool {
+        self.role >= Role::Admin && !self.totp_enabled
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserForRegister {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub invite: Option<String>, // invite code, required with QUOTE_REGISTRATION=invite, gives its role
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoginForm {
+    pub username: String,
+    pub password: String,
+    pub totp: Option<String>, // code or recovery code, when two-factor authentication is enabled
+}
+
+/// The token to send as `X-Auth-Token`, valid until it expires (see security::token).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginResult {
+    pub token: String,
+    pub user: UserAccount,
+    /// An admin without two-factor authentication, who has to enrol before anything else (`POST /api/me/totp`)
+    pub totp_enrollment_required: bool,
+}
+
+/// The user of a signed, unexpired token, unless its session was revoked, or the account deleted or disabled since.
+/// A token close to its expiration stays valid, the web layer sends its refresh (see `refresh_token`).
+pub async fn utx_from_token(db: &Db, token: &str) -> Result<UserCtx, Error> {
+    let token = verify_token(token)?;
+    if !SessionMac::touch(db, token.user_id, token.session_id).await? {
+        return Err(Error::SessionRevoked);
+    }
+    user_ctx(db, token.user_id, Some(token.session_id)).await?.ok_or(Error::InvalidToken)
 }
 
-pub async fn utx_from_token(_db: &Db, token: &str) -> Result<UserCtx, Error> {
-    match token.parse::<i64>() {
-        Ok(user_id) => Ok(UserCtx { user_id }),
-        Err(_) => Err(Error::InvalidToken(token.to_string())),
+/// The context of a user outside of a session (e.g., command line tools).
+pub async fn utx_from_user_id(db: &Db, user_id: i64) -> Result<UserCtx, Error> {
+    user_ctx(db, user_id, None).await?.ok_or(Error::Model(model::Error2::EntityNotFound("user_account", user_id.to_string())))
+}
+
+/// The context of an enabled user, with its role, None when the user does not exist.
+async fn user_ctx(db: &Db, user_id: i64, session_id: Option<i64>) -> Result<Option<UserCtx>, Error> {
+    let sql = "SELECT role, disabled, EXISTS(SELECT 1 FROM user_totp WHERE user_id = $1 AND enabled) FROM user_account WHERE id = $1";
+    let user: Option<(Role, bool, bool)> = sqlx::query_as(sql)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(model::Error2::from)?;
+
+    match user {
+        Some((role, false, totp_enabled)) => Ok(Some(UserCtx {
+            session_id,
+            totp_enabled,
+            ..UserCtx::new(user_id, role)
+        })),
+        Some((_, true, _)) => Err(Error::UserDisabled),
+        None => Ok(None),
+    }
+}
+
+/// Create the account, with an invite code when given (or required by the configuration).
+pub async fn register(db: &Db, data: UserForRegister) -> Result<UserAccount, Error> {
+    register_with(db, data, config().registration == RegistrationMode::InviteOnly).await
+}
+
+async fn register_with(db: &Db, data: UserForRegister, invite_only: bool) -> Result<UserAccount, Error> {
+    let username = data.username.trim();
+    let email = data.email.trim();
+    if !regex!(r"^[A-Za-z0-9_.-]{3,32}$").is_match(username) {
+        return Err(Error::InvalidRegistration("username must be 3 to 32 letters, digits, '_', '.' or '-'".to_string()));
+    }
+    if !regex!(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").is_match(email) {
+        return Err(Error::InvalidRegistration(format!("'{}' is not an email", email)));
+    }
+    if data.password.chars().count() < PWD_MIN_LEN {
+        return Err(Error::InvalidRegistration(format!("password must have at least {} characters", PWD_MIN_LEN)));
+    }
+    if UserMac::exists(db, username, email).await? {
+        return Err(Error::InvalidRegistration("username or email already registered".to_string()));
+    }
+
+    let invite = data.invite.as_deref().map(str::trim).filter(|code| !code.is_empty());
+    if invite.is_none() && invite_only {
+        return Err(Error::InvalidRegistration("an invite code is required".to_string()));
+    }
+
+    let user = UserForCreate {
+        username: username.to_string(),
+        email: email.to_string(),
+        pwd_hash: hash_pwd(&data.password)?,
+    };
+    match invite {
+        Some(code) => UserMac::create_invited(db, user, &invite::hash_invite_code(code))
+            .await?
+            .ok_or(Error::InvalidRegistration("invalid, expired or used up invite code".to_string())),
+        None => Ok(UserMac::create(db, user).await?),
     }
 }
 
+/// Check the credentials (and the TOTP code when enabled), and give the token of a new session.
+pub async fn login(db: &Db, form: LoginForm, client: SessionForCreate) -> Result<LoginResult, Error> {
+    let user = UserMac::get_for_auth(db, form.username.trim()).await?;
+    let pwd_hash = user.as_ref().map(|u| u.pwd_hash.as_str()).unwrap_or(DUMMY_PWD_HASH);
+    let user = match (verify_pwd(&form.password, pwd_hash), user) {
+        (true, Some(user)) if user.disabled => return Err(Error::UserDisabled),
+        (true, Some(user)) => user,
+        _ => return Err(Error::InvalidCredentials),
+    };
+    check_totp(db, user.id, form.totp.as_deref()).await?;
+
+    let session = SessionMac::create(db, user.id, client).await?;
+    login_result(db, user.id, session.id).await
+}
+
+/// The token of the session, with the user.
+async fn login_result(db: &Db, user_id: i64, session_id: i64) -> Result<LoginResult, Error> {
+    let user = UserMac::get(db, user_id).await?;
+    let totp_enrollment_required = user.role >= Role::Admin && !totp::totp_enabled(db, user_id).await?;
+    Ok(LoginResult {
+        token: new_token(user_id, session_id),
+        user,
+        totp_enrollment_required,
+    })
+}
+
+/// Revoke the session of the token, when valid.
+pub async fn logout(db: &Db, token: &str) -> Result<(), Error> {
+    if let Ok(token) = verify_token(token) {
+        let utx = UserCtx::new(token.user_id, Role::Viewer); // only to own the session
+        match SessionMac::delete(db, &utx, token.session_id).await {
+            Ok(_) | Err(model::Error2::EntityNotFound(_, _)) => (),
+            Err(ex) => return Err(ex.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Delete the expired sessions every QUOTE_SESSION_CLEANUP_SECS, for as long as the server runs.
+pub async fn cleanup_sessions(db: Arc<Db>) {
+    let mut interval = tokio::time::interval(config().session_cleanup);
+    loop {
+        interval.tick().await;
+        match SessionMac::delete_expired(&db).await {
+            Ok(0) => (),
+            Ok(count) => println!("Deleted {} expired sessions", count),
+            Err(ex) => println!("ERROR - expired sessions cleanup failed. Cause {:?}", ex),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 #[derive(ThisError, Debug)]
 pub enum Error {
-    #[error("Invalid Token {0}")]
-    InvalidToken(String),
-}
\ No newline at end of file
+    #[error("Invalid Token")]
+    InvalidToken,
+
+    #[error("Token Expired")]
+    TokenExpired,
+
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
+    #[error("User account disabled")]
+    UserDisabled,
+
+    #[error("Session expired or revoked")]
+    SessionRevoked,
+
+    #[error("Invalid JWT - {0}")]
+    InvalidJwt(String),
+
+    #[error("OpenID Connect login failed - {0}")]
+    Oidc(String),
+
+    #[error("Two-factor code required (`totp`)")]
+    TotpRequired,
+
+    #[error("Invalid two-factor code")]
+    InvalidTotp,
+
+    #[error("Two-factor authentication required for admins, enrol with POST /api/me/totp")]
+    TotpEnrollmentRequired,
+
+    #[error("Two-factor authentication - {0}")]
+    TotpState(String),
+
+    #[error("Invalid or expired API key")]
+    InvalidApiKey,
+
+    #[error("Missing API key scope - {0}")]
+    MissingScope(String),
+
+    #[error("Invalid Registration - {0}")]
+    InvalidRegistration(String),
+
+    #[error("Invalid Password - {0}")]
+    InvalidPassword(String),
+
+    #[error("Invalid, expired or already used email link")]
+    InvalidEmailToken,
+
+    #[error(transparent)]
+    Mail(#[from] mail::Error),
+
+    #[error("Password hash failed - {0}")]
+    PwdHash(String),
+
+    #[error(transparent)]
+    Model(#[from] model::Error2),
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/security.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,248 @@
+// OpenID Connect login (SSO) with the authorization code flow and PKCE, against the provider of config::OidcConfig.
+//   1. `authorization_url` keeps a random state, nonce and PKCE verifier (model::OidcLoginMac), and gives the provider URL to redirect to,
+//      with the state, also kept in a cookie of the browser (web::oidc).
+//   2. The provider redirects back to the callback with a code and the state, accepted only from the browser with the state cookie
+//      (against login CSRF, the login of a victim into the account of an attacker), `callback` exchanges the code (with the verifier)
+//      for the ID token, checked with the keys of the provider (`iss`, `aud`, `exp`, `nonce`).
+//   3. The user of the identity is the one linked before, or else the user of its verified email (linked from then on,
+//      when the user verified it too),
+//      or else a new user (not with QUOTE_REGISTRATION=invite), and gets a normal session.
+//   4. Unless it has two-factor authentication: the callback then only gives a ticket, and `totp_login` creates the session
+//      with the ticket and a TOTP code, as the password login.
+
+use super::jwt::verify_jwt;
+use super::totp::{check_totp, totp_enabled};
+use super::{login_result, to_hex, user_ctx, Error, JwtClaims, LoginResult};
+use crate::config::{config, JwtConfig, OidcConfig, RegistrationMode};
+use crate::model::{Db, IdentityForCreate, OidcLogin, OidcLoginMac, SessionForCreate, SessionMac, UserMac};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use lazy_regex::regex;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const LOGIN_TTL_SECS: i64 = 600;
+
+/// The provider endpoints, from its discovery document.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+	issuer: String,
+	authorization_endpoint: String,
+	token_endpoint: String,
+	jwks_uri: String,
+}
+
+#[derive(Debug)]
+struct Provider {
+	metadata: ProviderMetadata,
+	keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+	id_token: String,
+}
+
+/// The outcome of the callback: a session, or a ticket waiting for the TOTP code of the user (see `Oidc::totp_login`).
+#[derive(Debug)]
+pub enum OidcLoginResult {
+	Session(LoginResult),
+	TotpRequired { ticket: String },
+}
+
+/// The client of the provider, discovered on first use (and again when its keys do not verify an ID token).
+pub struct Oidc {
+	config: OidcConfig,
+	http: reqwest::Client,
+	provider: Mutex<Option<Arc<Provider>>>,
+}
+
+impl Oidc {
+	pub fn new(config: OidcConfig) -> Oidc {
+		Oidc {
+			config,
+			http: reqwest::Client::new(),
+			provider: Mutex::new(None),
+		}
+	}
+
+	/// The URL of the provider to send the browser to, for a new login, and the state of the login (for the browser to keep).
+	pub async fn authorization_url(&self, db: &Db) -> Result<(String, String), Error> {
+		let provider = self.provider(false).await?;
+		let login = OidcLogin {
+			state: random_hex(),
+			nonce: random_hex(),
+			code_verifier: random_hex(),
+		};
+		OidcLoginMac::create(db, &login, LOGIN_TTL_SECS).await?;
+
+		let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(login.code_verifier.as_bytes()));
+		let url = Url::parse_with_params(
+			&provider.metadata.authorization_endpoint,
+			&[
+				("response_type", "code"),
+				("client_id", &self.config.client_id),
+				("redirect_uri", &self.config.redirect_url),
+				("scope", &self.config.scopes),
+				("state", &login.state),
+				("nonce", &login.nonce),
+				("code_challenge", &challenge),
+				("code_challenge_method", "S256"),
+			],
+		)
+		.map_err(|ex| Error::Oidc(format!("invalid authorization endpoint - {}", ex)))?;
+
+		Ok((url.into(), login.state))
+	}
+
+	/// Finish the login of the state with the code of the provider, and give the token of a new session
+	/// (or, when the user has two-factor authentication, the ticket of `totp_login`).
+	pub async fn callback(&self, db: &Db, code: &str, state: &str, client: SessionForCreate) -> Result<OidcLoginResult, Error> {
+		let login = OidcLoginMac::take(db, state).await?.ok_or(Error::Oidc("unknown or expired login state".to_string()))?;
+		let claims = self.exchange_code(code, &login).await?;
+
+		let user_id = self.user_of(db, claims).await?;
+		user_ctx(db, user_id, None).await?.ok_or(Error::InvalidCredentials)?;
+		if totp_enabled(db, user_id).await? {
+			let ticket = random_hex();
+			OidcLoginMac::create_totp_pending(db, &hash_ticket(&ticket), user_id, LOGIN_TTL_SECS).await?;
+			return Ok(OidcLoginResult::TotpRequired { ticket });
+		}
+
+		let session = SessionMac::create(db, user_id, client).await?;
+		Ok(OidcLoginResult::Session(login_result(db, user_id, session.id).await?))
+	}
+
+	/// Finish the SSO login of the ticket with the TOTP code (or a recovery code) of its user, and give the token of a new session.
+	/// The ticket is used once, a wrong code needs a new SSO login.
+	pub async fn totp_login(&self, db: &Db, ticket: &str, code: &str, client: SessionForCreate) -> Result<LoginResult, Error> {
+		let user_id = OidcLoginMac::take_totp_pending(db, &hash_ticket(ticket))
+			.await?
+			.ok_or(Error::Oidc("unknown or expired two-factor login".to_string()))?;
+		check_totp(db, user_id, Some(code)).await?;
+
+		user_ctx(db, user_id, None).await?.ok_or(Error::InvalidCredentials)?;
+		let session = SessionMac::create(db, user_id, client).await?;
+		login_result(db, user_id, session.id).await
+	}
+
+	/// The claims of the ID token of the code.
+	async fn exchange_code(&self, code: &str, login: &OidcLogin) -> Result<JwtClaims, Error> {
+		let provider = self.provider(false).await?;
+		let mut form = vec![
+			("grant_type", "authorization_code"),
+			("code", code),
+			("redirect_uri", &self.config.redirect_url),
+			("client_id", &self.config.client_id),
+			("code_verifier", &login.code_verifier),
+		];
+		if let Some(secret) = &self.config.client_secret {
+			form.push(("client_secret", secret));
+		}
+		let response = self.http.post(&provider.metadata.token_endpoint).form(&form).send().await.map_err(provider_error)?;
+		if !response.status().is_success() {
+			let status = response.status();
+			let body = response.text().await.unwrap_or_default();
+			return Err(Error::Oidc(format!("token endpoint answered {} {}", status, body)));
+		}
+		let tokens: TokenResponse = response.json().await.map_err(provider_error)?;
+
+		let claims = match self.verify_id_token(&tokens.id_token, &provider) {
+			Err(Error::InvalidJwt(_)) => self.verify_id_token(&tokens.id_token, &*self.provider(true).await?), // rotated keys
+			result => result,
+		}?;
+		match claims.other.get("nonce").and_then(Value::as_str) == Some(login.nonce.as_str()) {
+			true => Ok(claims),
+			false => Err(Error::Oidc("ID token nonce mismatch".to_string())),
+		}
+	}
+
+	fn verify_id_token(&self, id_token: &str, provider: &Provider) -> Result<JwtClaims, Error> {
+		let jwt_config = JwtConfig {
+			secret: self.config.client_secret.clone(), // HS256 ID tokens are signed with the client secret
+			issuer: Some(provider.metadata.issuer.clone()),
+			audience: Some(self.config.client_id.clone()),
+			..JwtConfig::default()
+		};
+		verify_jwt(id_token, &jwt_config, &provider.keys)
+	}
+
+	/// The user linked to the identity, or else the user of its verified email (verified by both), or else a new user (unless invite-only).
+	async fn user_of(&self, db: &Db, claims: JwtClaims) -> Result<i64, Error> {
+		let issuer = claims.iss.clone().unwrap_or_default();
+		if let Some(user_id) = UserMac::get_by_identity(db, &issuer, &claims.sub).await? {
+			return Ok(user_id);
+		}
+
+		// an unverified email could be the one of someone else, on either side (e.g., an account registered ahead by an attacker)
+		let verified = match claims.other.get("email_verified") {
+			Some(Value::Bool(verified)) => *verified,
+			Some(Value::String(verified)) => verified == "true",
+			_ => false,
+		};
+		let email = claims.email.filter(|e| regex!(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").is_match(e));
+		if let Some(email) = email.as_deref().filter(|_| verified)
+			&& let Some(user_id) = UserMac::get_id_by_verified_email(db, email).await?
+		{
+			UserMac::link_identity(db, &issuer, &claims.sub, user_id).await?;
+			return Ok(user_id);
+		}
+
+		if config().registration == RegistrationMode::InviteOnly {
+			return Err(Error::Oidc("no account for this identity, and the registration is by invite only".to_string()));
+		}
+		let data = IdentityForCreate {
+			issuer,
+			subject: claims.sub,
+			username: claims.preferred_username.filter(|u| regex!(r"^[A-Za-z0-9_.-]{3,32}$").is_match(u)),
+			email,
+			role: Default::default(),
+		};
+		Ok(UserMac::create_from_identity(db, data).await?)
+	}
+
+	/// The discovered provider, discovered again when `refresh`.
+	async fn provider(&self, refresh: bool) -> Result<Arc<Provider>, Error> {
+		let mut provider = self.provider.lock().await;
+		if let Some(loaded) = provider.as_ref().filter(|_| !refresh) {
+			return Ok(loaded.clone());
+		}
+
+		let url = format!("{}/.well-known/openid-configuration", self.config.issuer);
+		let metadata: ProviderMetadata = self.get_json(&url).await?;
+		if metadata.issuer.trim_end_matches('/') != self.config.issuer {
+			return Err(Error::Oidc(format!("the provider issuer '{}' is not the configured one", metadata.issuer)));
+		}
+		let keys: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+
+		let loaded = Arc::new(Provider { metadata, keys: keys.keys });
+		*provider = Some(loaded.clone());
+		Ok(loaded)
+	}
+
+	async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, Error> {
+		let response = self.http.get(url).send().await.and_then(|r| r.error_for_status()).map_err(provider_error)?;
+		response.json().await.map_err(provider_error)
+	}
+}
+
+fn random_hex() -> String {
+	let mut bytes = [0u8; 32];
+	OsRng.fill_bytes(&mut bytes);
+	to_hex(&bytes)
+}
+
+fn hash_ticket(ticket: &str) -> String {
+	to_hex(&Sha256::digest(ticket.trim().as_bytes()))
+}
+
+fn provider_error(ex: reqwest::Error) -> Error {
+	Error::Oidc(format!("provider request failed - {}", ex))
+}
@@ -0,0 +1,18 @@
+// Password hashing, argon2id with the default parameters (PHC string format).
+
+use super::Error;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+
+pub fn hash_pwd(pwd: &str) -> Result<String, Error> {
+	let salt = SaltString::generate(&mut OsRng);
+	let hash = Argon2::default().hash_password(pwd.as_bytes(), &salt).map_err(|ex| Error::PwdHash(ex.to_string()))?;
+
+	Ok(hash.to_string())
+}
+
+/// Whether the password matches the hash (false on a malformed hash).
+pub fn verify_pwd(pwd: &str, pwd_hash: &str) -> bool {
+	PasswordHash::new(pwd_hash).is_ok_and(|hash| Argon2::default().verify_password(pwd.as_bytes(), &hash).is_ok())
+}
@@ -0,0 +1,91 @@
+// Auth tokens `user-[user-id].[session-id].[expiration].[signature]`, where the expiration is in unix seconds,
+// and the signature the HMAC-SHA256 (hex) of `user-[user-id].[session-id].[expiration]` with the server secret.
+// The session (see model::SessionMac) can be revoked before the token expires.
+
+use super::{to_hex, Error};
+use crate::config::config;
+use hmac::{Hmac, Mac};
+use lazy_regex::regex_captures;
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+	pub user_id: i64,
+	pub session_id: i64,
+	pub exp: i64, // unix seconds
+	/// Signed with the previous secret (being rotated)
+	pub previous_key: bool,
+}
+
+/// A new token for the user session, signed with the current secret.
+pub fn new_token(user_id: i64, session_id: i64) -> String {
+	let config = config();
+	sign_token(user_id, session_id, now() + config.token_ttl_secs, &config.token_secret)
+}
+
+/// Check the signature (current or previous secret) and the expiration.
+pub fn verify_token(token: &str) -> Result<Token, Error> {
+	let config = config();
+	verify_token_with(token, now(), &config.token_secret, config.token_secret_previous.as_deref())
+}
+
+/// A new token when the token is valid but close to its expiration, or signed with the previous secret.
+pub fn refresh_token(token: &str) -> Option<String> {
+	let token = verify_token(token).ok()?;
+	let refresh = token.previous_key || token.exp - now() < config().token_refresh_secs;
+	refresh.then(|| new_token(token.user_id, token.session_id))
+}
+
+fn sign_token(user_id: i64, session_id: i64, exp: i64, secret: &str) -> String {
+	let content = format!("user-{}.{}.{}", user_id, session_id, exp);
+	let sign = to_hex(&hmac(secret, &content).finalize().into_bytes());
+	format!("{}.{}", content, sign)
+}
+
+fn verify_token_with(token: &str, now: i64, secret: &str, previous: Option<&str>) -> Result<Token, Error> {
+	let (_whole, user_id, session_id, exp, sign) =
+		regex_captures!(r"^user-(\d+)\.(\d+)\.(\d+)\.([0-9a-f]{64})$", token).ok_or(Error::InvalidToken)?;
+	let user_id: i64 = user_id.parse().map_err(|_| Error::InvalidToken)?;
+	let session_id: i64 = session_id.parse().map_err(|_| Error::InvalidToken)?;
+	let exp: i64 = exp.parse().map_err(|_| Error::InvalidToken)?;
+	let sign = from_hex(sign).ok_or(Error::InvalidToken)?;
+
+	// `verify_slice` compares in constant time
+	let content = format!("user-{}.{}.{}", user_id, session_id, exp);
+	let previous_key = match hmac(secret, &content).verify_slice(&sign) {
+		Ok(()) => false,
+		Err(_) => match previous {
+			Some(previous) if hmac(previous, &content).verify_slice(&sign).is_ok() => true,
+			_ => return Err(Error::InvalidToken),
+		},
+	};
+
+	if exp <= now {
+		return Err(Error::TokenExpired);
+	}
+
+	Ok(Token { user_id, session_id, exp, previous_key })
+}
+
+fn hmac(secret: &str, content: &str) -> HmacSha256 {
+	let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+	mac.update(content.as_bytes());
+	mac
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+	(0..hex.len()).step_by(2).map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok()).collect()
+}
+
+fn now() -> i64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/security_token.rs"]
+mod tests;
+// endregion: Test
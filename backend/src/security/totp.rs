@@ -0,0 +1,212 @@
+// TOTP two-factor authentication (RFC 6238: HMAC-SHA1, 6 digits, 30 seconds steps), as in the authenticator apps.
+// The enrolment gives the secret (base32, in an otpauth URI), and is enabled by a first code, which gives the recovery codes.
+// Once enabled, the password login needs a code, or one of the single-use recovery codes. Admins have to enrol (see web::filter_auth).
+// After `totp_max_failures` wrong codes in a row, the user is locked out for `totp_lockout_secs`, against the guessing of the 6 digits.
+
+use super::{to_hex, Error, UserCtx};
+use crate::config::config;
+use crate::model::{Db, Role, TotpMac, UserMac};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type HmacSha1 = Hmac<Sha1>;
+
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+const SECRET_BYTES: usize = 20;
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_CODE_BYTES: usize = 10;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The secret of a new enrolment, to add to an authenticator app (usually by a QR code of the URI).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpEnrollment {
+	pub secret: String,
+	pub otpauth_uri: String,
+}
+
+/// The recovery codes, given only when the TOTP is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpRecoveryCodes {
+	pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TotpCode {
+	pub code: String,
+}
+
+/// Start the enrolment of the user with a new secret (until confirmed, a previous pending one is replaced).
+pub async fn enrol_totp(db: &Db, utx: &UserCtx) -> Result<TotpEnrollment, Error> {
+	let mut secret = [0u8; SECRET_BYTES];
+	OsRng.fill_bytes(&mut secret);
+	let secret = base32_encode(&secret);
+	if TotpMac::create_pending(db, utx.user_id, &secret).await?.is_none() {
+		return Err(Error::TotpState("two-factor authentication is already enabled".to_string()));
+	}
+
+	let user = UserMac::get(db, utx.user_id).await?;
+	let issuer = &config().totp_issuer;
+	let otpauth_uri = Url::parse_with_params(
+		&format!("otpauth://totp/{}:{}", issuer, user.username),
+		&[("secret", secret.as_str()), ("issuer", issuer), ("algorithm", "SHA1"), ("digits", "6"), ("period", "30")],
+	)
+	.map_err(|ex| Error::TotpState(format!("invalid otpauth URI - {}", ex)))?;
+
+	Ok(TotpEnrollment {
+		secret,
+		otpauth_uri: otpauth_uri.into(),
+	})
+}
+
+/// Enable the pending TOTP of the user with a first code, and give its recovery codes.
+pub async fn confirm_totp(db: &Db, utx: &UserCtx, code: &str) -> Result<TotpRecoveryCodes, Error> {
+	match TotpMac::get(db, utx.user_id).await? {
+		Some(totp) if !totp.enabled => verify_code(db, utx.user_id, &totp.secret, code).await?,
+		Some(_) => return Err(Error::TotpState("two-factor authentication is already enabled".to_string())),
+		None => return Err(Error::TotpState("no two-factor enrolment to confirm".to_string())),
+	}
+
+	let recovery_codes: Vec<String> = (0..RECOVERY_CODES).map(|_| new_recovery_code()).collect();
+	let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_recovery_code(code)).collect();
+	TotpMac::enable(db, utx.user_id, &hashes).await?;
+
+	Ok(TotpRecoveryCodes { recovery_codes })
+}
+
+/// Disable the TOTP of the user, with a code (or a recovery code). Not for admins, who must keep it.
+pub async fn disable_totp(db: &Db, utx: &UserCtx, code: &str) -> Result<(), Error> {
+	if utx.role >= Role::Admin {
+		return Err(Error::TotpState("admin accounts must keep two-factor authentication".to_string()));
+	}
+	check_totp(db, utx.user_id, Some(code)).await?;
+	TotpMac::delete(db, utx.user_id).await?;
+	Ok(())
+}
+
+/// Check the code (or recovery code) of the user when its TOTP is enabled, each code being accepted only once,
+/// and none while the user is locked out after too many failed ones.
+pub async fn check_totp(db: &Db, user_id: i64, code: Option<&str>) -> Result<(), Error> {
+	let totp = match TotpMac::get(db, user_id).await? {
+		Some(totp) if totp.enabled => totp,
+		_ => return Ok(()),
+	};
+	let code = code.map(str::trim).filter(|code| !code.is_empty()).ok_or(Error::TotpRequired)?;
+
+	let config = config();
+	TotpMac::count_attempt(db, user_id, config.totp_max_failures, config.totp_lockout_secs).await?;
+	match code.len() == DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit()) {
+		true => verify_code(db, user_id, &totp.secret, code).await?,
+		false => match TotpMac::use_recovery_code(db, user_id, &hash_recovery_code(code)).await? {
+			true => (),
+			false => return Err(Error::InvalidTotp),
+		},
+	}
+	TotpMac::reset_attempts(db, user_id).await?;
+
+	Ok(())
+}
+
+/// Whether the TOTP of the user is enabled.
+pub async fn totp_enabled(db: &Db, user_id: i64) -> Result<bool, Error> {
+	Ok(TotpMac::get(db, user_id).await?.is_some_and(|totp| totp.enabled))
+}
+
+async fn verify_code(db: &Db, user_id: i64, secret: &str, code: &str) -> Result<(), Error> {
+	let secret = base32_decode(secret).ok_or(Error::InvalidTotp)?;
+	let step = verify_totp_with(&secret, code.trim(), now(), config().totp_skew_steps).ok_or(Error::InvalidTotp)?;
+	match TotpMac::use_step(db, user_id, step).await? {
+		true => Ok(()),
+		false => Err(Error::InvalidTotp), // replayed
+	}
+}
+
+/// The time step of the code, when it is the one of a step within `skew_steps` of the time (unix seconds).
+pub(super) fn verify_totp_with(secret: &[u8], code: &str, time: i64, skew_steps: i64) -> Option<i64> {
+	let step = time / STEP_SECS;
+	(step - skew_steps..=step + skew_steps).find(|step| {
+		let expected = totp_code(secret, *step);
+		// compare all the digits, so the time does not tell how many are right
+		expected.len() == code.len() && expected.bytes().zip(code.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+	})
+}
+
+/// The code of the time step (RFC 4226 dynamic truncation).
+pub fn totp_code(secret: &[u8], step: i64) -> String {
+	let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC takes keys of any size");
+	mac.update(&(step as u64).to_be_bytes());
+	let hash = mac.finalize().into_bytes();
+	let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+	let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+	format!("{:0width$}", binary % 10u32.pow(DIGITS), width = DIGITS as usize)
+}
+
+/// The code of now, for a base32 secret (e.g., the dev seed one in the tests).
+pub fn totp_now(secret: &str) -> Option<String> {
+	base32_decode(secret).map(|secret| totp_code(&secret, now() / STEP_SECS))
+}
+
+/// A random code of 4 dash separated groups of 5 hex digits (80 bits).
+fn new_recovery_code() -> String {
+	let mut bytes = [0u8; RECOVERY_CODE_BYTES];
+	OsRng.fill_bytes(&mut bytes);
+	let code = to_hex(&bytes);
+	let groups: Vec<&str> = (0..code.len()).step_by(5).map(|i| &code[i..i + 5]).collect();
+	groups.join("-")
+}
+
+/// The hash of the code, whatever its case and dashes.
+fn hash_recovery_code(code: &str) -> String {
+	let code: String = code.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect();
+	to_hex(&Sha256::digest(code.as_bytes()))
+}
+
+// region: Base32
+/// RFC 4648 base32, without padding (as in the otpauth URIs).
+pub(super) fn base32_encode(bytes: &[u8]) -> String {
+	let mut encoded = String::new();
+	for chunk in bytes.chunks(5) {
+		let mut buffer = [0u8; 5];
+		buffer[..chunk.len()].copy_from_slice(chunk);
+		let bits = u64::from_be_bytes([0, 0, 0, buffer[0], buffer[1], buffer[2], buffer[3], buffer[4]]);
+		let chars = (chunk.len() * 8).div_ceil(5);
+		for i in 0..chars {
+			encoded.push(BASE32_ALPHABET[((bits >> (35 - i * 5)) & 0x1f) as usize] as char);
+		}
+	}
+	encoded
+}
+
+/// Decode base32, whatever its case, spaces and padding. None when it is not base32.
+pub(super) fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+	let mut bytes = Vec::new();
+	let (mut bits, mut count) = (0u64, 0);
+	for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+		let value = BASE32_ALPHABET.iter().position(|a| *a as char == c.to_ascii_uppercase())?;
+		bits = (bits << 5) | value as u64;
+		count += 5;
+		if count >= 8 {
+			count -= 8;
+			bytes.push((bits >> count) as u8);
+			bits &= (1 << count) - 1;
+		}
+	}
+	Some(bytes)
+}
+// endregion: Base32
+
+fn now() -> i64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/security_totp.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,195 @@
+use super::{cite, encode, CiteFormat, Error, ExportFormat};
+use crate::model::{Quote, QuoteFilter, QuoteMac, QuoteSort, QuoteStatus, Role, init_db};
+use crate::security::UserCtx;
+use futures::TryStreamExt;
+use serde_json::Value;
+
+#[tokio::test]
+async fn export_csv_ok() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let quotes = QuoteMac::stream(&db, &utx, &QuoteFilter::default())?;
+
+	//ACTION
+	let chunks: Vec<Vec<u8>> = encode(quotes, ExportFormat::Csv).try_collect().await?;
+
+	//CHECK
+	let csv = String::from_utf8(chunks.concat())?;
+	let lines: Vec<&str> = csv.lines().collect();
+	assert_eq!(3, chunks.len(), "one chunk for the header, then one per quote");
+	assert_eq!("id,quote,author,source,locator,tags,lang,translator,original_id", lines[0]);
+	assert_eq!("101,test quote 101,unknown,,,,und,,", lines[1]);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn export_json_filtered() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let filter = QuoteFilter {
+		sort: Some(QuoteSort::Popular),
+		..Default::default()
+	};
+
+	//ACTION
+	let quotes = QuoteMac::stream(&db, &utx, &filter)?;
+	let chunks: Vec<Vec<u8>> = encode(quotes, ExportFormat::Json).try_collect().await?;
+
+	//CHECK
+	let json: Value = serde_json::from_slice(&chunks.concat())?;
+	let ids: Vec<i64> = json.as_array().ok_or("should be an array")?.iter().filter_map(|q| q["id"].as_i64()).collect();
+	assert_eq!(vec![100, 101], ids, "most favorited first");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn export_json_empty() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let filter = QuoteFilter {
+		lang: Some("fr".to_string()),
+		..Default::default()
+	};
+
+	//ACTION
+	let quotes = QuoteMac::stream(&db, &utx, &filter)?;
+	let chunks: Vec<Vec<u8>> = encode(quotes, ExportFormat::Json).try_collect().await?;
+
+	//CHECK
+	let json: Value = serde_json::from_slice(&chunks.concat())?;
+	assert_eq!(Some(0), json.as_array().map(Vec::len));
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn export_fortune_strfile() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let filter = QuoteFilter {
+		collection_id: Some(100),
+		..Default::default()
+	};
+
+	//ACTION
+	let quotes = QuoteMac::stream(&db, &utx, &filter)?;
+	let text: Vec<Vec<u8>> = encode(quotes, ExportFormat::Fortune).try_collect().await?;
+	let quotes = QuoteMac::stream(&db, &utx, &filter)?;
+	let dat: Vec<Vec<u8>> = encode(quotes, ExportFormat::Strfile).try_collect().await?;
+
+	//CHECK
+	let text = String::from_utf8(text.concat())?;
+	assert_eq!("test quote 101\n%\ntest quote 100\n\t\t-- test author\n%\n", text);
+	let dat = dat.concat();
+	let words: Vec<u32> = dat.chunks(4).map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]])).collect();
+	// version, count, longest, shortest, flags
+	assert_eq!(vec![2, 2, 32, 15, 0], words[..5].to_vec());
+	assert_eq!(b'%', dat[20], "delimiter");
+	// offsets of the entries, then of the end of file
+	assert_eq!(vec![0, 17, text.len() as u32], words[6..].to_vec());
+
+	Ok(())
+}
+
+#[test]
+fn export_cite_formats() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let quote = Quote {
+		id: 1000,
+		cid: 123,
+		quote: "Imagination is more important than knowledge.".to_string(),
+		author: "Albert Einstein".to_string(),
+		source: Some("On Science & Religion".to_string()),
+		locator: Some("p. 12".to_string()),
+		tags: vec![],
+		lang: "en".to_string(),
+		original_id: None,
+		translator: None,
+		version: 1,
+		favorite_count: 0,
+		status: QuoteStatus::Approved,
+		moderation_reason: None,
+		is_favorite: None,
+	};
+	let quotes = [quote];
+
+	//ACTION
+	let bibtex = cite(&quotes, CiteFormat::Bibtex)?;
+	let csl: serde_json::Value = serde_json::from_str(&cite(&quotes, CiteFormat::CslJson)?)?;
+	let apa = cite(&quotes, CiteFormat::Apa)?;
+	let mla = cite(&quotes, CiteFormat::Mla)?;
+
+	//CHECK
+	assert!(bibtex.starts_with("@misc{einstein1000,\n  author = {Einstein, Albert},\n  title = {On Science \\& Religion},\n  pages = {12},"));
+	assert_eq!("Einstein", csl[0]["author"][0]["family"]);
+	assert_eq!("12", csl[0]["page"]);
+	assert_eq!("Einstein, A. (n.d.). On Science & Religion (p. 12).\n", apa);
+	assert_eq!("Einstein, Albert. On Science & Religion, p. 12.\n", mla);
+
+	Ok(())
+}
+
+#[test]
+fn export_cite_single_name_kindle_locator() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let quote = Quote {
+		id: 1000,
+		cid: 123,
+		quote: "Know thyself.".to_string(),
+		author: "Plato".to_string(),
+		source: Some(r"Dialogues ~ \Vol^2".to_string()),
+		locator: Some("p. 12, loc. 170-174".to_string()),
+		tags: vec![],
+		lang: "en".to_string(),
+		original_id: None,
+		translator: None,
+		version: 1,
+		favorite_count: 0,
+		status: QuoteStatus::Approved,
+		moderation_reason: None,
+		is_favorite: None,
+	};
+	let quotes = [quote];
+
+	//ACTION
+	let bibtex = cite(&quotes, CiteFormat::Bibtex)?;
+	let csl: serde_json::Value = serde_json::from_str(&cite(&quotes, CiteFormat::CslJson)?)?;
+	let unknown = "chicago".parse::<CiteFormat>();
+
+	//CHECK
+	assert!(bibtex.contains(r"  title = {Dialogues \textasciitilde{} \textbackslash{}Vol\textasciicircum{}2},"), "bibtex: {}", bibtex);
+	assert!(bibtex.contains("  pages = {12},\n  note = {p. 12, loc. 170-174},"), "bibtex: {}", bibtex);
+	assert_eq!(serde_json::json!([{ "family": "Plato" }]), csl[0]["author"], "no given name");
+	assert_eq!("12", csl[0]["page"]);
+	assert_eq!("p. 12, loc. 170-174", csl[0]["locator"]);
+	assert!(matches!(unknown, Err(Error::UnknownCiteFormat(_))));
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn export_cite_missing_metadata() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let quotes = [QuoteMac::get(&db, &utx, 100).await?, QuoteMac::get(&db, &utx, 101).await?];
+
+	//ACTION
+	let result = cite(&quotes, CiteFormat::Apa);
+
+	//CHECK
+	match result {
+		Err(Error::MissingCitationMetadata(message)) => {
+			assert_eq!("quote 100 has no source, quote 101 has no author and no source", message)
+		}
+		other => panic!("should be MissingCitationMetadata, was {:?}", other),
+	}
+
+	Ok(())
+}
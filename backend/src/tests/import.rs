@@ -0,0 +1,330 @@
+use super::{import_quotes, import_wikiquote, parse_mapping, ImportFormat, ImportOptions, ImportOutcome, WikiquoteOptions};
+use crate::export::{self, ExportFormat};
+use crate::model::{self, ListOptions, QuoteFilter, QuoteMac, QuotePatch, Role, init_db};
+use crate::security::UserCtx;
+use futures::TryStreamExt;
+
+#[tokio::test]
+async fn import_csv_mapping_ok() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let content = "Text,By,tags\n\
+		\"Simplicity is prerequisite for reliability.\",Edsger Dijkstra,\"software, design\"\n";
+	let mut options = ImportOptions::new(ImportFormat::Csv);
+	options.mapping = parse_mapping("quote:text,author:by")?;
+
+	//ACTION
+	let report = import_quotes(&db, &utx, content, &options).await?;
+
+	//CHECK
+	assert_eq!(1, report.created);
+	assert_eq!(2, report.records[0].line, "csv line of the record");
+	let id = report.records[0].id.ok_or("should have the created id")?;
+	let quote = QuoteMac::get(&db, &utx, id).await?;
+	assert_eq!("Edsger Dijkstra", quote.author);
+	assert_eq!(vec!["software", "design"], quote.tags);
+	assert_eq!(123, quote.cid);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_dry_run_report() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let content = r#"{"quote": "Stay hungry, stay foolish.", "author": "Stewart Brand"}
+{"quote": "  "}
+{"quote": "TEST QUOTE 100", "author": "Test Author"}
+{"quote": "stay hungry, stay foolish.", "author": "stewart brand"}
+{"quote": "Bonjour", "lang": "not a tag!"}
+not json"#;
+	let mut options = ImportOptions::new(ImportFormat::Ndjson);
+	options.dry_run = true;
+
+	//ACTION
+	let report = import_quotes(&db, &utx, content, &options).await?;
+
+	//CHECK
+	let outcomes: Vec<ImportOutcome> = report.records.iter().map(|r| r.outcome).collect();
+	use ImportOutcome::*;
+	assert_eq!(vec![Created, Rejected, Skipped, Skipped, Rejected, Rejected], outcomes);
+	assert_eq!((1, 2, 3), (report.created, report.skipped, report.rejected));
+	assert!(report.records[0].id.is_none(), "dry run should not create");
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
+	assert_eq!(2, quotes.len(), "only the seed quotes");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_dry_run_denied() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(789, Role::Viewer);
+	let mut options = ImportOptions::new(ImportFormat::Ndjson);
+	options.dry_run = true;
+
+	//ACTION
+	let result = import_quotes(&db, &utx, r#"{"quote": "test quote 100", "author": "test author"}"#, &options).await;
+
+	//CHECK
+	assert!(
+		matches!(result, Err(super::Error::Model(model::Error2::PermissionDenied(_)))),
+		"viewers cannot tell the existing quotes with a dry run, got {:?}",
+		result
+	);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_json_batches() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let content = r#"[
+		{"quote": "one", "author": "a"},
+		{"quote": "two", "author": "b", "tags": ["x"]},
+		{"quote": "three"}
+	]"#;
+	let mut options = ImportOptions::new(ImportFormat::Json);
+	options.batch_size = 2;
+
+	//ACTION
+	let report = import_quotes(&db, &utx, content, &options).await?;
+
+	//CHECK
+	assert_eq!(3, report.created);
+	assert_eq!(vec![2, 3, 4], report.records.iter().map(|r| r.line).collect::<Vec<_>>(), "lines of the objects");
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
+	assert_eq!(5, quotes.len());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_failed_batch_partial_report() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	// postgres rejects the NUL character of the second batch (only at insert, the duplicate check binds the quotes)
+	let content = r#"[
+		{"quote": "one", "author": "a"},
+		{"quote": "two", "author": "b\u0000"},
+		{"quote": "three", "author": "c"}
+	]"#;
+	let mut options = ImportOptions::new(ImportFormat::Json);
+	options.batch_size = 1;
+
+	//ACTION
+	let result = import_quotes(&db, &utx, content, &options).await;
+
+	//CHECK
+	let Err(super::Error::Interrupted { report, .. }) = result else {
+		panic!("should be interrupted, got {:?}", result);
+	};
+	assert_eq!(1, report.created);
+	let id = report.records[0].id.ok_or("should have the created id")?;
+	assert_eq!("one", QuoteMac::get(&db, &utx, id).await?.quote, "first batch committed");
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
+	assert_eq!(3, quotes.len(), "the seed quotes and the first batch");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_csv_missing_column() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let mut options = ImportOptions::new(ImportFormat::Csv);
+	options.mapping = parse_mapping("quote:text")?;
+
+	//ACTION
+	let result = import_quotes(&db, &utx, "quote,author\nhello,me\n", &options).await;
+
+	//CHECK
+	match result {
+		Err(super::Error::MissingColumn(column)) => assert_eq!("text", column),
+		other => panic!("should be MissingColumn, was {:?}", other),
+	}
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_fortune_ok() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let content = "%% a strfile comment\n\
+		A day without sunshine is like, you know, night.\n\
+		\t\t-- Steve Martin\n\
+		%\n\
+		Line one\n  indented line two\n\
+		%\n\
+		%\n";
+	let options = ImportOptions::new(ImportFormat::Fortune);
+
+	//ACTION
+	let report = import_quotes(&db, &utx, content, &options).await?;
+
+	//CHECK
+	assert_eq!(2, report.created, "empty entries are ignored");
+	assert_eq!(vec![2, 5], report.records.iter().map(|r| r.line).collect::<Vec<_>>());
+	let first = QuoteMac::get(&db, &utx, report.records[0].id.ok_or("should be created")?).await?;
+	assert_eq!("A day without sunshine is like, you know, night.", first.quote);
+	assert_eq!("Steve Martin", first.author);
+	let second = QuoteMac::get(&db, &utx, report.records[1].id.ok_or("should be created")?).await?;
+	assert_eq!("Line one\n  indented line two", second.quote);
+	assert_eq!("unknown", second.author);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_fortune_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let quote = "Percent lines:\n%%\n%\n% done\n %spaced";
+	let data = QuotePatch {
+		quote: Some(quote.to_string()),
+		author: Some("Test Author".to_string()),
+		..Default::default()
+	};
+	QuoteMac::create(&db, &utx, data).await?;
+
+	//ACTION
+	let quotes = QuoteMac::stream(&db, &utx, &QuoteFilter::default())?;
+	let text: Vec<Vec<u8>> = export::encode(quotes, ExportFormat::Fortune).try_collect().await?;
+	let records = super::fortune::parse_fortune(&String::from_utf8(text.concat())?);
+
+	//CHECK
+	assert_eq!(3, records.len(), "no entry split by the % lines");
+	let data = records.into_iter().filter_map(|r| r.data.ok()).find(|d| d.quote.as_deref().is_some_and(|q| q.starts_with("Percent")));
+	let data = data.ok_or("should be imported")?;
+	assert_eq!(Some(quote), data.quote.as_deref());
+	assert_eq!(Some("Test Author"), data.author.as_deref());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_kindle_ok() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let content = "\u{feff}Meditations (Marcus Aurelius)\r\n\
+		- Your Highlight on page 12 | Location 170-172 | Added on Sunday, May 3, 2020 10:15:42 PM\r\n\
+		\r\n\
+		You have power over your mind.\r\n\
+		==========\r\n\
+		Meditations (Marcus Aurelius)\r\n\
+		- Your Bookmark on page 13 | Location 180 | Added on Sunday, May 3, 2020 10:16:00 PM\r\n\
+		\r\n\
+		\r\n\
+		==========\r\n\
+		Meditations (Marcus Aurelius)\r\n\
+		- Your Highlight on page 12 | Location 170-74 | Added on Monday, May 4, 2020 9:01:00 AM\r\n\
+		\r\n\
+		You have power over your mind - not outside events.\r\n\
+		==========\r\n\
+		Meditations (Marcus Aurelius)\r\n\
+		- Your Note on page 12 | Location 172 | Added on Monday, May 4, 2020 9:02:00 AM\r\n\
+		\r\n\
+		Reread this one.\r\n\
+		==========\r\n";
+	let mut options = ImportOptions::new(ImportFormat::Kindle);
+	options.include_notes = true;
+
+	//ACTION
+	let report = import_quotes(&db, &utx, content, &options).await?;
+
+	//CHECK
+	assert_eq!((2, 0, 0), (report.created, report.skipped, report.rejected), "re-highlight kept, bookmark ignored");
+	let highlight = QuoteMac::get(&db, &utx, report.records[0].id.ok_or("should be created")?).await?;
+	assert_eq!("You have power over your mind - not outside events.", highlight.quote);
+	assert_eq!("Marcus Aurelius", highlight.author);
+	assert_eq!(Some("Meditations".to_string()), highlight.source);
+	assert_eq!(Some("p. 12, loc. 170-174".to_string()), highlight.locator);
+	let note = QuoteMac::get(&db, &utx, report.records[1].id.ok_or("should be created")?).await?;
+	assert_eq!("Reread this one.", note.quote);
+	assert_eq!("unknown", note.author, "notes are not the book author's");
+	assert_eq!(vec!["kindle-note"], note.tags);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn import_wikiquote_resume() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let dump = std::env::temp_dir().join("import_wikiquote_resume.xml");
+	std::fs::write(&dump, WIKIQUOTE_DUMP_FX)?;
+	let options = WikiquoteOptions {
+		dry_run: false,
+		batch_size: 1,
+		restart: false,
+	};
+
+	//ACTION
+	let report = import_wikiquote(&db, &utx, &dump, &options).await?;
+	let resumed = import_wikiquote(&db, &utx, &dump, &options).await?;
+
+	//CHECK
+	assert_eq!((2, 3), (report.pages, report.created), "film page and misattributed quote skipped");
+	assert_eq!(Some(12), report.last_page_id);
+	assert_eq!((Some(12), 0, 0), (resumed.resumed_after, resumed.pages, resumed.created));
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
+	let sourced = quotes.iter().find(|q| q.quote == "Imagination is more important than knowledge.").ok_or("should be imported")?;
+	assert_eq!("Albert Einstein", sourced.author);
+	assert_eq!(Some("On Science (1931)".to_string()), sourced.source);
+	assert_eq!(vec!["wikiquote", "sourced"], sourced.tags);
+	let disputed = quotes.iter().find(|q| q.author == "Albert Einstein" && q.tags.contains(&"disputed".to_string()));
+	assert_eq!(Some("God does not play dice."), disputed.map(|q| q.quote.as_str()));
+	assert!(!quotes.iter().any(|q| q.quote.contains("Insanity")), "misattributed skipped");
+
+	Ok(())
+}
+
+const WIKIQUOTE_DUMP_FX: &str = r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">
+  <siteinfo><sitename>Wikiquote</sitename></siteinfo>
+  <page>
+    <title>Albert Einstein</title>
+    <ns>0</ns>
+    <id>10</id>
+    <revision>
+      <id>9001</id>
+      <text xml:space="preserve">'''Albert Einstein''' was a physicist.
+== Sourced ==
+=== 1931 ===
+* [[Imagination]] is more important than ''knowledge''.&lt;ref&gt;note&lt;/ref&gt;
+** [[On Science]] (1931)
+== Disputed ==
+* God does not play dice.
+== Misattributed ==
+* Insanity is doing the same thing over and over.
+[[Category:1879 births]]</text>
+    </revision>
+  </page>
+  <page>
+    <title>The Matrix</title>
+    <ns>0</ns>
+    <id>11</id>
+    <revision><id>9002</id><text xml:space="preserve">== Quotes ==
+* There is no spoon.</text></revision>
+  </page>
+  <page>
+    <title>Ada Lovelace</title>
+    <ns>0</ns>
+    <id>12</id>
+    <revision><id>9003</id><text xml:space="preserve">== Quotes ==
+* The Analytical Engine weaves algebraic patterns.
+[[Category:1815 births]]</text></revision>
+  </page>
+</mediawiki>
+"#;
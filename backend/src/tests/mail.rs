@@ -0,0 +1,65 @@
+use super::{email_verification, mailer, password_reset, Error, Mailer, OutboxMailer};
+use crate::config::MailConfig;
+use std::env::temp_dir;
+use std::fs;
+
+const LINK: &str = "http://localhost:8080/#/reset-password?token=0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+// region: Mail Test Utils
+/// The email without its quoted-printable soft line breaks and `=` escapes (enough for the links).
+fn decode_quoted_printable(eml: &str) -> String {
+	eml.replace("=\r\n", "").replace("=\n", "").replace("=3D", "=")
+}
+// endregion: Mail Test Utils
+
+#[test]
+fn mail_templates() -> Result<(), Box<dyn std::error::Error>> {
+	//ACTION
+	let reset = password_reset("dave@example.com", "<dave>", LINK, 3600)?;
+	let verification = email_verification("dave@example.com", "dave", LINK, 2 * 24 * 3600)?;
+
+	//CHECK
+	assert_eq!(("dave@example.com", "Reset your password"), (reset.to.as_str(), reset.subject.as_str()));
+	assert!(reset.text.contains("Hello <dave>,") && reset.text.contains(LINK), "{}", reset.text);
+	assert!(reset.text.contains("valid 60 minutes"), "{}", reset.text);
+	assert!(reset.html.contains("Hello &#60;dave&#62;,"), "html escaped {}", reset.html);
+	assert!(reset.html.contains(&format!("href=\"{}\"", LINK)), "{}", reset.html);
+	assert!(reset.html.contains("<title>Reset your password</title>"), "layout {}", reset.html);
+	assert_eq!("Verify your email address", verification.subject);
+	assert!(verification.text.contains("valid 48 hours") && verification.text.contains(LINK), "{}", verification.text);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn mail_outbox() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let folder = temp_dir().join(format!("mail_outbox_{}", std::process::id()));
+	let _ = fs::remove_dir_all(&folder);
+	let outbox = OutboxMailer::new(folder.clone(), "quote-server <noreply@localhost>")?;
+	let config = MailConfig {
+		from: "not an address".to_string(),
+		..MailConfig::default()
+	};
+
+	//ACTION
+	outbox.send(password_reset("dave@example.com", "dave", LINK, 3600)?).await?;
+	let invalid_to = outbox.send(password_reset("not an address", "dave", LINK, 3600)?).await;
+	let invalid_from = mailer(&config);
+	let emails = outbox.emails()?;
+	let eml = decode_quoted_printable(&fs::read_to_string(&emails[0])?);
+	fs::remove_dir_all(&folder)?;
+
+	//CHECK
+	assert_eq!(1, emails.len(), "{:?}", emails);
+	assert!(eml.contains("From: quote-server <noreply@localhost>"), "{}", eml);
+	assert!(eml.contains("To: dave@example.com"), "{}", eml);
+	assert!(eml.contains("Subject: Reset your password"), "{}", eml);
+	assert!(eml.contains("multipart/alternative"), "text and html {}", eml);
+	assert!(eml.contains(&format!("href=\"{}\"", LINK)), "html part {}", eml);
+	assert!(eml.contains(LINK), "{}", eml);
+	assert!(matches!(invalid_to, Err(Error::InvalidAddress(_))), "{:?}", invalid_to);
+	assert!(matches!(invalid_from, Err(Error::InvalidAddress(_))), "{:?}", invalid_from.err());
+
+	Ok(())
+}
@@ -0,0 +1,75 @@
+use super::{ApiKeyForCreate, ApiKeyMac, ApiScope};
+use crate::model::db::init_db;
+use crate::model;
+use crate::model::Role;
+use crate::security::UserCtx;
+
+fn api_key_data(name: &str, scopes: Vec<ApiScope>) -> ApiKeyForCreate {
+	ApiKeyForCreate {
+		name: name.to_string(),
+		scopes,
+		expires: None,
+	}
+}
+
+#[tokio::test]
+async fn model_api_key_create_use_delete() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let other = UserCtx::new(456, Role::Contributor);
+
+	//ACTION
+	let api_key = ApiKeyMac::create(&db, &utx, api_key_data(" nightly import ", vec![ApiScope::QuotesWrite]), "qapi_0000", "hash-1").await?;
+	let used = ApiKeyMac::use_key(&db, "hash-1").await?;
+	let unknown = ApiKeyMac::use_key(&db, "hash-2").await?;
+	let listed = ApiKeyMac::list(&db, &utx).await?;
+	let not_mine = ApiKeyMac::delete(&db, &other, api_key.id).await;
+	let deleted = ApiKeyMac::delete(&db, &utx, api_key.id).await?;
+
+	//CHECK
+	assert_eq!(1000, api_key.id);
+	assert_eq!(("nightly import", vec![ApiScope::QuotesWrite]), (api_key.name.as_str(), api_key.scopes.clone()));
+	assert_eq!(None, api_key.last_used, "not used yet");
+	assert!(used.ok_or("key not found by its hash")?.last_used.is_some(), "last used tracked");
+	assert!(unknown.is_none());
+	assert_eq!(vec![api_key.id], listed.iter().map(|k| k.id).collect::<Vec<_>>());
+	assert!(matches!(not_mine, Err(model::Error2::EntityNotFound(_, _))), "{:?}", not_mine);
+	assert_eq!(api_key.id, deleted.id);
+	assert!(ApiKeyMac::use_key(&db, "hash-1").await?.is_none(), "revoked");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_api_key_expired_invalid() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let expiring = ApiKeyForCreate {
+		expires: Some(4102444800), // 2100-01-01
+		..api_key_data("expiring", vec![ApiScope::QuotesRead])
+	};
+	let api_key = ApiKeyMac::create(&db, &utx, expiring, "qapi_0000", "hash-1").await?;
+	sqlx::query("UPDATE api_key SET expires = now() - interval '1 minute' WHERE id = $1").bind(api_key.id).execute(&db).await?;
+
+	//ACTION
+	let expired = ApiKeyMac::use_key(&db, "hash-1").await?;
+	let no_name = ApiKeyMac::create(&db, &utx, api_key_data(" ", vec![ApiScope::QuotesRead]), "qapi_0001", "hash-2").await;
+	let no_scope = ApiKeyMac::create(&db, &utx, api_key_data("no scope", vec![]), "qapi_0002", "hash-3").await;
+	let past = ApiKeyForCreate {
+		expires: Some(1000),
+		..api_key_data("past", vec![ApiScope::QuotesRead])
+	};
+	let past = ApiKeyMac::create(&db, &utx, past, "qapi_0003", "hash-4").await;
+
+	//CHECK
+	assert_eq!(Some(4102444800), api_key.expires);
+	assert!(expired.is_none(), "expired key");
+	assert_eq!(1, ApiKeyMac::list(&db, &utx).await?.len(), "expired keys are still listed");
+	for result in [no_name, no_scope, past] {
+		assert!(matches!(result, Err(model::Error2::InvalidData(_))), "{:?}", result);
+	}
+
+	Ok(())
+}
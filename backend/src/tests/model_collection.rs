@@ -0,0 +1,177 @@
+use super::{CollectionMac, CollectionPatch, CollectionVisibility};
+use crate::model;
+use crate::model::db::init_db;
+use crate::model::{ListOptions, Quote, QuoteFilter, QuoteMac, QuotePatch, QuoteSort, Role};
+use futures::TryStreamExt;
+use crate::security::UserCtx;
+
+#[tokio::test]
+async fn model_collection_create_ok() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let data_fx = CollectionPatch {
+		title: Some("test - model_collection_create_ok".to_string()),
+		..Default::default()
+	};
+
+	//ACTION
+	let collection = CollectionMac::create(&db, &utx, data_fx).await?;
+
+	//CHECK
+	assert!(collection.id >= 1000, "Id should be >= 1000");
+	assert_eq!(123, collection.cid);
+	assert_eq!(CollectionVisibility::Private, collection.visibility);
+	assert!(collection.quote_ids.is_empty());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_get_private_of_other() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+
+	//ACTION
+	let result = CollectionMac::get(&db, &utx, 101).await;
+
+	//CHECK
+	match result {
+		Err(model::Error2::EntityNotFound(typ, id)) => {
+			assert_eq!("collection", typ);
+			assert_eq!("101", id);
+		}
+		other => panic!("Wrong result {:?} ", other),
+	}
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_list() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx_owner = UserCtx::new(456, Role::Contributor);
+	let utx_other = UserCtx::new(789, Role::Contributor);
+
+	//ACTION
+	let owner_collections = CollectionMac::list(&db, &utx_owner, &ListOptions::default()).await?;
+	let other_collections = CollectionMac::list(&db, &utx_other, &ListOptions::default()).await?;
+
+	//CHECK
+	assert_eq!(2, owner_collections.len(), "own private + public");
+	assert_eq!(1, other_collections.len(), "public only");
+	assert_eq!(100, other_collections[0].id);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_add_remove_reorder() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+
+	//ACTION - remove, add at the front, reorder
+	let removed = CollectionMac::remove_quote(&db, &utx, 100, 101).await?;
+	let added = CollectionMac::add_quote(&db, &utx, 100, 101, Some(0)).await?;
+	let reordered = CollectionMac::reorder(&db, &utx, 100, &[100, 101]).await?;
+
+	//CHECK
+	assert_eq!(vec![100], removed.quote_ids);
+	assert_eq!(vec![101, 100], added.quote_ids);
+	assert_eq!(vec![100, 101], reordered.quote_ids);
+	let quotes = CollectionMac::list_quotes(&db, &utx, 100, &ListOptions::default()).await?;
+	assert_eq!(vec![100, 101], quotes.iter().map(|q| q.id).collect::<Vec<_>>());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_add_concurrent() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let mut quote_ids = Vec::new();
+	for i in 0..8 {
+		let data = QuotePatch {
+			quote: Some(format!("test - model_collection_add_concurrent {}", i)),
+			..Default::default()
+		};
+		quote_ids.push(QuoteMac::create(&db, &utx, data).await?.id);
+	}
+
+	//ACTION - all at the front at once
+	let adds = quote_ids.iter().map(|quote_id| CollectionMac::add_quote(&db, &utx, 100, *quote_id, Some(0)));
+	for added in futures::future::join_all(adds).await {
+		added?;
+	}
+
+	//CHECK
+	let positions: Vec<(i32,)> = sqlx::query_as("SELECT position FROM collection_quote WHERE collection_id = 100 ORDER BY position")
+		.fetch_all(&db)
+		.await?;
+	assert_eq!((0..10).collect::<Vec<i32>>(), positions.into_iter().map(|(p,)| p).collect::<Vec<_>>(), "no duplicate or gap");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_reorder_invalid() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+
+	//ACTION
+	let result = CollectionMac::reorder(&db, &utx, 100, &[100]).await;
+
+	//CHECK
+	assert!(matches!(result, Err(model::Error2::InvalidData(_))), "missing quote 101");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_update_not_owner() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let data_fx = CollectionPatch {
+		title: Some("test - not mine".to_string()),
+		..Default::default()
+	};
+
+	//ACTION
+	let result = CollectionMac::update(&db, &utx, 100, data_fx).await;
+
+	//CHECK
+	assert!(matches!(result, Err(model::Error2::EntityNotFound("collection", _))), "public but not owned");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_quotes_list_in_order() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	CollectionMac::reorder(&db, &utx, 100, &[100, 101]).await?;
+	let filter = QuoteFilter {
+		collection_id: Some(100),
+		..Default::default()
+	};
+
+	//ACTION
+	let listed = QuoteMac::list(&db, &utx, &filter, &ListOptions::default()).await?;
+	let streamed: Vec<Quote> = QuoteMac::stream(&db, &utx, &filter)?.try_collect().await?;
+	let newest = QuoteFilter { sort: Some(QuoteSort::Newest), ..filter };
+	let newest = QuoteMac::list(&db, &utx, &newest, &ListOptions::default()).await?;
+
+	//CHECK
+	assert_eq!(vec![100, 101], listed.iter().map(|q| q.id).collect::<Vec<_>>(), "collection order");
+	assert_eq!(vec![100, 101], streamed.iter().map(|q| q.id).collect::<Vec<_>>(), "collection order");
+	assert_eq!(vec![101, 100], newest.iter().map(|q| q.id).collect::<Vec<_>>(), "explicit sort");
+
+	Ok(())
+}
@@ -1,4 +1,4 @@
-use super::init_db;
+use super::{init_db, split_sql};
 
 #[tokio::test]
 async fn model_db_init_db() -> Result<(), Box<dyn std::error::Error>> {
@@ -11,4 +11,18 @@ async fn model_db_init_db() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(2, result.len(), "number of seed quotes");
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn model_db_split_sql() {
+    // Fixture
+    let content = "INSERT INTO t VALUES ('a;b'); -- it's; a comment\nCREATE FUNCTION f() AS $$ BEGIN; END; $$ LANGUAGE plpgsql;";
+
+    // Action
+    let sqls = split_sql(content);
+
+    // Check
+    assert_eq!(3, sqls.len(), "number of statements");
+    assert_eq!("INSERT INTO t VALUES ('a;b')", sqls[0]);
+    assert!(sqls[1].ends_with("$$ BEGIN; END; $$ LANGUAGE plpgsql"));
+}
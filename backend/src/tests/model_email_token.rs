@@ -0,0 +1,54 @@
+use super::{EmailToken, EmailTokenMac, EmailTokenPurpose};
+use crate::model::db::init_db;
+
+fn email_token(user_id: i64) -> EmailToken {
+	EmailToken {
+		user_id,
+		email: "bob@example.com".to_string(),
+	}
+}
+
+#[tokio::test]
+async fn model_email_token_take() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	EmailTokenMac::create(&db, EmailTokenPurpose::PasswordReset, &email_token(456), "hash-1", 600).await?;
+	EmailTokenMac::create(&db, EmailTokenPurpose::EmailVerification, &email_token(456), "hash-2", 600).await?;
+	EmailTokenMac::create(&db, EmailTokenPurpose::PasswordReset, &email_token(789), "hash-3", 600).await?;
+	sqlx::query("UPDATE email_token SET expires = now() - interval '1 minute' WHERE token_hash = 'hash-3'").execute(&db).await?;
+
+	//ACTION
+	let other_purpose = EmailTokenMac::take(&db, EmailTokenPurpose::EmailVerification, "hash-1").await?;
+	let taken = EmailTokenMac::take(&db, EmailTokenPurpose::PasswordReset, "hash-1").await?;
+	let taken_again = EmailTokenMac::take(&db, EmailTokenPurpose::PasswordReset, "hash-1").await?;
+	let expired = EmailTokenMac::take(&db, EmailTokenPurpose::PasswordReset, "hash-3").await?;
+
+	//CHECK
+	assert!(other_purpose.is_none(), "a reset token does not verify an email");
+	assert_eq!(Some((456, "bob@example.com")), taken.as_ref().map(|t| (t.user_id, t.email.as_str())));
+	assert!(taken_again.is_none(), "a token is only taken once");
+	assert!(expired.is_none(), "expired token");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_email_token_replaced() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	EmailTokenMac::create(&db, EmailTokenPurpose::PasswordReset, &email_token(456), "hash-1", 600).await?;
+	EmailTokenMac::create(&db, EmailTokenPurpose::EmailVerification, &email_token(456), "hash-2", 600).await?;
+
+	//ACTION
+	EmailTokenMac::create(&db, EmailTokenPurpose::PasswordReset, &email_token(456), "hash-3", 600).await?;
+	let previous = EmailTokenMac::take(&db, EmailTokenPurpose::PasswordReset, "hash-1").await?;
+	let other_purpose = EmailTokenMac::take(&db, EmailTokenPurpose::EmailVerification, "hash-2").await?;
+	let latest = EmailTokenMac::take(&db, EmailTokenPurpose::PasswordReset, "hash-3").await?;
+
+	//CHECK
+	assert!(previous.is_none(), "replaced by the new token");
+	assert!(other_purpose.is_some(), "kept (other purpose)");
+	assert!(latest.is_some());
+
+	Ok(())
+}
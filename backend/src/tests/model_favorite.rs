@@ -0,0 +1,65 @@
+use super::FavoriteMac;
+use crate::model::db::init_db;
+use crate::model::{ListOptions, QuoteFilter, QuoteMac, QuoteSort, Role};
+use crate::security::UserCtx;
+
+#[tokio::test]
+async fn model_favorite_add_remove() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+
+	//ACTION
+	let added = FavoriteMac::add(&db, &utx, 101).await?;
+	let added_again = FavoriteMac::add(&db, &utx, 101).await?;
+	let removed = FavoriteMac::remove(&db, &utx, 101).await?;
+
+	//CHECK
+	assert_eq!(Some(true), added.is_favorite);
+	assert_eq!(1, added.favorite_count);
+	assert_eq!(1, added_again.favorite_count, "adding twice is a no-op");
+	assert_eq!(Some(false), removed.is_favorite);
+	assert_eq!(0, removed.favorite_count);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_favorite_list() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+
+	//ACTION
+	let quotes = FavoriteMac::list(&db, &utx, &ListOptions::default()).await?;
+
+	//CHECK
+	assert_eq!(1, quotes.len());
+	assert_eq!(100, quotes[0].id);
+	assert_eq!(Some(true), quotes[0].is_favorite);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_favorite_quote_list_popular() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let filter = QuoteFilter {
+		sort: Some(QuoteSort::Popular),
+		..Default::default()
+	};
+
+	//ACTION
+	let quotes = QuoteMac::list(&db, &utx, &filter, &ListOptions::default()).await?;
+
+	//CHECK
+	assert_eq!(100, quotes[0].id, "most favorited first");
+	assert_eq!(1, quotes[0].favorite_count);
+	assert_eq!(Some(true), quotes[0].is_favorite);
+	assert_eq!(101, quotes[1].id);
+	assert_eq!(Some(false), quotes[1].is_favorite);
+
+	Ok(())
+}
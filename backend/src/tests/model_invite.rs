@@ -0,0 +1,86 @@
+use super::{InviteForCreate, InviteMac};
+use crate::model::db::init_db;
+use crate::model::{self, Role, UserForCreate, UserMac};
+use crate::security::UserCtx;
+
+fn user_data(username: &str) -> UserForCreate {
+	UserForCreate {
+		username: username.to_string(),
+		email: format!("{}@example.com", username),
+		pwd_hash: "!".to_string(),
+	}
+}
+
+#[tokio::test]
+async fn model_invite_create_use_list() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let data = InviteForCreate {
+		role: Some(Role::Editor),
+		max_uses: Some(2),
+		..InviteForCreate::default()
+	};
+
+	//ACTION
+	let invite = InviteMac::create(&db, &utx, data, "inv_00000000", "hash-1").await?;
+	let dave = UserMac::create_invited(&db, user_data("dave"), "hash-1").await?;
+	let erin = UserMac::create_invited(&db, user_data("erin"), "hash-1").await?;
+	let used_up = UserMac::create_invited(&db, user_data("frank"), "hash-1").await?;
+	let unknown = UserMac::create_invited(&db, user_data("frank"), "hash-2").await?;
+	let listed = InviteMac::list(&db, &utx).await?;
+
+	//CHECK
+	assert_eq!((1000, 0, 2), (invite.id, invite.uses, invite.max_uses));
+	let dave = dave.ok_or("dave registered")?;
+	assert_eq!(Role::Editor, dave.role, "role of the invite");
+	assert!(erin.is_some(), "second use");
+	assert!(used_up.is_none(), "used up");
+	assert!(unknown.is_none(), "unknown code");
+	assert!(UserMac::get_id_by_email(&db, "frank@example.com").await?.is_none(), "no user without a valid invite");
+	assert_eq!(1, listed.len());
+	assert_eq!(2, listed[0].invite.uses);
+	let used_by: Vec<&str> = listed[0].used_by.iter().map(|u| u.username.as_str()).collect();
+	assert_eq!(vec!["dave", "erin"], used_by);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_invite_revoke_invalid() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let moderator = UserCtx::new(456, Role::Moderator);
+	let invite = InviteMac::create(&db, &utx, InviteForCreate::default(), "inv_00000000", "hash-1").await?;
+
+	//ACTION
+	let revoked = InviteMac::revoke(&db, &utx, invite.id).await?;
+	let after_revoke = UserMac::create_invited(&db, user_data("dave"), "hash-1").await?;
+	let not_admin = InviteMac::create(&db, &moderator, InviteForCreate::default(), "inv_00000001", "hash-2").await;
+	let not_admin_list = InviteMac::list(&db, &moderator).await;
+	let no_use = InviteForCreate {
+		max_uses: Some(0),
+		..InviteForCreate::default()
+	};
+	let no_use = InviteMac::create(&db, &utx, no_use, "inv_00000002", "hash-3").await;
+	let past = InviteForCreate {
+		expires: Some(946684800), // 2000-01-01
+		..InviteForCreate::default()
+	};
+	let past = InviteMac::create(&db, &utx, past, "inv_00000003", "hash-4").await;
+	let unknown = InviteMac::revoke(&db, &utx, 999).await;
+
+	//CHECK
+	assert_eq!((Role::Contributor, 1), (invite.role, invite.max_uses), "single-use contributor invite by default");
+	assert!(invite.expires > invite.ctime, "expires in the future by default");
+	assert!(revoked.expires <= revoked.ctime + 60, "expired now");
+	assert!(after_revoke.is_none(), "revoked");
+	assert!(matches!(not_admin, Err(model::Error2::PermissionDenied(_))), "{:?}", not_admin);
+	assert!(matches!(not_admin_list, Err(model::Error2::PermissionDenied(_))), "{:?}", not_admin_list);
+	assert!(matches!(no_use, Err(model::Error2::InvalidData(_))), "{:?}", no_use);
+	assert!(matches!(past, Err(model::Error2::InvalidData(_))), "{:?}", past);
+	assert!(matches!(unknown, Err(model::Error2::EntityNotFound(_, _))), "{:?}", unknown);
+
+	Ok(())
+}
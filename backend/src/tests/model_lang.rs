@@ -0,0 +1,34 @@
+use super::{best_lang_match, detect_lang, normalize_lang_tag, parse_accept_language};
+
+#[test]
+fn model_lang_normalize() {
+	assert_eq!("es-MX", normalize_lang_tag("es-mx").unwrap());
+	assert_eq!("zh-Hant-TW", normalize_lang_tag("ZH_hant_tw").unwrap());
+	assert_eq!("de", normalize_lang_tag(" de ").unwrap());
+	assert!(normalize_lang_tag("e").is_err());
+	assert!(normalize_lang_tag("en--US").is_err());
+	assert!(normalize_lang_tag("english language").is_err());
+}
+
+#[test]
+fn model_lang_detect() {
+	assert_eq!("en", detect_lang("The only thing we have to fear is fear itself, nothing more and nothing less."));
+	assert_eq!("es", detect_lang("La vida es sueño, y los sueños, sueños son, como dijo el poeta."));
+	assert_eq!("de", detect_lang("Ich denke, also bin ich, und das ist die Wahrheit für mich."));
+	assert_eq!("und", detect_lang("42"));
+}
+
+#[test]
+fn model_lang_accept_language() {
+	let ranges = parse_accept_language("de;q=0.7, es-MX, en;q=0.8, fr;q=0");
+	assert_eq!(vec!["es-MX", "en", "de"], ranges);
+}
+
+#[test]
+fn model_lang_best_match() {
+	let available = ["en", "es-ES", "de"];
+	assert_eq!(Some(1), best_lang_match(&available, &["es-MX".to_string()]), "primary language match");
+	assert_eq!(Some(2), best_lang_match(&available, &["fr".to_string(), "de".to_string()]), "preference order");
+	assert_eq!(None, best_lang_match(&available, &["fr".to_string()]));
+	assert_eq!(Some(0), best_lang_match(&available, &["*".to_string()]));
+}
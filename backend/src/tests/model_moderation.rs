@@ -0,0 +1,152 @@
+use super::ModerationMac;
+use crate::model::db::init_db;
+use crate::model::{self, ListOptions, QuoteFilter, QuoteMac, QuotePatch, QuoteStatus, Role};
+use crate::security::UserCtx;
+
+// A submitter whose quotes need moderation (as with QUOTE_MODERATION=untrusted).
+fn untrusted_utx(user_id: i64) -> UserCtx {
+	UserCtx { user_id, role: Role::Contributor, trusted: false, session_id: None, totp_enabled: false }
+}
+
+fn quote_patch(quote: &str) -> QuotePatch {
+	QuotePatch {
+		quote: Some(quote.to_string()),
+		..Default::default()
+	}
+}
+
+#[tokio::test]
+async fn model_moderation_pending_hidden() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let submitter = untrusted_utx(789);
+	let other = UserCtx::new(456, Role::Contributor);
+	let moderator = UserCtx::new(123, Role::Admin);
+
+	//ACTION
+	let quote = QuoteMac::create(&db, &submitter, quote_patch("pending quote")).await?;
+
+	//CHECK
+	assert_eq!(QuoteStatus::Pending, quote.status);
+	let listed = QuoteMac::list(&db, &other, &QuoteFilter::default(), &ListOptions::default()).await?;
+	assert!(listed.iter().all(|q| q.id != quote.id), "not in the public list");
+	match QuoteMac::get(&db, &other, quote.id).await {
+		Err(model::Error2::EntityNotFound(_, id)) => assert_eq!(quote.id.to_string(), id),
+		other => panic!("should be EntityNotFound, got {:?}", other),
+	}
+	assert_eq!(QuoteStatus::Pending, QuoteMac::get(&db, &submitter, quote.id).await?.status, "visible to its submitter");
+	assert_eq!(QuoteStatus::Pending, QuoteMac::get(&db, &moderator, quote.id).await?.status, "visible to moderators");
+	let filter = QuoteFilter {
+		status: Some(QuoteStatus::Pending),
+		..Default::default()
+	};
+	let mine = QuoteMac::list(&db, &submitter, &filter, &ListOptions::default()).await?;
+	assert_eq!(vec![quote.id], mine.iter().map(|q| q.id).collect::<Vec<_>>());
+	assert!(QuoteMac::list(&db, &other, &filter, &ListOptions::default()).await?.is_empty());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_moderation_approve_reject() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let submitter = untrusted_utx(789);
+	let moderator = UserCtx::new(123, Role::Admin);
+	let quote_1 = QuoteMac::create(&db, &submitter, quote_patch("quote to approve")).await?;
+	let quote_2 = QuoteMac::create(&db, &submitter, quote_patch("quote to reject")).await?;
+
+	//ACTION
+	let queue = ModerationMac::list_pending(&db, &moderator, &ListOptions::default()).await?;
+	let approved = ModerationMac::approve(&db, &moderator, quote_1.id, None).await?;
+	let rejected = ModerationMac::reject(&db, &moderator, quote_2.id, "duplicate".to_string()).await?;
+
+	//CHECK
+	assert_eq!(vec![quote_1.id, quote_2.id], queue.iter().map(|q| q.id).collect::<Vec<_>>(), "oldest first");
+	assert_eq!(QuoteStatus::Approved, approved.status);
+	assert_eq!(quote_1.version + 1, approved.version);
+	let rejected_seen = QuoteMac::get(&db, &submitter, quote_2.id).await?;
+	assert_eq!(QuoteStatus::Rejected, rejected_seen.status);
+	assert_eq!(Some("duplicate".to_string()), rejected.moderation_reason);
+	assert_eq!(Some("duplicate".to_string()), rejected_seen.moderation_reason, "the submitter sees the reason");
+	assert!(ModerationMac::list_pending(&db, &moderator, &ListOptions::default()).await?.is_empty());
+	let listed = QuoteMac::list(&db, &submitter, &QuoteFilter::default(), &ListOptions::default()).await?;
+	assert!(listed.iter().any(|q| q.id == quote_1.id), "approved quote listed");
+	assert!(listed.iter().all(|q| q.id != quote_2.id), "rejected quote not listed");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_moderation_untrusted_edit() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let submitter = untrusted_utx(789);
+	let moderator = UserCtx::new(123, Role::Admin);
+	let quote_1 = QuoteMac::create(&db, &submitter, quote_patch("quote to approve")).await?;
+	let quote_2 = QuoteMac::create(&db, &submitter, quote_patch("quote to reject")).await?;
+	ModerationMac::approve(&db, &moderator, quote_1.id, None).await?;
+	ModerationMac::reject(&db, &moderator, quote_2.id, "too short".to_string()).await?;
+
+	//ACTION
+	let edited_1 = QuoteMac::update(&db, &submitter, quote_1.id, quote_patch("quote approved, then edited"), None).await?;
+	let edited_2 = QuoteMac::update(&db, &submitter, quote_2.id, quote_patch("quote rejected, then made longer"), None).await?;
+	let moderator_edit = QuoteMac::update(&db, &moderator, quote_1.id, quote_patch("quote edited by a moderator"), None).await?;
+
+	//CHECK
+	assert_eq!(QuoteStatus::Pending, edited_1.status, "approved content changed");
+	assert_eq!(QuoteStatus::Pending, edited_2.status, "back in the queue");
+	assert_eq!(None, edited_2.moderation_reason, "the reason was for the old content");
+	assert_eq!(QuoteStatus::Pending, moderator_edit.status, "trusted edits keep the status");
+	let queue = ModerationMac::list_pending(&db, &moderator, &ListOptions::default()).await?;
+	assert_eq!(vec![quote_1.id, quote_2.id], queue.iter().map(|q| q.id).collect::<Vec<_>>());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_moderation_untrusted_edit_hidden() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let submitter = untrusted_utx(789);
+	let moderator = UserCtx::new(123, Role::Admin);
+	let quote = QuoteMac::create(&db, &submitter, quote_patch("quote to report")).await?;
+	ModerationMac::approve(&db, &moderator, quote.id, None).await?;
+	// as hidden by its reports (see ReportMac::create)
+	sqlx::query("UPDATE quote SET status = 'hidden' WHERE id = $1").bind(quote.id).execute(&db).await?;
+
+	//ACTION
+	let edited = QuoteMac::update(&db, &submitter, quote.id, quote_patch("quote reported, then edited"), None).await?;
+
+	//CHECK
+	assert_eq!(QuoteStatus::Hidden, edited.status, "still waiting for the resolution of the reports");
+	assert_eq!("quote reported, then edited", edited.quote);
+	assert!(ModerationMac::list_pending(&db, &moderator, &ListOptions::default()).await?.is_empty(), "not in the approval queue");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_moderation_errors() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let moderator = UserCtx::new(123, Role::Admin);
+	let other = UserCtx::new(456, Role::Contributor);
+
+	//ACTION
+	let not_moderator = ModerationMac::list_pending(&db, &other, &ListOptions::default()).await;
+	let no_reason = ModerationMac::reject(&db, &moderator, 100, " ".to_string()).await;
+	let not_pending = ModerationMac::approve(&db, &moderator, 100, None).await;
+	let not_found = ModerationMac::approve(&db, &moderator, 999, None).await;
+
+	//CHECK
+	assert!(matches!(not_moderator, Err(model::Error2::PermissionDenied(_))), "{:?}", not_moderator);
+	assert!(matches!(no_reason, Err(model::Error2::InvalidData(_))), "{:?}", no_reason);
+	match not_pending {
+		Err(model::Error2::InvalidData(message)) => assert!(message.contains("not pending"), "{}", message),
+		other => panic!("should be InvalidData, got {:?}", other),
+	}
+	assert!(matches!(not_found, Err(model::Error2::EntityNotFound(_, _))), "{:?}", not_found);
+
+	Ok(())
+}
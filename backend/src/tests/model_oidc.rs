@@ -0,0 +1,31 @@
+use super::{OidcLogin, OidcLoginMac};
+use crate::model::db::init_db;
+
+fn oidc_login(state: &str) -> OidcLogin {
+	OidcLogin {
+		state: state.to_string(),
+		nonce: "nonce".to_string(),
+		code_verifier: "verifier".to_string(),
+	}
+}
+
+#[tokio::test]
+async fn model_oidc_login_take() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	OidcLoginMac::create(&db, &oidc_login("state-1"), 600).await?;
+	OidcLoginMac::create(&db, &oidc_login("state-2"), 600).await?;
+	sqlx::query("UPDATE oidc_login SET expires = now() - interval '1 minute' WHERE state = 'state-2'").execute(&db).await?;
+
+	//ACTION
+	let taken = OidcLoginMac::take(&db, "state-1").await?;
+	let taken_again = OidcLoginMac::take(&db, "state-1").await?;
+	let expired = OidcLoginMac::take(&db, "state-2").await?;
+
+	//CHECK
+	assert_eq!(Some(("nonce", "verifier")), taken.as_ref().map(|l| (l.nonce.as_str(), l.code_verifier.as_str())));
+	assert!(taken_again.is_none(), "a login is only taken once");
+	assert!(expired.is_none(), "expired login");
+
+	Ok(())
+}
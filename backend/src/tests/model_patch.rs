@@ -0,0 +1,69 @@
+use super::{apply_json_patch, parse_json_patch};
+use crate::model;
+use serde_json::json;
+
+const ALLOWED: &[&str] = &["quote", "source", "tags"];
+
+#[test]
+fn model_patch_apply_ok() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let mut doc = json!({ "quote": "q", "source": "s", "tags": ["a", "c"] });
+	let ops = parse_json_patch(json!([
+		{ "op": "test", "path": "/quote", "value": "q" },
+		{ "op": "replace", "path": "/quote", "value": "q2" },
+		{ "op": "add", "path": "/tags/1", "value": "b" },
+		{ "op": "add", "path": "/tags/-", "value": "d" },
+		{ "op": "remove", "path": "/tags/0" },
+		{ "op": "remove", "path": "/source" },
+	]))?;
+
+	//ACTION
+	apply_json_patch(&mut doc, &ops, ALLOWED)?;
+
+	//CHECK
+	assert_eq!(json!({ "quote": "q2", "tags": ["b", "c", "d"] }), doc);
+
+	Ok(())
+}
+
+#[test]
+fn model_patch_test_failed() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let mut doc = json!({ "quote": "q" });
+	let ops = parse_json_patch(json!([{ "op": "test", "path": "/quote", "value": "other" }]))?;
+
+	//ACTION
+	let result = apply_json_patch(&mut doc, &ops, ALLOWED);
+
+	//CHECK
+	match result {
+		Err(model::Error2::InvalidPatch(msg)) => assert_eq!("test failed at '/quote'", msg),
+		other => panic!("Wrong result {:?} ", other),
+	}
+
+	Ok(())
+}
+
+#[test]
+fn model_patch_unsupported() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let mut doc = json!({ "quote": "q", "tags": [] });
+
+	//ACTION
+	let unsupported_path = apply_json_patch(&mut doc, &parse_json_patch(json!([{ "op": "replace", "path": "/id", "value": 1 }]))?, ALLOWED);
+	let out_of_range = apply_json_patch(&mut doc, &parse_json_patch(json!([{ "op": "remove", "path": "/tags/0" }]))?, ALLOWED);
+	let unsupported_op = parse_json_patch(json!([{ "op": "move", "from": "/quote", "path": "/source" }]));
+
+	//CHECK
+	match unsupported_path {
+		Err(model::Error2::InvalidPatch(msg)) => assert_eq!("unsupported path '/id'", msg),
+		other => panic!("Wrong result {:?} ", other),
+	}
+	match out_of_range {
+		Err(model::Error2::InvalidPatch(msg)) => assert_eq!("index out of range at '/tags/0'", msg),
+		other => panic!("Wrong result {:?} ", other),
+	}
+	assert!(matches!(unsupported_op, Err(model::Error2::InvalidPatch(_))), "move is not supported");
+
+	Ok(())
+}
@@ -0,0 +1,64 @@
+use super::{ensure, ensure_owned, Action, PermissionDenied};
+use crate::model::db::init_db;
+use crate::model::{self, ListOptions, ModerationMac, QuoteFilter, QuoteMac, QuotePatch, Role};
+use crate::security::UserCtx;
+
+fn quote_patch(quote: &str) -> QuotePatch {
+	QuotePatch {
+		quote: Some(quote.to_string()),
+		..Default::default()
+	}
+}
+
+#[test]
+fn model_permission_roles() {
+	let viewer = UserCtx::new(1, Role::Viewer);
+	let contributor = UserCtx::new(2, Role::Contributor);
+	let editor = UserCtx::new(3, Role::Editor);
+	let moderator = UserCtx::new(4, Role::Moderator);
+
+	assert!(ensure(&viewer, Action::ReadQuotes).is_ok());
+	assert!(ensure(&viewer, Action::CreateQuote).is_err());
+	assert!(ensure_owned(&contributor, Action::UpdateQuote, 2).is_ok(), "own quote");
+	assert!(ensure_owned(&contributor, Action::UpdateQuote, 3).is_err(), "quote of another");
+	assert!(ensure_owned(&viewer, Action::DeleteQuote, 1).is_err(), "viewers change nothing");
+	assert!(ensure_owned(&editor, Action::DeleteQuote, 2).is_ok());
+	assert!(ensure(&editor, Action::Moderate).is_err());
+	assert!(ensure(&moderator, Action::Moderate).is_ok());
+	assert!(editor.trusted, "editors publish without moderation");
+}
+
+#[tokio::test]
+async fn model_permission_quote_mac() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let viewer = UserCtx::new(789, Role::Viewer);
+	let contributor = UserCtx::new(456, Role::Contributor);
+	let editor = UserCtx::new(789, Role::Editor);
+
+	//ACTION
+	let viewer_list = QuoteMac::list(&db, &viewer, &QuoteFilter::default(), &ListOptions::default()).await?;
+	let viewer_create = QuoteMac::create(&db, &viewer, quote_patch("viewer quote")).await;
+	let other_update = QuoteMac::update(&db, &contributor, 100, quote_patch("not mine"), None).await;
+	let other_delete = QuoteMac::delete(&db, &contributor, 100, None).await;
+	let own = QuoteMac::create(&db, &contributor, quote_patch("my quote")).await?;
+	let own_update = QuoteMac::update(&db, &contributor, own.id, quote_patch("my quote, updated"), None).await?;
+	let editor_update = QuoteMac::update(&db, &editor, 100, quote_patch("edited"), None).await?;
+	let moderate = ModerationMac::list_pending(&db, &editor, &ListOptions::default()).await;
+
+	//CHECK
+	assert_eq!(2, viewer_list.len(), "viewers read");
+	match viewer_create {
+		Err(model::Error2::PermissionDenied(PermissionDenied { user_id, role, action })) => {
+			assert_eq!((789, Role::Viewer, Action::CreateQuote), (user_id, role, action))
+		}
+		other => panic!("should be PermissionDenied, got {:?}", other),
+	}
+	assert!(matches!(other_update, Err(model::Error2::PermissionDenied(_))), "{:?}", other_update);
+	assert!(matches!(other_delete, Err(model::Error2::PermissionDenied(_))), "{:?}", other_delete);
+	assert_eq!("my quote, updated", own_update.quote);
+	assert_eq!("edited", editor_update.quote);
+	assert!(matches!(moderate, Err(model::Error2::PermissionDenied(_))), "{:?}", moderate);
+
+	Ok(())
+}
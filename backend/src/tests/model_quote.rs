@@ -3,14 +3,16 @@
 use super::{Quote, QuoteMac};
 use crate::model;
 use crate::model::db::init_db;
-use crate::model::quote::{QuotePatch};
-use crate::security::utx_from_token;
+use crate::model::quote::{QuotePatch, Tags, TranslationForCreate};
+use crate::model::{ListOptions, Nullable, QuoteFilter, Role, parse_json_patch};
+use serde_json::json;
+use crate::security::UserCtx;
 
 #[tokio::test]
 async fn model_quote_create() -> Result<(), Box<dyn std::error::Error>> {
 	//FIXTURE
 	let db = init_db().await?;
-	let utx = utx_from_token(&db, "123").await?;
+	let utx = UserCtx::new(123, Role::Admin);
 	let data_fx = QuotePatch {
 		quote: Some("test - model_quote_create 1".to_string()),
 		..Default::default()
@@ -31,7 +33,7 @@ async fn model_quote_create() -> Result<(), Box<dyn std::error::Error>> {
 async fn model_quote_get_ok() -> Result<(), Box<dyn std::error::Error>> {
 	//FIXTURE
 	let db = init_db().await?;
-	let utx = utx_from_token(&db, "123").await?;
+	let utx = UserCtx::new(123, Role::Admin);
 
 	//ACTION
 	let quote = QuoteMac::get(&db, &utx, 100).await?;
@@ -48,7 +50,7 @@ async fn model_quote_get_ok() -> Result<(), Box<dyn std::error::Error>> {
 async fn model_quote_get_wong_id() -> Result<(), Box<dyn std::error::Error>> {
 	//FIXTURE
 	let db = init_db().await?;
-	let utx = utx_from_token(&db, "123").await?;
+	let utx = UserCtx::new(123, Role::Admin);
 
 	//ACTION
 	let result = QuoteMac::get(&db, &utx, 999).await;
@@ -70,7 +72,7 @@ async fn model_quote_get_wong_id() -> Result<(), Box<dyn std::error::Error>> {
 async fn model_quote_update_ok() -> Result<(), Box<dyn std::error::Error>> {
 	//FIXTURE
 	let db = init_db().await?;
-	let utx = utx_from_token(&db, "123").await?;
+	let utx = UserCtx::new(123, Role::Admin);
 	let data_fx = QuotePatch {
 		quote: Some("test - model_quote_update_ok 1".to_string()),
 		..Default::default()
@@ -82,13 +84,189 @@ async fn model_quote_update_ok() -> Result<(), Box<dyn std::error::Error>> {
 	};
 
 	//ACTION
-	let quote_updated = QuoteMac::update(&db, &utx, quote_fx.id, update_data_fx.clone()).await?;
+	let quote_updated = QuoteMac::update(&db, &utx, quote_fx.id, update_data_fx.clone(), None).await?;
 
 	//CHECK
-	let quotes = QuoteMac::list(&db, &utx).await?;
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
 	assert_eq!(3, quotes.len());
 	assert_eq!(quote_fx.id, quote_updated.id);
 	assert_eq!(update_data_fx.quote.unwrap(), quote_updated.quote);
+	assert_eq!(quote_fx.version + 1, quote_updated.version);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_update_version_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let data_fx = QuotePatch {
+		quote: Some("test - model_quote_update_version_mismatch".to_string()),
+		..Default::default()
+	};
+	let quote_v2 = QuoteMac::update(&db, &utx, 100, data_fx.clone(), Some(1)).await?;
+
+	//ACTION
+	let result = QuoteMac::update(&db, &utx, 100, data_fx, Some(1)).await;
+
+	//CHECK
+	assert_eq!(2, quote_v2.version);
+	match result {
+		Ok(_) => panic!("Should not succeed"),
+		Err(model::Error2::QuoteVersionMismatch(current)) => {
+			assert_eq!(100, current.id);
+			assert_eq!(2, current.version);
+		}
+		other_error => panic!("Wrong Error {:?} ", other_error),
+	}
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_update_merge_patch_clear() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let data_fx = QuotePatch {
+		source: Some(Nullable::Value("test source".to_string())),
+		tags: Some(Tags(vec!["a".to_string()])),
+		..Default::default()
+	};
+	QuoteMac::update(&db, &utx, 100, data_fx, None).await?;
+
+	//ACTION
+	let patch = QuotePatch::from_merge_patch(json!({ "source": null, "author": "test - author merged" }))?;
+	let quote = QuoteMac::update(&db, &utx, 100, patch, None).await?;
+
+	//CHECK
+	assert_eq!(None, quote.source);
+	assert_eq!("test - author merged", quote.author);
+	assert_eq!(vec!["a".to_string()], quote.tags, "tags left unchanged");
+	assert_eq!("test quote 100", quote.quote, "quote left unchanged");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_merge_patch_invalid() -> Result<(), Box<dyn std::error::Error>> {
+	//ACTION
+	let clear_quote = QuotePatch::from_merge_patch(json!({ "quote": null }));
+	let unknown = QuotePatch::from_merge_patch(json!({ "cid": 1 }));
+
+	//CHECK
+	assert!(matches!(clear_quote, Err(model::Error2::InvalidPatch(_))), "quote cannot be cleared");
+	match unknown {
+		Err(model::Error2::InvalidPatch(msg)) => assert_eq!("unsupported path '/cid'", msg),
+		other => panic!("Wrong result {:?} ", other),
+	}
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_json_patch_tags() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let ops = parse_json_patch(json!([
+		{ "op": "test", "path": "/author", "value": "test author" },
+		{ "op": "add", "path": "/tags/-", "value": "wisdom" },
+		{ "op": "add", "path": "/tags/0", "value": "classic" },
+		{ "op": "replace", "path": "/source", "value": "test source" },
+	]))?;
+
+	//ACTION
+	let quote = QuoteMac::patch(&db, &utx, 100, &ops, Some(1)).await?;
+
+	//CHECK
+	assert_eq!(vec!["classic".to_string(), "wisdom".to_string()], quote.tags);
+	assert_eq!(Some("test source".to_string()), quote.source);
+	assert_eq!(2, quote.version);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_create_detect_lang() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let detected_fx = QuotePatch {
+		quote: Some("Ich denke, also bin ich, und das ist die Wahrheit für mich.".to_string()),
+		..Default::default()
+	};
+	let given_fx = QuotePatch {
+		quote: Some("test - model_quote_create_detect_lang".to_string()),
+		lang: Some("en-gb".to_string()),
+		..Default::default()
+	};
+
+	//ACTION
+	let detected = QuoteMac::create(&db, &utx, detected_fx).await?;
+	let given = QuoteMac::create(&db, &utx, given_fx).await?;
+
+	//CHECK
+	assert_eq!("de", detected.lang);
+	assert_eq!("en-GB", given.lang);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_list_filter_lang() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let es_mx = QuotePatch {
+		quote: Some("test - model_quote_list_filter_lang".to_string()),
+		lang: Some("es-MX".to_string()),
+		..Default::default()
+	};
+	let es_mx = QuoteMac::create(&db, &utx, es_mx).await?;
+	let filter = |lang: &str| QuoteFilter { lang: Some(lang.to_string()), ..Default::default() };
+
+	//ACTION
+	let upper = QuoteMac::list(&db, &utx, &filter("ES"), &ListOptions::default()).await?;
+	let underscore = QuoteMac::list(&db, &utx, &filter("es_mx"), &ListOptions::default()).await?;
+	let wildcard = QuoteMac::list(&db, &utx, &filter("e%"), &ListOptions::default()).await;
+	let single = QuoteMac::list(&db, &utx, &filter("e_"), &ListOptions::default()).await;
+
+	//CHECK
+	assert_eq!(vec![es_mx.id], upper.iter().map(|q| q.id).collect::<Vec<_>>());
+	assert_eq!(vec![es_mx.id], underscore.iter().map(|q| q.id).collect::<Vec<_>>());
+	assert!(matches!(wildcard, Err(model::Error2::InvalidData(_))), "no LIKE wildcards");
+	assert!(matches!(single, Err(model::Error2::InvalidData(_))), "no LIKE wildcards");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_quote_translation_get_in_lang() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	QuoteMac::update(&db, &utx, 100, QuotePatch { lang: Some("en".to_string()), ..Default::default() }, None).await?;
+	let translation_fx = TranslationForCreate {
+		quote: "cita de prueba 100".to_string(),
+		lang: "es".to_string(),
+		translator: Some("test translator".to_string()),
+	};
+
+	//ACTION
+	let translation = QuoteMac::create_translation(&db, &utx, 100, translation_fx.clone()).await?;
+	let duplicate = QuoteMac::create_translation(&db, &utx, 100, translation_fx).await;
+	let in_es = QuoteMac::get_in_lang(&db, &utx, 100, &["es-MX".to_string()]).await?;
+	let in_fr = QuoteMac::get_in_lang(&db, &utx, translation.id, &["fr".to_string(), "en".to_string()]).await?;
+
+	//CHECK
+	assert_eq!(Some(100), translation.original_id);
+	assert_eq!("test author", translation.author, "author from the original");
+	assert_eq!(Some("test translator".to_string()), translation.translator);
+	assert!(matches!(duplicate, Err(model::Error2::InvalidData(_))), "one translation per lang");
+	assert_eq!(translation.id, in_es.id);
+	assert_eq!(100, in_fr.id, "no fr, so en original");
 
 	Ok(())
 }
@@ -97,10 +275,10 @@ async fn model_quote_update_ok() -> Result<(), Box<dyn std::error::Error>> {
 async fn model_quote_list() -> Result<(), Box<dyn std::error::Error>> {
 	//FIXTURE
 	let db = init_db().await?;
-	let utx = utx_from_token(&db, "123").await?;
+	let utx = UserCtx::new(123, Role::Admin);
 
 	//ACTION
-	let quotes = QuoteMac::list(&db, &utx).await?;
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
 
 	//CHECK
 	assert_eq!(2, quotes.len());
@@ -120,10 +298,10 @@ async fn model_quote_list() -> Result<(), Box<dyn std::error::Error>> {
 async fn model_quote_delete_simple() -> Result<(), Box<dyn std::error::Error>> {
 	//FIXTURE
 	let db = init_db().await?;
-	let utx = utx_from_token(&db, "123").await?;
+	let utx = UserCtx::new(123, Role::Admin);
 
 	//ACTION
-	let quote = QuoteMac::delete(&db, &utx, 100).await?;
+	let quote = QuoteMac::delete(&db, &utx, 100, None).await?;
 
 	//CHECK - deleted item
 	assert_eq!(100, quote.id);
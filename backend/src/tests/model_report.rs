@@ -0,0 +1,128 @@
+use super::{ReportForCreate, ReportMac, ReportOutcome, ReportReason, ReportResolution};
+use crate::model::db::init_db;
+use crate::model::{self, ListOptions, QuoteMac, QuotePatch, QuoteStatus, Role};
+use crate::security::UserCtx;
+
+fn report(reason: ReportReason) -> ReportForCreate {
+	ReportForCreate { reason, comment: None }
+}
+
+#[tokio::test]
+async fn model_report_auto_hide_dismiss() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let moderator = UserCtx::new(123, Role::Admin);
+	let reader = UserCtx::new(456, Role::Contributor);
+
+	//ACTION
+	for (user_id, reason) in [(1, ReportReason::Offensive), (2, ReportReason::Offensive), (3, ReportReason::Duplicate)] {
+		ReportMac::create(&db, &UserCtx::new(user_id, Role::Contributor), 100, report(reason)).await?;
+	}
+	let hidden = QuoteMac::get(&db, &reader, 100).await;
+	let summaries = ReportMac::list_open(&db, &moderator, &ListOptions::default()).await?;
+	let resolution = ReportResolution { outcome: ReportOutcome::Dismissed, note: None };
+	let resolved = ReportMac::resolve(&db, &moderator, 100, resolution).await?;
+
+	//CHECK
+	assert!(matches!(hidden, Err(model::Error2::EntityNotFound(_, _))), "hidden at 3 reports: {:?}", hidden);
+	assert_eq!(1, summaries.len());
+	assert_eq!((100, 3, QuoteStatus::Hidden), (summaries[0].quote_id, summaries[0].report_count, summaries[0].status));
+	assert_eq!(vec!["duplicate".to_string(), "offensive".to_string()], summaries[0].reasons);
+	assert_eq!(3, resolved.len());
+	assert!(resolved.iter().all(|r| r.outcome == Some(ReportOutcome::Dismissed) && r.resolved_by == Some(123)));
+	assert_eq!(QuoteStatus::Approved, QuoteMac::get(&db, &reader, 100).await?.status, "published again");
+	assert!(ReportMac::list_open(&db, &moderator, &ListOptions::default()).await?.is_empty());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_report_resolve_removed() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let moderator = UserCtx::new(123, Role::Admin);
+	let reader = UserCtx::new(456, Role::Contributor);
+	let data = ReportForCreate {
+		reason: ReportReason::Misattributed,
+		comment: Some("Not from this author".to_string()),
+	};
+	ReportMac::create(&db, &reader, 101, data).await?;
+
+	//ACTION
+	let no_note = ReportMac::resolve(&db, &moderator, 101, ReportResolution { outcome: ReportOutcome::Removed, note: None }).await;
+	let resolution = ReportResolution {
+		outcome: ReportOutcome::Removed,
+		note: Some("misattributed".to_string()),
+	};
+	ReportMac::resolve(&db, &moderator, 101, resolution).await?;
+	let again = ReportMac::resolve(&db, &moderator, 101, ReportResolution { outcome: ReportOutcome::Dismissed, note: None }).await;
+
+	//CHECK
+	assert!(matches!(no_note, Err(model::Error2::InvalidData(_))), "{:?}", no_note);
+	assert!(matches!(again, Err(model::Error2::InvalidData(_))), "no open report: {:?}", again);
+	let quote = QuoteMac::get(&db, &moderator, 101).await?;
+	assert_eq!(QuoteStatus::Rejected, quote.status);
+	assert_eq!(Some("misattributed".to_string()), quote.moderation_reason);
+	let audit = ReportMac::list_for_quote(&db, &moderator, 101).await?;
+	assert_eq!(1, audit.len());
+	assert_eq!(Some(ReportOutcome::Removed), audit[0].outcome);
+	assert_eq!(Some("Not from this author".to_string()), audit[0].comment);
+	let resolved_at = audit[0].resolved_at.ok_or("should have the resolution time")?;
+	assert!(audit[0].ctime > 0 && audit[0].ctime <= resolved_at, "{:?}", audit[0]);
+	assert!(matches!(ReportMac::list_for_quote(&db, &reader, 101).await, Err(model::Error2::PermissionDenied(_))));
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_report_duplicate_rate_limit() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let data = (0..10)
+		.map(|idx| QuotePatch {
+			quote: Some(format!("quote to report {}", idx)),
+			..Default::default()
+		})
+		.collect();
+	let quotes = QuoteMac::create_many(&db, &utx, data).await?;
+
+	//ACTION
+	ReportMac::create(&db, &utx, 100, report(ReportReason::Other)).await?;
+	let duplicate = ReportMac::create(&db, &utx, 100, report(ReportReason::Other)).await;
+	let mut results = Vec::new();
+	for quote in quotes.iter() {
+		results.push(ReportMac::create(&db, &utx, quote.id, report(ReportReason::Duplicate)).await);
+	}
+
+	//CHECK
+	assert!(matches!(duplicate, Err(model::Error2::InvalidData(_))), "{:?}", duplicate);
+	assert!(results[..9].iter().all(|r| r.is_ok()), "10 reports per hour");
+	assert!(matches!(results[9], Err(model::Error2::RateLimited(_))), "{:?}", results[9]);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_report_rate_limit_concurrent() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let data = (0..15)
+		.map(|idx| QuotePatch {
+			quote: Some(format!("quote to report {}", idx)),
+			..Default::default()
+		})
+		.collect();
+	let quotes = QuoteMac::create_many(&db, &utx, data).await?;
+
+	//ACTION
+	let creates = quotes.iter().map(|quote| ReportMac::create(&db, &utx, quote.id, report(ReportReason::Duplicate)));
+	let results = futures::future::join_all(creates).await;
+
+	//CHECK
+	assert_eq!(10, results.iter().filter(|r| r.is_ok()).count(), "10 reports per hour");
+	assert!(results.iter().filter_map(|r| r.as_ref().err()).all(|ex| matches!(ex, model::Error2::RateLimited(_))));
+
+	Ok(())
+}
@@ -0,0 +1,89 @@
+use super::{SessionForCreate, SessionMac};
+use crate::model::db::init_db;
+use crate::model::{self, Db, Role};
+use crate::security::UserCtx;
+
+fn session_utx(user_id: i64, session_id: i64) -> UserCtx {
+	UserCtx {
+		session_id: Some(session_id),
+		..UserCtx::new(user_id, Role::Contributor)
+	}
+}
+
+async fn expire_session(db: &Db, id: i64) -> Result<(), sqlx::Error> {
+	sqlx::query("UPDATE session SET expires = now() - interval '1 minute' WHERE id = $1").bind(id).execute(db).await?;
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_session_create_list() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let client = SessionForCreate {
+		ip: Some("192.0.2.1".to_string()),
+		user_agent: Some("test agent".to_string()),
+	};
+
+	//ACTION
+	let session = SessionMac::create(&db, 123, client).await?;
+	let touched = SessionMac::touch(&db, 123, session.id).await?;
+	let other_user = SessionMac::touch(&db, 456, session.id).await?;
+	let sessions = SessionMac::list(&db, &session_utx(123, session.id)).await?;
+
+	//CHECK
+	assert_eq!(1000, session.id);
+	assert_eq!((Some("192.0.2.1"), Some("test agent")), (session.ip.as_deref(), session.user_agent.as_deref()));
+	assert!(session.expires > session.ctime, "expires after its creation");
+	assert!(touched, "active session");
+	assert!(!other_user, "a session of another user");
+	let ids: Vec<(i64, bool)> = sessions.iter().map(|s| (s.id, s.current)).collect();
+	assert_eq!(vec![(1000, true), (123, false)], ids, "last seen first");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_session_delete() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = session_utx(123, 123);
+	let session_1 = SessionMac::create(&db, 123, SessionForCreate::default()).await?;
+	let session_2 = SessionMac::create(&db, 123, SessionForCreate::default()).await?;
+
+	//ACTION
+	let deleted = SessionMac::delete(&db, &utx, session_1.id).await?;
+	let not_mine = SessionMac::delete(&db, &utx, 456).await;
+	let all = SessionMac::delete_all(&db, &utx).await?;
+
+	//CHECK
+	assert_eq!(session_1.id, deleted.id);
+	assert!(!SessionMac::touch(&db, 123, session_1.id).await?, "revoked");
+	assert!(matches!(not_mine, Err(model::Error2::EntityNotFound(_, _))), "{:?}", not_mine);
+	assert!(SessionMac::touch(&db, 456, 456).await?, "the session of bob is kept");
+	let mut ids: Vec<i64> = all.iter().map(|s| s.id).collect();
+	ids.sort();
+	assert_eq!(vec![123, session_2.id], ids);
+	assert!(SessionMac::list(&db, &utx).await?.is_empty());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_session_expired() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	expire_session(&db, 456).await?;
+
+	//ACTION
+	let touched = SessionMac::touch(&db, 456, 456).await?;
+	let listed = SessionMac::list(&db, &session_utx(456, 456)).await?;
+	let deleted = SessionMac::delete_expired(&db).await?;
+
+	//CHECK
+	assert!(!touched, "expired session");
+	assert!(listed.is_empty(), "expired sessions are not listed");
+	assert_eq!(1, deleted);
+	assert!(SessionMac::touch(&db, 123, 123).await?, "active sessions are kept");
+
+	Ok(())
+}
@@ -0,0 +1,97 @@
+use super::{StatsCache, StatsMac, StatsOptions};
+use crate::model::db::init_db;
+use crate::model::{QuoteFilter, QuoteMac, QuotePatch, Role, Tags};
+use crate::security::UserCtx;
+use std::time::Duration;
+
+fn quote_patch(quote: &str, author: &str, tags: &[&str], lang: &str) -> QuotePatch {
+	QuotePatch {
+		quote: Some(quote.to_string()),
+		author: Some(author.to_string()),
+		tags: Some(Tags(tags.iter().map(|t| t.to_string()).collect())),
+		lang: Some(lang.to_string()),
+		..Default::default()
+	}
+}
+
+#[tokio::test]
+async fn model_stats_aggregates() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(456, Role::Contributor);
+	let data = vec![
+		quote_patch("quote a1", "A", &["x", "y"], "en"),
+		quote_patch("quote a2", "A", &["x", "y"], "en"),
+		quote_patch("quote a3", "A", &["x", "y"], "en"),
+		quote_patch("quote b1", "B", &["x"], "fr"),
+	];
+	QuoteMac::create_many(&db, &utx, data).await?;
+
+	//ACTION
+	let stats = StatsMac::stats(&db, &utx, &QuoteFilter::default(), &StatsOptions::default()).await?;
+	let filter = QuoteFilter {
+		lang: Some("fr".to_string()),
+		..Default::default()
+	};
+	let stats_fr = StatsMac::stats(&db, &utx, &filter, &StatsOptions::default()).await?;
+
+	//CHECK
+	assert_eq!(6, stats.total, "4 + 2 seed quotes");
+	let counts = |counts: &[super::StatCount]| counts.iter().map(|c| (c.key.clone(), c.count)).collect::<Vec<_>>();
+	assert_eq!(
+		vec![("A".to_string(), 3), ("B".to_string(), 1), ("test author".to_string(), 1), ("unknown".to_string(), 1)],
+		counts(&stats.by_author)
+	);
+	assert_eq!(vec![("456".to_string(), 4), ("123".to_string(), 2)], counts(&stats.by_creator));
+	assert_eq!(vec![("x".to_string(), 4), ("y".to_string(), 3)], counts(&stats.by_tag));
+	assert_eq!(counts(&stats.by_creator), counts(&stats.top_contributors));
+	assert_eq!(1, stats.by_period.len(), "all created today");
+	assert_eq!((6, 6), (stats.by_period[0].count, stats.by_period[0].total));
+	assert_eq!((6, 0, None), (stats.growth.current, stats.growth.previous, stats.growth.rate));
+	assert_eq!(1, stats_fr.total);
+	assert_eq!(vec![("B".to_string(), 1)], counts(&stats_fr.by_author));
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_stats_cache_ttl() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let cache = StatsCache::new(Duration::from_secs(60));
+	let no_cache = StatsCache::new(Duration::ZERO);
+	let (filter, options) = (QuoteFilter::default(), StatsOptions::default());
+	cache.stats(&db, &utx, &filter, &options).await?;
+
+	//ACTION
+	QuoteMac::create(&db, &utx, quote_patch("new quote", "C", &[], "en")).await?;
+	let cached = cache.stats(&db, &utx, &filter, &options).await?;
+	let fresh = no_cache.stats(&db, &utx, &filter, &options).await?;
+
+	//CHECK
+	assert_eq!(2, cached.total, "computed before the new quote");
+	assert_eq!(3, fresh.total);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn model_stats_period_after_edit() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	sqlx::query("UPDATE quote SET ctime = now() - interval '3 days' WHERE id = 100").execute(&db).await?;
+	let before = StatsMac::stats(&db, &utx, &QuoteFilter::default(), &StatsOptions::default()).await?;
+
+	//ACTION
+	QuoteMac::update(&db, &utx, 100, quote_patch("quote 100, edited", "test author", &[], "en"), None).await?;
+	let after = StatsMac::stats(&db, &utx, &QuoteFilter::default(), &StatsOptions::default()).await?;
+
+	//CHECK
+	let periods = |stats: &super::QuoteStats| stats.by_period.iter().map(|p| (p.period.clone(), p.count)).collect::<Vec<_>>();
+	assert_eq!(2, before.by_period.len(), "created 3 days ago and today");
+	assert_eq!(periods(&before), periods(&after), "an edit is not a creation");
+
+	Ok(())
+}
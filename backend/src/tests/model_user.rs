@@ -0,0 +1,27 @@
+use super::{UserForCreate, UserMac};
+use crate::model::db::init_db;
+
+#[tokio::test]
+async fn model_user_create_get() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let data = UserForCreate {
+		username: "Dave".to_string(),
+		email: "Dave@Example.com".to_string(),
+		pwd_hash: "not-a-hash".to_string(),
+	};
+
+	//ACTION
+	let user = UserMac::create(&db, data).await?;
+	let user_get = UserMac::get(&db, user.id).await?;
+
+	//CHECK
+	assert_eq!(1000, user.id);
+	assert_eq!(("dave", "dave@example.com", false), (user_get.username.as_str(), user_get.email.as_str(), user_get.disabled));
+	assert!(UserMac::exists(&db, "DAVE", "other@example.com").await?, "same username, other case");
+	assert!(UserMac::exists(&db, "other", "alice@example.com").await?, "seed email");
+	assert!(!UserMac::exists(&db, "other", "other@example.com").await?);
+	assert_eq!("not-a-hash", UserMac::get_for_auth(&db, "dave").await?.map(|u| u.pwd_hash).unwrap_or_default());
+
+	Ok(())
+}
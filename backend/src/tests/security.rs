@@ -0,0 +1,123 @@
+use super::{create_invite, login, register, register_with, utx_from_token, Error, LoginForm, UserCtx, UserForRegister};
+use crate::model::{init_db, InviteForCreate, Role, SessionForCreate};
+
+fn login_form(username: &str, password: &str) -> LoginForm {
+	LoginForm {
+		username: username.to_string(),
+		password: password.to_string(),
+		totp: None,
+	}
+}
+
+#[tokio::test]
+async fn security_register_login() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let data = UserForRegister {
+		username: "dave".to_string(),
+		email: "dave@example.com".to_string(),
+		password: "correct horse".to_string(),
+		invite: None,
+	};
+
+	//ACTION
+	let user = register(&db, data.clone()).await?;
+	let again = register(&db, data).await;
+	let result = login(&db, login_form("Dave", "correct horse"), SessionForCreate::default()).await?;
+	let utx = utx_from_token(&db, &result.token).await?;
+
+	//CHECK
+	assert!(matches!(again, Err(Error::InvalidRegistration(_))), "{:?}", again);
+	assert_eq!(user.id, result.user.id);
+	assert!(result.token.starts_with(&format!("user-{}.", user.id)), "{}", result.token);
+	assert_eq!(user.id, utx.user_id);
+	let stored: (String,) = sqlx::query_as("SELECT pwd_hash FROM user_account WHERE id = $1").bind(user.id).fetch_one(&db).await?;
+	assert!(stored.0.starts_with("$argon2id$"), "argon2id hash");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_login_errors() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let result = login(&db, login_form("bob", "welcome-dev"), SessionForCreate::default()).await?;
+	sqlx::query("UPDATE user_account SET disabled = true WHERE id = 456").execute(&db).await?;
+
+	//ACTION
+	let wrong_pwd = login(&db, login_form("alice", "welcome-dev!"), SessionForCreate::default()).await;
+	let unknown = login(&db, login_form("nobody", "welcome-dev"), SessionForCreate::default()).await;
+	let disabled = login(&db, login_form("bob", "welcome-dev"), SessionForCreate::default()).await;
+	let disabled_token = utx_from_token(&db, &result.token).await;
+	let forged = utx_from_token(&db, "1").await;
+
+	//CHECK
+	assert!(matches!(wrong_pwd, Err(Error::InvalidCredentials)), "{:?}", wrong_pwd.err());
+	assert!(matches!(unknown, Err(Error::InvalidCredentials)), "{:?}", unknown.err());
+	assert!(matches!(disabled, Err(Error::UserDisabled)), "{:?}", disabled.err());
+	assert!(matches!(disabled_token, Err(Error::UserDisabled)), "token of a disabled user");
+	assert!(matches!(forged, Err(Error::InvalidToken)), "a user id is not a token");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_register_invalid() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let data = |username: &str, email: &str, password: &str| UserForRegister {
+		username: username.to_string(),
+		email: email.to_string(),
+		password: password.to_string(),
+		invite: None,
+	};
+
+	//ACTION
+	let results = vec![
+		register(&db, data("x", "x@example.com", "long enough")).await,
+		register(&db, data("xavier", "not-an-email", "long enough")).await,
+		register(&db, data("xavier", "x@example.com", "short")).await,
+	];
+
+	//CHECK
+	for result in results {
+		assert!(matches!(result, Err(Error::InvalidRegistration(_))), "{:?}", result);
+	}
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_register_invite_only() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let invite = InviteForCreate {
+		role: Some(Role::Moderator),
+		..InviteForCreate::default()
+	};
+	let created = create_invite(&db, &utx, invite).await?;
+	let data = |username: &str, invite: Option<&str>| UserForRegister {
+		username: username.to_string(),
+		email: format!("{}@example.com", username),
+		password: "correct horse".to_string(),
+		invite: invite.map(str::to_string),
+	};
+
+	//ACTION
+	let no_invite = register_with(&db, data("dave", None), true).await;
+	let wrong_invite = register_with(&db, data("dave", Some("inv_0000")), true).await;
+	let dave = register_with(&db, data("dave", Some(&format!(" {} ", created.code))), true).await?;
+	let used_up = register_with(&db, data("erin", Some(&created.code)), true).await;
+	let open = register_with(&db, data("frank", None), false).await?;
+
+	//CHECK
+	assert!(created.code.starts_with(&created.invite.prefix), "{} {}", created.code, created.invite.prefix);
+	assert!(matches!(no_invite, Err(Error::InvalidRegistration(_))), "{:?}", no_invite);
+	assert!(matches!(wrong_invite, Err(Error::InvalidRegistration(_))), "{:?}", wrong_invite);
+	assert_eq!(Role::Moderator, dave.role, "role of the invite");
+	assert!(matches!(used_up, Err(Error::InvalidRegistration(_))), "single-use {:?}", used_up);
+	assert_eq!(Role::Contributor, open.role, "open registration");
+
+	Ok(())
+}
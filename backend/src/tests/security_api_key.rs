@@ -0,0 +1,41 @@
+use super::{create_api_key, utx_from_api_key, API_KEY_PREFIX};
+use crate::model::{ApiKeyForCreate, ApiScope, Role, init_db};
+use crate::security::{Error, UserCtx};
+
+#[tokio::test]
+async fn security_api_key_create_auth() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let data = ApiKeyForCreate {
+		name: "backup script".to_string(),
+		scopes: vec![ApiScope::QuotesRead],
+		expires: None,
+	};
+
+	//ACTION
+	let created = create_api_key(&db, &UserCtx::new(456, Role::Contributor), data).await?;
+	let (utx, scopes) = utx_from_api_key(&db, &created.key).await?;
+	let wrong_key = utx_from_api_key(&db, &format!("{}{}", API_KEY_PREFIX, "0".repeat(64))).await;
+	let not_a_key = utx_from_api_key(&db, "123").await;
+
+	//CHECK
+	assert!(created.key.starts_with("qapi_") && created.key.len() == 69, "{}", created.key);
+	assert!(created.key.starts_with(&created.api_key.prefix), "shown prefix of the key");
+	let (stored,): (String,) = sqlx::query_as("SELECT key_hash FROM api_key WHERE id = $1").bind(created.api_key.id).fetch_one(&db).await?;
+	assert!(!stored.contains(&created.key[5..]), "only the hash is stored");
+	assert_eq!(456, utx.user_id);
+	assert_eq!(None, utx.session_id);
+	assert_eq!(vec![ApiScope::QuotesRead], scopes);
+	assert!(matches!(wrong_key, Err(Error::InvalidApiKey)), "{:?}", wrong_key.err());
+	assert!(matches!(not_a_key, Err(Error::InvalidApiKey)), "{:?}", not_a_key.err());
+
+	Ok(())
+}
+
+#[test]
+fn security_api_key_scopes() {
+	assert!(ApiScope::Admin.allows(ApiScope::QuotesWrite));
+	assert!(ApiScope::QuotesWrite.allows(ApiScope::QuotesRead));
+	assert!(!ApiScope::QuotesWrite.allows(ApiScope::Admin));
+	assert!(!ApiScope::QuotesRead.allows(ApiScope::QuotesWrite));
+}
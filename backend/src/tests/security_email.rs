@@ -0,0 +1,123 @@
+use super::{request_password_reset, reset_password, send_email_verification, verify_email, EmailVerification, PasswordReset};
+use crate::mail::OutboxMailer;
+use crate::model::{init_db, SessionForCreate, UserMac};
+use crate::security::{login, utx_from_token, Error, LoginForm};
+use lazy_regex::regex_captures;
+use std::env::temp_dir;
+use std::fs;
+use std::sync::Arc;
+
+// region: Email Test Utils
+/// A new outbox of the test (emptied).
+fn outbox(name: &str) -> Result<OutboxMailer, Box<dyn std::error::Error>> {
+	let folder = temp_dir().join(format!("{}_{}", name, std::process::id()));
+	let _ = fs::remove_dir_all(&folder);
+	Ok(OutboxMailer::new(folder, "quote-server <noreply@localhost>")?)
+}
+
+/// The token of the link of the last email of the outbox.
+fn last_token(outbox: &OutboxMailer) -> Result<String, Box<dyn std::error::Error>> {
+	let emails = outbox.emails()?;
+	let eml = fs::read_to_string(emails.last().ok_or("no email in the outbox")?)?;
+	let eml = eml.replace("=\r\n", "").replace("=\n", "").replace("=3D", "="); // quoted-printable
+	let (_, token) = regex_captures!(r"token=([0-9a-f]{64})", &eml).ok_or("no token in the email")?;
+	Ok(token.to_string())
+}
+
+fn login_form(username: &str, password: &str) -> LoginForm {
+	LoginForm {
+		username: username.to_string(),
+		password: password.to_string(),
+		totp: None,
+	}
+}
+// endregion: Email Test Utils
+
+#[tokio::test]
+async fn security_email_password_reset() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let outbox = Arc::new(outbox("security_email_password_reset")?);
+	let session = login(&db, login_form("bob", "welcome-dev"), SessionForCreate::default()).await?;
+
+	//ACTION
+	request_password_reset(&db, outbox.clone(), "nobody@example.com").await?;
+	let unknown_emails = outbox.emails()?.len();
+	request_password_reset(&db, outbox.clone(), " Bob@Example.com ").await?;
+	let token = last_token(&outbox)?;
+	let reset = |token: &str, password: &str| PasswordReset {
+		token: token.to_string(),
+		password: password.to_string(),
+	};
+	let too_short = reset_password(&db, reset(&token, "short")).await;
+	reset_password(&db, reset(&token, "new password")).await?;
+	let used_again = reset_password(&db, reset(&token, "other password")).await;
+	let old_session = utx_from_token(&db, &session.token).await;
+	let old_pwd = login(&db, login_form("bob", "welcome-dev"), SessionForCreate::default()).await;
+	let new_pwd = login(&db, login_form("bob", "new password"), SessionForCreate::default()).await;
+
+	//CHECK
+	assert_eq!(0, unknown_emails, "no email for an unknown address");
+	assert!(matches!(too_short, Err(Error::InvalidPassword(_))), "{:?}", too_short);
+	assert!(matches!(used_again, Err(Error::InvalidEmailToken)), "single-use {:?}", used_again);
+	assert!(matches!(old_session, Err(Error::SessionRevoked)), "sessions revoked {:?}", old_session.err());
+	assert!(matches!(old_pwd, Err(Error::InvalidCredentials)), "{:?}", old_pwd.err());
+	assert!(new_pwd.is_ok(), "{:?}", new_pwd.err());
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_email_password_reset_throttled() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let outbox = Arc::new(outbox("security_email_password_reset_throttled")?);
+
+	//ACTION
+	request_password_reset(&db, outbox.clone(), "bob@example.com").await?;
+	let token = last_token(&outbox)?;
+	request_password_reset(&db, outbox.clone(), "bob@example.com").await?;
+	let throttled_emails = outbox.emails()?.len();
+	sqlx::query("UPDATE email_token SET ctime = now() - interval '10 minutes' WHERE user_id = 456").execute(&db).await?;
+	request_password_reset(&db, outbox.clone(), "bob@example.com").await?;
+	let later_emails = outbox.emails()?.len();
+	let later_token = last_token(&outbox)?;
+
+	//CHECK
+	assert_eq!(1, throttled_emails, "no second email within the interval");
+	assert_eq!(2, later_emails, "a new email after the interval");
+	assert_ne!(token, later_token, "a new token");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_email_verification() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let outbox = outbox("security_email_verification")?;
+	sqlx::query("UPDATE user_account SET email_verified = false WHERE id IN (456, 789)").execute(&db).await?;
+
+	//ACTION
+	send_email_verification(&db, &outbox, 123).await?;
+	let verified_emails = outbox.emails()?.len();
+	send_email_verification(&db, &outbox, 456).await?;
+	let token = last_token(&outbox)?;
+	let unknown = verify_email(&db, EmailVerification { token: "0".repeat(64) }).await;
+	let user = verify_email(&db, EmailVerification { token: token.clone() }).await?;
+	let used_again = verify_email(&db, EmailVerification { token }).await;
+	send_email_verification(&db, &outbox, 789).await?;
+	let changed_token = last_token(&outbox)?;
+	sqlx::query("UPDATE user_account SET email = 'carol@example.org' WHERE id = 789").execute(&db).await?;
+	let changed = verify_email(&db, EmailVerification { token: changed_token }).await;
+
+	//CHECK
+	assert_eq!(0, verified_emails, "no email for an already verified address");
+	assert!(matches!(unknown, Err(Error::InvalidEmailToken)), "{:?}", unknown);
+	assert_eq!((456, true), (user.id, user.email_verified));
+	assert!(matches!(used_again, Err(Error::InvalidEmailToken)), "single-use {:?}", used_again);
+	assert!(matches!(changed, Err(Error::InvalidEmailToken)), "email changed since {:?}", changed);
+	assert!(!UserMac::get(&db, 789).await?.email_verified);
+
+	Ok(())
+}
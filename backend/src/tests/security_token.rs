@@ -0,0 +1,62 @@
+use super::{new_token, now, refresh_token, sign_token, verify_token, verify_token_with, Token};
+use crate::config::config;
+use crate::security::Error;
+
+#[test]
+fn security_token_sign_verify() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let token = sign_token(123, 7, 2000, "secret");
+
+	//ACTION
+	let verified = verify_token_with(&token, 1000, "secret", None)?;
+	let tampered = verify_token_with(&token.replacen("user-123", "user-456", 1), 1000, "secret", None);
+	let other_key = verify_token_with(&token, 1000, "other secret", None);
+	let expired = verify_token_with(&token, 2000, "secret", None);
+	let wrong_format = verify_token_with("123", 1000, "secret", None);
+
+	//CHECK
+	assert!(token.starts_with("user-123.7.2000."), "{}", token);
+	assert_eq!(Token { user_id: 123, session_id: 7, exp: 2000, previous_key: false }, verified);
+	assert!(matches!(tampered, Err(Error::InvalidToken)), "{:?}", tampered);
+	assert!(matches!(other_key, Err(Error::InvalidToken)), "{:?}", other_key);
+	assert!(matches!(expired, Err(Error::TokenExpired)), "{:?}", expired);
+	assert!(matches!(wrong_format, Err(Error::InvalidToken)), "{:?}", wrong_format);
+
+	Ok(())
+}
+
+#[test]
+fn security_token_rotation() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let old_token = sign_token(123, 7, 2000, "old secret");
+
+	//ACTION
+	let verified = verify_token_with(&old_token, 1000, "new secret", Some("old secret"))?;
+	let retired = verify_token_with(&old_token, 1000, "new secret", None);
+
+	//CHECK
+	assert!(verified.previous_key, "accepted with the previous key");
+	assert!(matches!(retired, Err(Error::InvalidToken)), "{:?}", retired);
+
+	Ok(())
+}
+
+#[test]
+fn security_token_refresh() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let fresh = new_token(123, 7);
+	let expiring = sign_token(123, 7, now() + 60, &config().token_secret);
+
+	//ACTION
+	let fresh_refresh = refresh_token(&fresh);
+	let expiring_refresh = refresh_token(&expiring).ok_or("expiring token not refreshed")?;
+
+	//CHECK
+	assert_eq!(None, fresh_refresh);
+	let refreshed = verify_token(&expiring_refresh)?;
+	assert_eq!((123, 7), (refreshed.user_id, refreshed.session_id), "same user session");
+	assert!(refreshed.exp > now() + 3600, "a new full lifetime");
+	assert_eq!(None, refresh_token("user-123.1.00"), "invalid tokens are not refreshed");
+
+	Ok(())
+}
@@ -0,0 +1,120 @@
+use super::{base32_decode, base32_encode, confirm_totp, disable_totp, enrol_totp, totp_code, totp_now, verify_totp_with};
+use crate::config::config;
+use crate::model::{self, init_db, Role, SessionForCreate};
+use crate::security::{login, utx_from_token, Error, LoginForm, UserCtx};
+
+fn login_form(username: &str, totp: Option<&str>) -> LoginForm {
+	LoginForm {
+		username: username.to_string(),
+		password: "welcome-dev".to_string(),
+		totp: totp.map(str::to_string),
+	}
+}
+
+#[test]
+fn security_totp_code() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let secret = b"12345678901234567890"; // RFC 6238 test vectors (last 6 digits)
+
+	//ACTION
+	let codes: Vec<String> = [59, 1111111109, 1234567890].iter().map(|time| totp_code(secret, time / 30)).collect();
+	let next = totp_code(secret, 1234567890 / 30 + 1);
+	let after_next = totp_code(secret, 1234567890 / 30 + 2);
+
+	//CHECK
+	assert_eq!(vec!["287082", "081804", "005924"], codes);
+	assert_eq!(Some(1234567890 / 30), verify_totp_with(secret, "005924", 1234567890, 0));
+	assert_eq!(Some(1234567890 / 30 + 1), verify_totp_with(secret, &next, 1234567890, 1), "clock skew");
+	assert_eq!(None, verify_totp_with(secret, &next, 1234567890, 0), "no clock skew");
+	assert_eq!(None, verify_totp_with(secret, &after_next, 1234567890, 1), "beyond the clock skew");
+	assert_eq!(None, verify_totp_with(secret, "00592", 1234567890, 1), "digits missing");
+
+	Ok(())
+}
+
+#[test]
+fn security_totp_base32() {
+	assert_eq!("MZXW6YTBOI", base32_encode(b"foobar"));
+	assert_eq!(Some(b"foobar".to_vec()), base32_decode("mzxw 6ytb oi======"));
+	assert_eq!(Some(b"Hello!\xde\xad\xbe\xef".to_vec()), base32_decode("JBSWY3DPEHPK3PXP"));
+	assert_eq!(None, base32_decode("MZXW1"), "not base32");
+}
+
+#[tokio::test]
+async fn security_totp_enrol_login() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let utx = UserCtx::new(789, Role::Contributor);
+
+	//ACTION
+	let enrollment = enrol_totp(&db, &utx).await?;
+	let wrong_code = confirm_totp(&db, &utx, "000000").await;
+	let code = totp_now(&enrollment.secret).ok_or("base32 secret")?;
+	let recovery = confirm_totp(&db, &utx, &code).await?;
+	let enrol_again = enrol_totp(&db, &utx).await;
+	let without_code = login(&db, login_form("carol", None), SessionForCreate::default()).await;
+	let replayed_code = login(&db, login_form("carol", Some(&code)), SessionForCreate::default()).await;
+	let recovery_code = recovery.recovery_codes[0].to_uppercase();
+	let with_recovery = login(&db, login_form("carol", Some(&recovery_code)), SessionForCreate::default()).await?;
+	let recovery_again = login(&db, login_form("carol", Some(&recovery_code)), SessionForCreate::default()).await;
+
+	//CHECK
+	assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/quote-server:carol?secret="), "{}", enrollment.otpauth_uri);
+	assert!(matches!(wrong_code, Err(Error::InvalidTotp)), "{:?}", wrong_code.err());
+	assert_eq!(10, recovery.recovery_codes.len());
+	assert_eq!(23, recovery.recovery_codes[0].len(), "80 bits recovery codes: {}", recovery.recovery_codes[0]);
+	assert!(matches!(enrol_again, Err(Error::TotpState(_))), "{:?}", enrol_again.err());
+	assert!(matches!(without_code, Err(Error::TotpRequired)), "{:?}", without_code.err());
+	assert!(matches!(replayed_code, Err(Error::InvalidTotp)), "a code is only accepted once");
+	assert!(utx_from_token(&db, &with_recovery.token).await?.totp_enabled);
+	assert!(!with_recovery.totp_enrollment_required);
+	assert!(matches!(recovery_again, Err(Error::InvalidTotp)), "single-use recovery code");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_totp_lockout() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	let max_failures = config().totp_max_failures;
+
+	//ACTION
+	let mut failures = Vec::new();
+	for _ in 0..max_failures {
+		failures.push(login(&db, login_form("alice", Some("not-a-recovery-code")), SessionForCreate::default()).await);
+	}
+	let code = totp_now("JBSWY3DPEHPK3PXP").ok_or("base32 secret")?;
+	let locked_out = login(&db, login_form("alice", Some(&code)), SessionForCreate::default()).await;
+	sqlx::query("UPDATE user_totp SET locked_until = now() - interval '1 second' WHERE user_id = 123").execute(&db).await?;
+	let after_lockout = login(&db, login_form("alice", Some(&code)), SessionForCreate::default()).await;
+	let (failed_attempts,): (i32,) = sqlx::query_as("SELECT failed_attempts FROM user_totp WHERE user_id = 123").fetch_one(&db).await?;
+
+	//CHECK
+	assert!(failures.iter().all(|failure| matches!(failure, Err(Error::InvalidTotp))), "{:?}", failures);
+	assert!(matches!(locked_out, Err(Error::Model(model::Error2::RateLimited(_)))), "{:?}", locked_out.err());
+	assert!(after_lockout.is_ok(), "{:?}", after_lockout.err());
+	assert_eq!(0, failed_attempts, "reset by the accepted code");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn security_totp_admin_required() -> Result<(), Box<dyn std::error::Error>> {
+	//FIXTURE
+	let db = init_db().await?;
+	sqlx::query("DELETE FROM user_totp WHERE user_id = 123").execute(&db).await?;
+
+	//ACTION
+	let result = login(&db, login_form("alice", None), SessionForCreate::default()).await?;
+	let utx = utx_from_token(&db, &result.token).await?;
+	let disable_admin = disable_totp(&db, &utx, "000000").await;
+
+	//CHECK
+	assert!(result.totp_enrollment_required, "admin without TOTP");
+	assert!(utx.must_enrol_totp());
+	assert!(matches!(disable_admin, Err(Error::TotpState(_))), "{:?}", disable_admin.err());
+	assert!(!UserCtx::new(456, Role::Contributor).must_enrol_totp(), "optional for the others");
+
+	Ok(())
+}
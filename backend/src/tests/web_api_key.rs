@@ -0,0 +1,113 @@
+use super::api_key_rest_filters;
+use crate::model::{init_db, ApiKey};
+use crate::security::{new_token, ApiKeyCreated};
+use crate::web::handle_rejection;
+use crate::web::quote::quote_rest_filters;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use serde_json::json;
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_api_key_create_use_revoke() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = api_key_rest_filters("api", db.clone()).or(quote_rest_filters("api", db.clone())).recover(handle_rejection);
+
+	// ACTION
+	let resp_create = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/api-keys")
+		.json(&json!({ "name": "reader", "scopes": ["quotes:read"] }))
+		.reply(&apis)
+		.await;
+	let created: ApiKeyCreated = extract_body_data(resp_create)?;
+	let resp_read = warp::test::request()
+		.method("GET")
+		.header("X-Api-Key", &created.key)
+		.path("/api/quotes")
+		.reply(&apis)
+		.await;
+	let resp_write = warp::test::request()
+		.method("POST")
+		.header("X-Api-Key", &created.key)
+		.path("/api/quotes")
+		.json(&json!({ "quote": "written with a read key" }))
+		.reply(&apis)
+		.await;
+	let resp_keys = warp::test::request()
+		.method("GET")
+		.header("X-Api-Key", &created.key)
+		.path("/api/me/api-keys")
+		.reply(&apis)
+		.await;
+	let resp_list = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/api-keys")
+		.reply(&apis)
+		.await;
+	let resp_delete = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path(&format!("/api/me/api-keys/{}", created.api_key.id))
+		.reply(&apis)
+		.await;
+	let resp_revoked = warp::test::request()
+		.method("GET")
+		.header("X-Api-Key", &created.key)
+		.path("/api/quotes")
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert!(created.key.starts_with("qapi_"), "{}", created.key);
+	assert_eq!(200, resp_read.status(), "http status (quotes:read)");
+	assert_eq!(403, resp_write.status(), "http status (no quotes:write)");
+	assert!(from_utf8(resp_write.body())?.contains("quotes:write"), "names the missing scope");
+	assert_eq!(403, resp_keys.status(), "http status (no admin)");
+	assert!(!from_utf8(resp_list.body())?.contains(&created.key), "the key is only given at its creation");
+	let api_keys: Vec<ApiKey> = extract_body_data(resp_list)?;
+	assert_eq!(1, api_keys.len());
+	assert!(api_keys[0].last_used.is_some(), "last used tracked");
+	assert_eq!(200, resp_delete.status(), "http status (delete)");
+	assert_eq!(401, resp_revoked.status(), "http status (revoked key)");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_api_key_admin_not_enrolled() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = api_key_rest_filters("api", db.clone()).or(quote_rest_filters("api", db.clone())).recover(handle_rejection);
+	let resp_create = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/me/api-keys")
+		.json(&json!({ "name": "reader", "scopes": ["quotes:read"] }))
+		.reply(&apis)
+		.await;
+	let created: ApiKeyCreated = extract_body_data(resp_create)?;
+	// bob promoted after the creation of his key, without two-factor authentication
+	sqlx::query("UPDATE user_account SET role = 'admin' WHERE id = 456").execute(&*db).await?;
+
+	// ACTION
+	let resp_read = warp::test::request()
+		.method("GET")
+		.header("X-Api-Key", &created.key)
+		.path("/api/quotes")
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert_eq!(403, resp_read.status(), "http status (admin key without two-factor authentication)");
+	assert!(from_utf8(resp_read.body())?.contains("Two-factor"), "{}", from_utf8(resp_read.body())?);
+
+	Ok(())
+}
@@ -0,0 +1,219 @@
+use super::auth_rest_filters;
+use crate::mail::OutboxMailer;
+use crate::model::{init_db, UserAccount};
+use crate::security::{totp_now, LoginResult};
+use crate::web::handle_rejection;
+use crate::web::quote::quote_rest_filters;
+use crate::web::test_utils::{extract_body_data, outbox};
+use anyhow::{Context, Result};
+use lazy_regex::regex_captures;
+use serde_json::json;
+use std::env::temp_dir;
+use std::fs;
+use std::str::from_utf8;
+use std::sync::Arc;
+use std::time::Duration;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_auth_register_login() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = auth_rest_filters("api", db.clone(), outbox()?).or(quote_rest_filters("api", db.clone())).recover(handle_rejection);
+
+	// ACTION
+	let resp_register = warp::test::request()
+		.method("POST")
+		.path("/api/register")
+		.json(&json!({ "username": "dave", "email": "dave@example.com", "password": "correct horse" }))
+		.reply(&apis)
+		.await;
+	let resp_login = warp::test::request()
+		.method("POST")
+		.path("/api/login")
+		.json(&json!({ "username": "dave", "password": "correct horse" }))
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp_register.status(), "http status (register)");
+	let user: UserAccount = extract_body_data(resp_register)?;
+	assert_eq!("dave", user.username);
+	assert!(!from_utf8(resp_login.body())?.contains("pwd_hash"));
+	assert_eq!(200, resp_login.status(), "http status (login)");
+	let result: LoginResult = extract_body_data(resp_login)?;
+	let resp_quotes = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", &result.token)
+		.path("/api/quotes")
+		.reply(&apis)
+		.await;
+	assert_eq!(200, resp_quotes.status(), "the token authenticates");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_auth_login_unauthorized() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = auth_rest_filters("api", db.clone(), outbox()?).or(quote_rest_filters("api", db.clone())).recover(handle_rejection);
+
+	// ACTION
+	let resp_login = warp::test::request()
+		.method("POST")
+		.path("/api/login")
+		.json(&json!({ "username": "alice", "password": "wrong password" }))
+		.reply(&apis)
+		.await;
+	let resp_forged = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", "1")
+		.path("/api/quotes")
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert_eq!(401, resp_login.status(), "http status (login)");
+	assert_eq!(401, resp_forged.status(), "http status (forged token)");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_auth_cookie_login_logout() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = auth_rest_filters("api", db.clone(), outbox()?).or(quote_rest_filters("api", db.clone())).recover(handle_rejection);
+
+	// ACTION
+	let resp_login = warp::test::request()
+		.method("POST")
+		.path("/api/login")
+		.json(&json!({ "username": "alice", "password": "welcome-dev", "totp": totp_now("JBSWY3DPEHPK3PXP") }))
+		.reply(&apis)
+		.await;
+	let resp_logout = warp::test::request().method("POST").path("/api/logout").reply(&apis).await;
+
+	// CHECK
+	assert_eq!(200, resp_login.status(), "http status (login)");
+	let set_cookie = resp_login.headers().get("set-cookie").context("login sets the auth cookie")?.to_str()?.to_string();
+	for attribute in ["HttpOnly", "Secure", "SameSite=Strict", "Path=/"] {
+		assert!(set_cookie.contains(attribute), "{} in '{}'", attribute, set_cookie);
+	}
+	let cookie = set_cookie.split(';').next().context("cookie name=value")?;
+	assert!(cookie.starts_with("auth-token=user-123."), "{}", cookie);
+	let resp_quotes = warp::test::request()
+		.method("GET")
+		.header("Cookie", cookie)
+		.path("/api/quotes")
+		.reply(&apis)
+		.await;
+	assert_eq!(200, resp_quotes.status(), "the cookie authenticates");
+	let resp_no_auth = warp::test::request().method("GET").path("/api/quotes").reply(&apis).await;
+	assert_eq!(400, resp_no_auth.status(), "neither header nor cookie");
+
+	assert_eq!(200, resp_logout.status(), "http status (logout)");
+	let cleared = resp_logout.headers().get("set-cookie").context("logout clears the auth cookie")?.to_str()?;
+	assert!(cleared.starts_with("auth-token=;") && cleared.contains("Max-Age=0"), "{}", cleared);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_auth_email_verification_password_reset() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let folder = temp_dir().join(format!("web_auth_email_{}", std::process::id()));
+	let _ = fs::remove_dir_all(&folder);
+	let outbox = Arc::new(OutboxMailer::new(folder, "quote-server <noreply@localhost>")?);
+	let apis = auth_rest_filters("api", db.clone(), outbox.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp_register = warp::test::request()
+		.method("POST")
+		.path("/api/register")
+		.json(&json!({ "username": "dave", "email": "dave@example.com", "password": "correct horse" }))
+		.reply(&apis)
+		.await;
+	let verification_token = last_token(&outbox)?;
+	let resp_verify = warp::test::request()
+		.method("POST")
+		.path("/api/email-verification/confirm")
+		.json(&json!({ "token": verification_token }))
+		.reply(&apis)
+		.await;
+	let resp_unknown = warp::test::request()
+		.method("POST")
+		.path("/api/password-reset")
+		.json(&json!({ "email": "nobody@example.com" }))
+		.reply(&apis)
+		.await;
+	let resp_request = warp::test::request()
+		.method("POST")
+		.path("/api/password-reset")
+		.json(&json!({ "email": "dave@example.com" }))
+		.reply(&apis)
+		.await;
+	let reset_token = next_token(&outbox, 2).await?;
+	let resp_reset = warp::test::request()
+		.method("POST")
+		.path("/api/password-reset/confirm")
+		.json(&json!({ "token": reset_token, "password": "battery staple" }))
+		.reply(&apis)
+		.await;
+	let resp_reset_again = warp::test::request()
+		.method("POST")
+		.path("/api/password-reset/confirm")
+		.json(&json!({ "token": reset_token, "password": "battery staple" }))
+		.reply(&apis)
+		.await;
+	let resp_login = warp::test::request()
+		.method("POST")
+		.path("/api/login")
+		.json(&json!({ "username": "dave", "password": "battery staple" }))
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	let user: UserAccount = extract_body_data(resp_register)?;
+	assert!(!user.email_verified, "not verified at the registration");
+	assert_eq!(200, resp_verify.status(), "http status (verify)");
+	let user: UserAccount = extract_body_data(resp_verify)?;
+	assert!(user.email_verified, "verified by the link");
+	assert_eq!(resp_request.body(), resp_unknown.body(), "same answer for an unknown email");
+	assert_eq!(2, outbox.emails()?.len(), "the verification and reset emails only");
+	assert_eq!(200, resp_reset.status(), "http status (reset)");
+	assert_eq!(400, resp_reset_again.status(), "http status (used reset link)");
+	assert_eq!(200, resp_login.status(), "http status (login with the new password)");
+
+	Ok(())
+}
+
+// region:    Web Test Utils
+/// The token of the link of the last email of the outbox.
+fn last_token(outbox: &OutboxMailer) -> Result<String> {
+	let emails = outbox.emails()?;
+	let eml = fs::read_to_string(emails.last().context("no email in the outbox")?)?;
+	let eml = eml.replace("=\r\n", "").replace("=\n", "").replace("=3D", "="); // quoted-printable
+	let (_, token) = regex_captures!(r"token=([0-9a-f]{64})", &eml).context("no token in the email")?;
+	Ok(token.to_string())
+}
+
+/// The token of the link of the nth email of the outbox, waiting for it as it is sent in its own task.
+async fn next_token(outbox: &OutboxMailer, nth: usize) -> Result<String> {
+	for _ in 0..50 {
+		if outbox.emails()?.len() >= nth
+			&& let Ok(token) = last_token(outbox)
+		{
+			return Ok(token);
+		}
+		tokio::time::sleep(Duration::from_millis(100)).await;
+	}
+	anyhow::bail!("no email {} in the outbox", nth)
+}
+// endregion: Web Test Utils
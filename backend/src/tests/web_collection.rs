@@ -0,0 +1,127 @@
+use super::collection_rest_filters;
+use crate::model::{Collection, CollectionMac, Quote, QuoteMac, QuotePatch, Role, init_db};
+use crate::security::{new_token, UserCtx};
+use crate::web::handle_rejection;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use serde_json::json;
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_collection_create_ok() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let collection_apis = collection_rest_filters("api", db.clone()).recover(handle_rejection);
+	let body = json!({
+		"title": "test - web_collection_create_ok",
+		"description": "onboarding quotes",
+		"visibility": "unlisted",
+	});
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/collections")
+		.json(&body)
+		.reply(&collection_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let collection: Collection = extract_body_data(resp)?;
+	assert!(collection.id >= 1000, "collection.id should be >= to 1000");
+	assert_eq!(Some("onboarding quotes".to_string()), collection.description);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_collection_list_quotes_paginated() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let collection_apis = collection_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/collections/100/quotes?limit=1&offset=1")
+		.reply(&collection_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let quotes: Vec<Quote> = extract_body_data(resp)?;
+	assert_eq!(1, quotes.len(), "number of quotes");
+	assert_eq!(100, quotes[0].id, "second quote of the collection");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_collection_add_quote_ok() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let collection_apis = collection_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/collections/100/quotes/100")
+		.reply(&collection_apis)
+		.await;
+	assert_eq!(200, resp.status(), "http status (remove)");
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/collections/100/quotes")
+		.json(&json!({ "quote_id": 100, "position": 0 }))
+		.reply(&collection_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status (add)");
+	let collection: Collection = extract_body_data(resp)?;
+	assert_eq!(vec![100, 101], collection.quote_ids);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_collection_cite_bibtex() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let collection_apis = collection_rest_filters("api", db.clone()).recover(handle_rejection);
+	let utx = UserCtx::new(123, Role::Admin);
+	for (id, author) in [(100, "Test Author"), (101, "Plato")] {
+		let patch_fx = QuotePatch::from_merge_patch(json!({ "author": author, "source": "Test Book" }))?;
+		QuoteMac::update(&db, &utx, id, patch_fx, None).await?;
+	}
+	CollectionMac::reorder(&db, &utx, 100, &[100, 101]).await?;
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/collections/100/cite?format=bibtex")
+		.reply(&collection_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	assert_eq!("application/x-bibtex; charset=utf-8", resp.headers()["Content-Type"]);
+	let body = from_utf8(resp.body())?;
+	assert!(body.contains("@misc{plato101,\n  author = {Plato},"), "body: {}", body);
+	assert!(body.contains("@misc{author100,\n  author = {Author, Test},"), "body: {}", body);
+	assert!(body.find("@misc{author100") < body.find("@misc{plato101"), "collection order, body: {}", body);
+
+	Ok(())
+}
@@ -0,0 +1,81 @@
+use super::export_rest_filters;
+use crate::model::init_db;
+use crate::security::new_token;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use serde_json::Value;
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_export_ndjson() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let export_apis = export_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/export?format=ndjson&sort=popular")
+		.reply(&export_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	assert_eq!("application/x-ndjson", resp.headers()["Content-Type"]);
+	assert_eq!("attachment; filename=\"quotes.ndjson\"", resp.headers()["Content-Disposition"]);
+	let lines: Vec<Value> = from_utf8(resp.body())?.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+	assert_eq!(2, lines.len(), "one line per quote");
+	assert_eq!(100, lines[0]["id"]);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_export_unknown_format() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let export_apis = export_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/export?format=xml")
+		.reply(&export_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(400, resp.status(), "http status");
+	let body: Value = serde_json::from_str(from_utf8(resp.body())?)?;
+	assert_eq!("export::Error", body["errorMessage"]);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_export_invalid_filter() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let export_apis = export_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/export?format=csv&lang=e%25")
+		.reply(&export_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(400, resp.status(), "http status, not a truncated 200");
+	let body: Value = serde_json::from_str(from_utf8(resp.body())?)?;
+	assert_eq!("model::Error", body["errorMessage"]);
+
+	Ok(())
+}
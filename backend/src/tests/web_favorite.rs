@@ -0,0 +1,64 @@
+use super::favorite_rest_filters;
+use crate::model::{init_db, Quote};
+use crate::security::new_token;
+use crate::web::handle_rejection;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_favorite_put_delete() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let favorite_apis = favorite_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp_put = warp::test::request()
+		.method("PUT")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/quotes/100/favorite")
+		.reply(&favorite_apis)
+		.await;
+	let resp_delete = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/quotes/100/favorite")
+		.reply(&favorite_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp_put.status(), "http status (put)");
+	let quote: Quote = extract_body_data(resp_put)?;
+	assert_eq!(2, quote.favorite_count, "seed favorite + 123");
+	assert_eq!(200, resp_delete.status(), "http status (delete)");
+	let quote: Quote = extract_body_data(resp_delete)?;
+	assert_eq!(1, quote.favorite_count);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_favorite_list_me() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let favorite_apis = favorite_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/me/favorites")
+		.reply(&favorite_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let quotes: Vec<Quote> = extract_body_data(resp)?;
+	assert_eq!(1, quotes.len(), "number of favorites");
+	assert_eq!(100, quotes[0].id);
+
+	Ok(())
+}
@@ -0,0 +1,62 @@
+use super::import_rest_filters;
+use crate::model::init_db;
+use crate::security::new_token;
+use crate::web::handle_rejection;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use serde_json::{from_str, Value};
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_import_csv_dry_run() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let import_apis = import_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Content-Type", "text/csv")
+		.path("/api/import?dry_run=true")
+		.body("quote,author\nA new quote,Someone\n,Nobody\n")
+		.reply(&import_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let report: Value = extract_body_data(resp)?;
+	assert_eq!(true, report["dry_run"]);
+	assert_eq!(1, report["created"]);
+	assert_eq!(1, report["rejected"]);
+	assert_eq!("missing quote", report["records"][1]["reason"]);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_import_unknown_format() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let import_apis = import_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/import?format=xml")
+		.body("<quotes/>")
+		.reply(&import_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(400, resp.status(), "http status");
+	let body: Value = from_str(from_utf8(resp.body())?)?;
+	assert_eq!("import::Error", body["errorMessage"]);
+
+	Ok(())
+}
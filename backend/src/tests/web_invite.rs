@@ -0,0 +1,75 @@
+use super::invite_rest_filters;
+use crate::model::{init_db, InviteListing, Role, UserAccount};
+use crate::security::{new_token, InviteCreated};
+use crate::web::auth::auth_rest_filters;
+use crate::web::handle_rejection;
+use crate::web::test_utils::{extract_body_data, outbox};
+use anyhow::Result;
+use serde_json::json;
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_invite_create_register_list() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = invite_rest_filters("api", db.clone()).or(auth_rest_filters("api", db.clone(), outbox()?)).recover(handle_rejection);
+
+	// ACTION
+	let resp_create = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/invites")
+		.json(&json!({ "role": "editor" }))
+		.reply(&apis)
+		.await;
+	let created: InviteCreated = extract_body_data(resp_create)?;
+	let resp_register = warp::test::request()
+		.method("POST")
+		.path("/api/register")
+		.json(&json!({ "username": "dave", "email": "dave@example.com", "password": "correct horse", "invite": created.code }))
+		.reply(&apis)
+		.await;
+	let resp_used_up = warp::test::request()
+		.method("POST")
+		.path("/api/register")
+		.json(&json!({ "username": "erin", "email": "erin@example.com", "password": "correct horse", "invite": created.code }))
+		.reply(&apis)
+		.await;
+	let resp_list = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/invites")
+		.reply(&apis)
+		.await;
+	let resp_not_admin = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/invites")
+		.reply(&apis)
+		.await;
+	let resp_revoke = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path(&format!("/api/invites/{}", created.invite.id))
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert!(created.code.starts_with("inv_"), "{}", created.code);
+	assert_eq!(200, resp_register.status(), "http status (register)");
+	let user: UserAccount = extract_body_data(resp_register)?;
+	assert_eq!(("dave", Role::Editor), (user.username.as_str(), user.role));
+	assert_eq!(400, resp_used_up.status(), "http status (used up invite)");
+	assert!(!from_utf8(resp_list.body())?.contains(&created.code), "the code is only given at its creation");
+	let invites: Vec<InviteListing> = extract_body_data(resp_list)?;
+	assert_eq!(1, invites.len());
+	let used_by: Vec<(i64, &str)> = invites[0].used_by.iter().map(|u| (u.user_id, u.username.as_str())).collect();
+	assert_eq!(vec![(user.id, "dave")], used_by, "who used the invite");
+	assert_eq!(403, resp_not_admin.status(), "http status (not admin)");
+	assert_eq!(200, resp_revoke.status(), "http status (revoke)");
+
+	Ok(())
+}
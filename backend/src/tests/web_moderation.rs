@@ -0,0 +1,100 @@
+use super::moderation_rest_filters;
+use crate::model::{Quote, QuoteMac, QuotePatch, QuoteStatus, Role, init_db};
+use crate::security::{new_token, UserCtx};
+use crate::web::handle_rejection;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_moderation_list_reject() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let moderation_apis = moderation_rest_filters("api", db.clone()).recover(handle_rejection);
+	let submitter = UserCtx { user_id: 789, role: Role::Contributor, trusted: false, session_id: None, totp_enabled: false };
+	let patch = QuotePatch {
+		quote: Some("pending quote".to_string()),
+		..Default::default()
+	};
+	let pending = QuoteMac::create(&db, &submitter, patch).await?;
+
+	// ACTION
+	let resp_list = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/moderation")
+		.reply(&moderation_apis)
+		.await;
+	let resp_reject = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path(&format!("/api/moderation/{}/reject", pending.id))
+		.json(&json!({ "reason": "no source" }))
+		.reply(&moderation_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp_list.status(), "http status (list)");
+	let quotes: Vec<Quote> = extract_body_data(resp_list)?;
+	assert_eq!(vec![pending.id], quotes.iter().map(|q| q.id).collect::<Vec<_>>());
+	assert_eq!(200, resp_reject.status(), "http status (reject)");
+	let quote: Quote = extract_body_data(resp_reject)?;
+	assert_eq!(QuoteStatus::Rejected, quote.status);
+	assert_eq!(Some("no source".to_string()), quote.moderation_reason);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_moderation_forbidden() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let moderation_apis = moderation_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/moderation/100/approve")
+		.json(&json!({}))
+		.reply(&moderation_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(403, resp.status(), "http status");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_moderation_approve_no_body() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let moderation_apis = moderation_rest_filters("api", db.clone()).recover(handle_rejection);
+	let submitter = UserCtx { user_id: 789, role: Role::Contributor, trusted: false, session_id: None, totp_enabled: false };
+	let patch = QuotePatch {
+		quote: Some("pending quote".to_string()),
+		..Default::default()
+	};
+	let pending = QuoteMac::create(&db, &submitter, patch).await?;
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path(&format!("/api/moderation/{}/approve", pending.id))
+		.reply(&moderation_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let quote: Quote = extract_body_data(resp)?;
+	assert_eq!(QuoteStatus::Approved, quote.status);
+
+	Ok(())
+}
@@ -1,8 +1,8 @@
 // *** Replaces web_todo.rs from the same directory ***
 
 use super::quote_rest_filters;
-use crate::model::{init_db, Quote, QuoteMac};
-use crate::security::utx_from_token;
+use crate::model::{ListOptions, Quote, QuoteFilter, QuoteMac, QuotePatch, Role, init_db};
+use crate::security::{new_token, UserCtx};
 use crate::web::handle_rejection;
 use anyhow::{Context, Result};
 use serde::Deserialize;
@@ -23,7 +23,7 @@ async fn web_quote_list() -> Result<()> {
 	// ACTION
 	let resp = warp::test::request()
 		.method("GET")
-		.header("X-Auth-Token", "123")
+		.header("X-Auth-Token", new_token(123, 123))
 		.path("/api/quotes")
 		.reply(&quote_apis)
 		.await;
@@ -43,6 +43,30 @@ async fn web_quote_list() -> Result<()> {
 	Ok(())
 }
 
+#[tokio::test]
+async fn web_quote_list_paginated() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/quotes?limit=1&offset=1")
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let quotes: Vec<Quote> = extract_body_data(resp)?;
+	assert_eq!(1, quotes.len(), "number of quotes");
+	assert_eq!(100, quotes[0].id);
+
+	Ok(())
+}
+
 #[tokio::test]
 async fn web_quote_get_ok() -> Result<()> {
 	// FIXTURE
@@ -53,13 +77,14 @@ async fn web_quote_get_ok() -> Result<()> {
 	// ACTION
 	let resp = warp::test::request()
 		.method("GET")
-		.header("X-Auth-Token", "123")
+		.header("X-Auth-Token", new_token(123, 123))
 		.path("/api/quotes/100")
 		.reply(&quote_apis)
 		.await;
 
 	// CHECK - status
 	assert_eq!(200, resp.status(), "http status");
+	assert_eq!("\"100-1\"", resp.headers()["ETag"], "etag");
 
 	// extract response .data
 	let quote: Quote = extract_body_data(resp)?;
@@ -68,6 +93,52 @@ async fn web_quote_get_ok() -> Result<()> {
 	assert_eq!(100, quote.id);
 	assert_eq!("test quote 100", quote.quote);
 	assert_eq!("test author", quote.author);
+	assert_eq!(1, quote.version);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_get_accept_language() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let body = json!({ "quote": "Zitat 100", "lang": "de", "translator": "test translator" });
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/quotes/100/translations")
+		.json(&body)
+		.reply(&quote_apis)
+		.await;
+	assert_eq!(200, resp.status(), "http status (create translation)");
+
+	// ACTION
+	let resp_header = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Accept-Language", "fr;q=0.9, de-AT;q=0.8")
+		.path("/api/quotes/100")
+		.reply(&quote_apis)
+		.await;
+	let resp_param = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Accept-Language", "de")
+		.path("/api/quotes/100?lang=und")
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp_header.status(), "http status");
+	assert_eq!("de", resp_header.headers()["Content-Language"]);
+	let quote: Quote = extract_body_data(resp_header)?;
+	assert_eq!("Zitat 100", quote.quote);
+	assert_eq!(Some(100), quote.original_id);
+
+	let quote: Quote = extract_body_data(resp_param)?;
+	assert_eq!(100, quote.id, "?lang= wins over Accept-Language");
 
 	Ok(())
 }
@@ -87,7 +158,7 @@ async fn web_quote_create_ok() -> Result<()> {
 	// ACTION
 	let resp = warp::test::request()
 		.method("POST")
-		.header("X-Auth-Token", "123")
+		.header("X-Auth-Token", new_token(123, 123))
 		.path("/api/quotes")
 		.json(&body)
 		.reply(&quote_apis)
@@ -123,7 +194,7 @@ async fn web_quote_update_ok() -> Result<()> {
 	// ACTION
 	let resp = warp::test::request()
 		.method("PATCH")
-		.header("X-Auth-Token", "123")
+		.header("X-Auth-Token", new_token(123, 123))
 		.path("/api/quotes/100")
 		.json(&body)
 		.reply(&quote_apis)
@@ -143,6 +214,225 @@ async fn web_quote_update_ok() -> Result<()> {
 	Ok(())
 }
 
+#[tokio::test]
+async fn web_quote_update_if_match_ok() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let body = json!({ "quote": "test - web_quote_update_if_match_ok" });
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("PATCH")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("If-Match", "\"100-1\"")
+		.path("/api/quotes/100")
+		.json(&body)
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	assert_eq!("\"100-2\"", resp.headers()["ETag"], "etag");
+	let quote: Quote = extract_body_data(resp)?;
+	assert_eq!(2, quote.version);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_update_if_match_stale() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let utx = UserCtx::new(123, Role::Admin);
+	let patch_fx = QuotePatch {
+		author: Some("test - other editor".to_string()),
+		..Default::default()
+	};
+	QuoteMac::update(&db, &utx, 100, patch_fx, None).await?;
+	let body = json!({ "quote": "test - web_quote_update_if_match_stale" });
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("PATCH")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("If-Match", "\"100-1\"")
+		.path("/api/quotes/100")
+		.json(&body)
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK - status
+	assert_eq!(412, resp.status(), "http status");
+	assert_eq!("\"100-2\"", resp.headers()["ETag"], "etag");
+
+	// CHECK - .data is the current representation
+	let quote: Quote = extract_body_data(resp)?;
+	assert_eq!(100, quote.id);
+	assert_eq!(2, quote.version);
+	assert_eq!("test quote 100", quote.quote);
+	assert_eq!("test - other editor", quote.author);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_delete_if_match_stale() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("If-Match", "\"100-7\"")
+		.path("/api/quotes/100")
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(412, resp.status(), "http status");
+	let utx = UserCtx::new(123, Role::Admin);
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
+	assert_eq!(2, quotes.len(), "quotes length");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_update_if_match_no_match() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let body = json!({ "quote": "Zitat 100", "lang": "de" });
+	let resp = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/quotes/100/translations")
+		.json(&body)
+		.reply(&quote_apis)
+		.await;
+	assert_eq!(200, resp.status(), "http status (create translation)");
+	let resp_translation = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Accept-Language", "de")
+		.path("/api/quotes/100")
+		.reply(&quote_apis)
+		.await;
+	let translation_etag = resp_translation.headers()["ETag"].to_str()?.to_string();
+	let body = json!({ "quote": "test - web_quote_update_if_match_no_match" });
+	let update = |if_match: &str| {
+		warp::test::request()
+			.method("PATCH")
+			.header("X-Auth-Token", new_token(123, 123))
+			.header("If-Match", if_match)
+			.path("/api/quotes/100")
+			.json(&body)
+	};
+
+	// ACTION
+	let resp_other_quote = update(&translation_etag).reply(&quote_apis).await;
+	let resp_weak = update("W/\"100-1\"").reply(&quote_apis).await;
+	let resp_list = update("\"100-1\", \"100-2\"").reply(&quote_apis).await;
+	let resp_bare = update("1").reply(&quote_apis).await;
+
+	// CHECK
+	assert_eq!("\"1000-1\"", translation_etag, "etag of the translation");
+	assert_eq!(412, resp_other_quote.status(), "http status (etag of the translation)");
+	assert_eq!("\"100-1\"", resp_other_quote.headers()["ETag"], "etag of the current quote");
+	assert_eq!(412, resp_weak.status(), "http status (weak etag)");
+	assert_eq!(412, resp_list.status(), "http status (list of etags)");
+	assert_eq!(400, resp_bare.status(), "http status (not an etag)");
+	let quote = QuoteMac::get(&db, &UserCtx::new(123, Role::Admin), 100).await?;
+	assert_eq!(1, quote.version, "not updated");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_update_merge_patch_ok() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let body = json!({ "author": null, "tags": ["merged"] });
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("PATCH")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Content-Type", "application/merge-patch+json")
+		.path("/api/quotes/100")
+		.body(body.to_string())
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let quote: Quote = extract_body_data(resp)?;
+	assert_eq!("unknown", quote.author);
+	assert_eq!(vec!["merged".to_string()], quote.tags);
+	assert_eq!("test quote 100", quote.quote);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_update_json_patch_invalid() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let body = json!([{ "op": "replace", "path": "/cid", "value": 1 }]);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("PATCH")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Content-Type", "application/json-patch+json")
+		.path("/api/quotes/100")
+		.body(body.to_string())
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(400, resp.status(), "http status");
+	let body: Value = from_str(from_utf8(resp.body())?)?;
+	assert_eq!("Invalid Patch - unsupported path '/cid'", body["errorDetail"]);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_quote_update_unsupported_media_type() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("PATCH")
+		.header("X-Auth-Token", new_token(123, 123))
+		.header("Content-Type", "text/plain")
+		.path("/api/quotes/100")
+		.body("quote")
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(415, resp.status(), "http status");
+
+	Ok(())
+}
+
 #[tokio::test]
 async fn web_quote_delete_ok() -> Result<()> {
 	// FIXTURE
@@ -153,7 +443,7 @@ async fn web_quote_delete_ok() -> Result<()> {
 	// ACTION
 	let resp = warp::test::request()
 		.method("DELETE")
-		.header("X-Auth-Token", "123")
+		.header("X-Auth-Token", new_token(123, 123))
 		.path("/api/quotes/100")
 		.reply(&quote_apis)
 		.await;
@@ -170,14 +460,49 @@ async fn web_quote_delete_ok() -> Result<()> {
 	assert_eq!("test author", quote.author);
 
 	// CHECK - list .len() should be 1
-	let utx = utx_from_token(&db, "123").await?;
-	let quotes = QuoteMac::list(&db, &utx).await?;
+	let utx = UserCtx::new(123, Role::Admin);
+	let quotes = QuoteMac::list(&db, &utx, &QuoteFilter::default(), &ListOptions::default()).await?;
 	assert_eq!(1, quotes.len(), "quotes length");
 	assert_eq!(101, quotes[0].id, "quote remaining should be 101");
 
 	Ok(())
 }
 
+#[tokio::test]
+async fn web_quote_cite() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let quote_apis = quote_rest_filters("api", db.clone()).recover(handle_rejection);
+	let utx = UserCtx::new(123, Role::Admin);
+	let patch_fx = QuotePatch::from_merge_patch(json!({ "source": "Test Book" }))?;
+	QuoteMac::update(&db, &utx, 100, patch_fx, None).await?;
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/quotes/100/cite?format=mla")
+		.reply(&quote_apis)
+		.await;
+	let missing_resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/quotes/101/cite")
+		.reply(&quote_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	assert_eq!("text/plain; charset=utf-8", resp.headers()["Content-Type"]);
+	assert_eq!("author, test. Test Book.\n", from_utf8(resp.body())?);
+	assert_eq!(422, missing_resp.status(), "http status (missing metadata)");
+	let body: Value = from_str(from_utf8(missing_resp.body())?)?;
+	assert_eq!("Missing citation metadata - quote 101 has no author and no source", body["errorDetail"]);
+
+	Ok(())
+}
+
 // region:    Web Test Utils
 fn extract_body_data<D>(resp: Response<Bytes>) -> Result<D>
 where
@@ -0,0 +1,73 @@
+use super::report_rest_filters;
+use crate::model::{init_db, Report, ReportOutcome, ReportReason, ReportSummary};
+use crate::security::new_token;
+use crate::web::handle_rejection;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_report_create_resolve() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let report_apis = report_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp_create = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/quotes/100/reports")
+		.json(&json!({ "reason": "offensive", "comment": "rude" }))
+		.reply(&report_apis)
+		.await;
+	let resp_list = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/moderation/reports")
+		.reply(&report_apis)
+		.await;
+	let resp_resolve = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/moderation/reports/100/resolve")
+		.json(&json!({ "outcome": "dismissed" }))
+		.reply(&report_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp_create.status(), "http status (create)");
+	let report: Report = extract_body_data(resp_create)?;
+	assert_eq!((100, 456, ReportReason::Offensive), (report.quote_id, report.cid, report.reason));
+	assert_eq!(200, resp_list.status(), "http status (list)");
+	let summaries: Vec<ReportSummary> = extract_body_data(resp_list)?;
+	assert_eq!(vec![(100, 1)], summaries.iter().map(|s| (s.quote_id, s.report_count)).collect::<Vec<_>>());
+	assert_eq!(200, resp_resolve.status(), "http status (resolve)");
+	let reports: Vec<Report> = extract_body_data(resp_resolve)?;
+	assert_eq!(Some(ReportOutcome::Dismissed), reports[0].outcome);
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_report_list_forbidden() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let report_apis = report_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/moderation/reports")
+		.reply(&report_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(403, resp.status(), "http status");
+
+	Ok(())
+}
@@ -0,0 +1,109 @@
+use super::session_rest_filters;
+use crate::model::{init_db, Session};
+use crate::security::{new_token, totp_now, LoginResult};
+use crate::web::auth::auth_rest_filters;
+use crate::web::handle_rejection;
+use crate::web::test_utils::{extract_body_data, outbox};
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_session_login_list_revoke() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = auth_rest_filters("api", db.clone(), outbox()?).or(session_rest_filters("api", db.clone())).recover(handle_rejection);
+	let resp_login = warp::test::request()
+		.method("POST")
+		.path("/api/login")
+		.header("User-Agent", "test laptop")
+		.json(&json!({ "username": "alice", "password": "welcome-dev", "totp": totp_now("JBSWY3DPEHPK3PXP") }))
+		.reply(&apis)
+		.await;
+	let laptop: LoginResult = extract_body_data(resp_login)?;
+
+	// ACTION
+	let resp_list = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", &laptop.token)
+		.path("/api/me/sessions")
+		.reply(&apis)
+		.await;
+	let resp_delete = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/sessions/1000")
+		.reply(&apis)
+		.await;
+	let resp_revoked = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", &laptop.token)
+		.path("/api/me/sessions")
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp_list.status(), "http status (list)");
+	let sessions: Vec<Session> = extract_body_data(resp_list)?;
+	assert_eq!(vec![1000, 123], sessions.iter().map(|s| s.id).collect::<Vec<_>>());
+	assert!(sessions[0].current, "session of the request");
+	assert_eq!(Some("test laptop"), sessions[0].user_agent.as_deref());
+	assert_eq!(200, resp_delete.status(), "http status (delete)");
+	assert!(resp_delete.headers().get("set-cookie").is_none(), "another session, the cookie is kept");
+	assert_eq!(401, resp_revoked.status(), "the token of a revoked session");
+
+	Ok(())
+}
+
+#[tokio::test]
+async fn web_session_logout_everywhere() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let apis = auth_rest_filters("api", db.clone(), outbox()?).or(session_rest_filters("api", db.clone())).recover(handle_rejection);
+
+	// ACTION
+	let resp_not_mine = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/sessions/456")
+		.reply(&apis)
+		.await;
+	let resp_delete_all = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/sessions")
+		.reply(&apis)
+		.await;
+	let resp_after = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/sessions")
+		.reply(&apis)
+		.await;
+	let resp_logout = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/logout")
+		.reply(&apis)
+		.await;
+	let resp_logged_out = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(456, 456))
+		.path("/api/me/sessions")
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert_eq!(400, resp_not_mine.status(), "the session of another user (not found)");
+	assert_eq!(200, resp_delete_all.status(), "http status (delete all)");
+	let revoked: Vec<Session> = extract_body_data(resp_delete_all)?;
+	assert_eq!(vec![123], revoked.iter().map(|s| s.id).collect::<Vec<_>>());
+	assert_eq!(401, resp_after.status(), "logged out everywhere");
+	assert_eq!(200, resp_logout.status(), "http status (logout)");
+	assert_eq!(401, resp_logged_out.status(), "the logout revokes the session");
+
+	Ok(())
+}
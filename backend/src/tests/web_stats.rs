@@ -0,0 +1,34 @@
+use super::stats_rest_filters;
+use crate::model::{init_db, QuoteStats};
+use crate::security::new_token;
+use crate::web::handle_rejection;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_stats_get() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	let db = Arc::new(db);
+	let stats_apis = stats_rest_filters("api", db.clone()).recover(handle_rejection);
+
+	// ACTION
+	let resp = warp::test::request()
+		.method("GET")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/stats?interval=month&top=1")
+		.reply(&stats_apis)
+		.await;
+
+	// CHECK
+	assert_eq!(200, resp.status(), "http status");
+	let stats: QuoteStats = extract_body_data(resp)?;
+	assert_eq!(2, stats.total);
+	assert_eq!(1, stats.by_author.len(), "top=1");
+	assert_eq!(("123".to_string(), 2), (stats.by_creator[0].key.clone(), stats.by_creator[0].count));
+	assert!(stats.by_period[0].period.ends_with("-01"), "month periods start on the 1st");
+
+	Ok(())
+}
@@ -0,0 +1,34 @@
+// Helpers shared by the web tests.
+
+use crate::mail::{Mailer, OutboxMailer};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{from_str, from_value, Value};
+use std::env::temp_dir;
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::hyper::Response;
+
+pub fn extract_body_data<D>(resp: Response<Bytes>) -> Result<D>
+where
+	for<'de> D: Deserialize<'de>,
+{
+	// parse the body as serde_json::Value
+	let body = from_utf8(resp.body())?;
+	let mut body: Value =
+		from_str(body).with_context(|| format!("Cannot parse resp.body to JSON. resp.body: '{}'", body))?;
+
+	// extract the data
+	let data = body["data"].take();
+
+	// deserialize the data to D
+	let data: D = from_value(data)?;
+
+	Ok(data)
+}
+
+/// A mailer writing to a shared outbox folder, for the tests not reading the emails.
+pub fn outbox() -> Result<Arc<dyn Mailer>> {
+	Ok(Arc::new(OutboxMailer::new(temp_dir().join("web_test_outbox"), "quote-server <noreply@localhost>")?))
+}
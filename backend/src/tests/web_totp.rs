@@ -0,0 +1,58 @@
+use super::totp_rest_filters;
+use crate::model::init_db;
+use crate::security::{new_token, totp_now, TotpEnrollment, TotpRecoveryCodes};
+use crate::web::handle_rejection;
+use crate::web::session::session_rest_filters;
+use crate::web::test_utils::extract_body_data;
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_totp_admin_enrol() -> Result<()> {
+	// FIXTURE
+	let db = init_db().await?;
+	sqlx::query("DELETE FROM user_totp WHERE user_id = 123").execute(&db).await?;
+	let db = Arc::new(db);
+	let apis = totp_rest_filters("api", db.clone()).or(session_rest_filters("api", db.clone())).recover(handle_rejection);
+	let sessions = |user_id: i64| warp::test::request().method("GET").header("X-Auth-Token", new_token(user_id, user_id)).path("/api/me/sessions");
+
+	// ACTION
+	let resp_before = sessions(123).reply(&apis).await;
+	let resp_contributor = sessions(456).reply(&apis).await;
+	let resp_enrol = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/totp")
+		.reply(&apis)
+		.await;
+	let enrollment: TotpEnrollment = extract_body_data(resp_enrol)?;
+	let resp_confirm = warp::test::request()
+		.method("POST")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/totp/confirm")
+		.json(&json!({ "code": totp_now(&enrollment.secret) }))
+		.reply(&apis)
+		.await;
+	let resp_after = sessions(123).reply(&apis).await;
+	let resp_disable = warp::test::request()
+		.method("DELETE")
+		.header("X-Auth-Token", new_token(123, 123))
+		.path("/api/me/totp")
+		.json(&json!({ "code": "000000" }))
+		.reply(&apis)
+		.await;
+
+	// CHECK
+	assert_eq!(403, resp_before.status(), "admin without TOTP");
+	assert_eq!(200, resp_contributor.status(), "TOTP optional for contributors");
+	assert!(enrollment.otpauth_uri.contains(&format!("secret={}", enrollment.secret)), "{}", enrollment.otpauth_uri);
+	assert_eq!(200, resp_confirm.status(), "http status (confirm)");
+	let recovery: TotpRecoveryCodes = extract_body_data(resp_confirm)?;
+	assert_eq!(10, recovery.recovery_codes.len());
+	assert_eq!(200, resp_after.status(), "admin enrolled");
+	assert_eq!(400, resp_disable.status(), "admins keep TOTP");
+
+	Ok(())
+}
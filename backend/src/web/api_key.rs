@@ -0,0 +1,59 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use crate::model::{ApiKeyForCreate, ApiKeyMac, ApiScope, Db};
+use crate::security::{self, UserCtx};
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn api_key_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::Admin));
+	let api_keys_path = warp::path(base_path).and(warp::path("me")).and(warp::path("api-keys"));
+
+	// CREATE an API key `POST /me/api-keys with body {"name": "...", "scopes": ["quotes:read"], "expires": 1767225600}`
+	// (the key is only in this response)
+	let create = api_keys_path
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(common.clone())
+		.and(warp::body::json())
+		.and_then(api_key_create);
+
+	// LIST my API keys `GET /me/api-keys`
+	let list = api_keys_path
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(common.clone())
+		.and_then(api_key_list);
+
+	// REVOKE an API key `DELETE /me/api-keys/1000`
+	let delete = api_keys_path
+		.and(warp::delete())
+		.and(common.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(api_key_delete);
+
+	create.or(list).or(delete)
+}
+
+async fn api_key_create(db: Arc<Db>, utx: UserCtx, data: ApiKeyForCreate) -> Result<Json, warp::Rejection> {
+	let created = security::create_api_key(&db, &utx, data).await?;
+	json_response(created)
+}
+
+async fn api_key_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+	let api_keys = ApiKeyMac::list(&db, &utx).await?;
+	json_response(api_keys)
+}
+
+async fn api_key_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+	let api_key = ApiKeyMac::delete(&db, &utx, id).await?;
+	json_response(api_key)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_api_key.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,152 @@
+use super::filter_auth::{auth_cookie, clear_auth_cookie, do_auth, request_token};
+use super::filter_utils::with_mailer;
+use super::json_response;
+use crate::mail::Mailer;
+use crate::model::{ApiScope, Db, SessionForCreate};
+use crate::security::{self, EmailVerification, LoginForm, PasswordReset, PasswordResetRequest, UserCtx, UserForRegister};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use warp::http::header::SET_COOKIE;
+use warp::reply::{Json, WithHeader};
+use warp::Filter;
+
+pub fn auth_rest_filters(
+	base_path: &'static str,
+	db: Arc<Db>,
+	mailer: Arc<dyn Mailer>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let with_db = super::filter_utils::with_db(db.clone());
+	let with_mailer = with_mailer(mailer);
+
+	// REGISTER `POST /register with body {"username": "...", "email": "...", "password": "..."}`, sends the email verification
+	let register = warp::path(base_path)
+		.and(warp::path("register"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db.clone())
+		.and(with_mailer.clone())
+		.and(warp::body::json())
+		.and_then(auth_register);
+
+	// LOGIN `POST /login with body {"username": "...", "password": "..."}`, gives the X-Auth-Token, also set as auth cookie
+	let login = warp::path(base_path)
+		.and(warp::path("login"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db.clone())
+		.and(warp::addr::remote())
+		.and(warp::header::optional::<String>("user-agent"))
+		.and(warp::body::json())
+		.and_then(auth_login);
+
+	// LOGOUT `POST /logout`, revokes the session of the token and clears the auth cookie
+	let logout = warp::path(base_path)
+		.and(warp::path("logout"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db.clone())
+		.and(request_token())
+		.and_then(auth_logout);
+
+	// PASSWORD RESET `POST /password-reset with body {"email": "..."}`, emails the reset link (same answer for an unknown email)
+	let reset_request = warp::path(base_path)
+		.and(warp::path("password-reset"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db.clone())
+		.and(with_mailer.clone())
+		.and(warp::body::json())
+		.and_then(auth_reset_request);
+
+	// PASSWORD RESET `POST /password-reset/confirm with body {"token": "...", "password": "..."}`, logs out everywhere
+	let reset = warp::path(base_path)
+		.and(warp::path("password-reset"))
+		.and(warp::path("confirm"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db.clone())
+		.and(warp::body::json())
+		.and_then(auth_reset);
+
+	// EMAIL VERIFICATION `POST /me/email-verification`, emails the verification link again
+	let verification_request = warp::path(base_path)
+		.and(warp::path("me"))
+		.and(warp::path("email-verification"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db.clone())
+		.and(do_auth(db.clone(), ApiScope::Admin))
+		.and(with_mailer)
+		.and_then(auth_verification_request);
+
+	// EMAIL VERIFICATION `POST /email-verification/confirm with body {"token": "..."}`
+	let verification = warp::path(base_path)
+		.and(warp::path("email-verification"))
+		.and(warp::path("confirm"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db)
+		.and(warp::body::json())
+		.and_then(auth_verification);
+
+	register.or(login).or(logout).or(reset_request).or(reset).or(verification_request).or(verification)
+}
+
+async fn auth_register(db: Arc<Db>, mailer: Arc<dyn Mailer>, data: UserForRegister) -> Result<Json, warp::Rejection> {
+	let user = security::register(&db, data).await?;
+	// the account is created anyway, the user can ask for the email again
+	if let Err(ex) = security::send_email_verification(&db, &*mailer, user.id).await {
+		println!("ERROR - email verification of user {} not sent. Cause {:?}", user.id, ex);
+	}
+	json_response(user)
+}
+
+async fn auth_login(
+	db: Arc<Db>,
+	addr: Option<SocketAddr>,
+	user_agent: Option<String>,
+	form: LoginForm,
+) -> Result<WithHeader<Json>, warp::Rejection> {
+	let client = SessionForCreate {
+		ip: addr.map(|addr| addr.ip().to_string()),
+		user_agent,
+	};
+	let result = security::login(&db, form, client).await?;
+	let cookie = auth_cookie(&result.token);
+	Ok(warp::reply::with_header(json_response(result)?, SET_COOKIE, cookie))
+}
+
+async fn auth_logout(db: Arc<Db>, token: Option<String>) -> Result<WithHeader<Json>, warp::Rejection> {
+	if let Some(token) = token {
+		security::logout(&db, &token).await?;
+	}
+	let result = json!({ "success": true });
+	Ok(warp::reply::with_header(json_response(result)?, SET_COOKIE, clear_auth_cookie()))
+}
+
+async fn auth_reset_request(db: Arc<Db>, mailer: Arc<dyn Mailer>, data: PasswordResetRequest) -> Result<Json, warp::Rejection> {
+	security::request_password_reset(&db, mailer, &data.email);
+	json_response(json!({ "success": true }))
+}
+
+async fn auth_reset(db: Arc<Db>, data: PasswordReset) -> Result<Json, warp::Rejection> {
+	security::reset_password(&db, data).await?;
+	json_response(json!({ "success": true }))
+}
+
+async fn auth_verification_request(db: Arc<Db>, utx: UserCtx, mailer: Arc<dyn Mailer>) -> Result<Json, warp::Rejection> {
+	security::send_email_verification(&db, &*mailer, utx.user_id).await?;
+	json_response(json!({ "success": true }))
+}
+
+async fn auth_verification(db: Arc<Db>, data: EmailVerification) -> Result<Json, warp::Rejection> {
+	let user = security::verify_email(&db, data).await?;
+	json_response(user)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_auth.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,191 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use super::quote::{cite_response, CiteParams};
+use crate::model::{ApiScope, CollectionMac, CollectionPatch, Db, ListOptions, Quote, QuoteFilter, QuoteMac};
+use crate::security::UserCtx;
+use futures::TryStreamExt;
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::{Filter, Reply};
+
+pub fn collection_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let collections_path = warp::path(base_path).and(warp::path("collections"));
+	let common_read = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesRead));
+	let common_write = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesWrite));
+
+	// LIST collections `GET collections/`
+	let list = collections_path
+		.and(warp::get())
+		.and(warp::path::end())
+		.and(common_read.clone())
+		.and(warp::query::<ListOptions>())
+		.and_then(collection_list);
+
+	// GET collection `GET /collections/100`
+	let get = collections_path
+		.and(warp::get())
+		.and(common_read.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(collection_get);
+
+	// CREATE collection `POST /collections with body CollectionPatch`
+	let create = collections_path
+		.and(warp::post())
+		.and(warp::path::end())
+		.and(common_write.clone())
+		.and(warp::body::json())
+		.and_then(collection_create);
+
+	// UPDATE collection `PATCH /collections/100 with body CollectionPatch`
+	let update = collections_path
+		.and(warp::patch())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(collection_update);
+
+	// DELETE collection `DELETE /collections/100`
+	let delete = collections_path
+		.and(warp::delete())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(collection_delete);
+
+	// LIST collection quotes, in order `GET /collections/100/quotes`
+	let list_quotes = collections_path
+		.and(warp::get())
+		.and(common_read.clone())
+		.and(warp::path::param())
+		.and(warp::path("quotes"))
+		.and(warp::path::end())
+		.and(warp::query::<ListOptions>())
+		.and_then(collection_list_quotes);
+
+	// ADD quote `POST /collections/100/quotes with body {"quote_id": 101, "position": 0}`
+	let add_quote = collections_path
+		.and(warp::post())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("quotes"))
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(collection_add_quote);
+
+	// REORDER quotes `PUT /collections/100/quotes with body {"quote_ids": [101, 100]}`
+	let reorder = collections_path
+		.and(warp::put())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("quotes"))
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(collection_reorder);
+
+	// REMOVE quote `DELETE /collections/100/quotes/101`
+	let remove_quote = collections_path
+		.and(warp::delete())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("quotes"))
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(collection_remove_quote);
+
+	// CITE the collection quotes `GET /collections/100/cite?format=bibtex`
+	let cite = collections_path
+		.and(warp::get())
+		.and(common_read.clone())
+		.and(warp::path::param())
+		.and(warp::path("cite"))
+		.and(warp::path::end())
+		.and(warp::query::<CiteParams>())
+		.and_then(collection_cite);
+
+	list.or(get)
+		.or(create)
+		.or(update)
+		.or(delete)
+		.or(list_quotes)
+		.or(add_quote)
+		.or(reorder)
+		.or(remove_quote)
+		.or(cite)
+}
+
+#[derive(Debug, Deserialize)]
+struct AddQuoteBody {
+	quote_id: i64,
+	position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReorderBody {
+	quote_ids: Vec<i64>,
+}
+
+async fn collection_list(db: Arc<Db>, utx: UserCtx, list_options: ListOptions) -> Result<Json, warp::Rejection> {
+	let collections = CollectionMac::list(&db, &utx, &list_options).await?;
+	json_response(collections)
+}
+
+async fn collection_get(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::get(&db, &utx, id).await?;
+	json_response(collection)
+}
+
+async fn collection_create(db: Arc<Db>, utx: UserCtx, patch: CollectionPatch) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::create(&db, &utx, patch).await?;
+	json_response(collection)
+}
+
+async fn collection_update(db: Arc<Db>, utx: UserCtx, id: i64, patch: CollectionPatch) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::update(&db, &utx, id, patch).await?;
+	json_response(collection)
+}
+
+async fn collection_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::delete(&db, &utx, id).await?;
+	json_response(collection)
+}
+
+async fn collection_list_quotes(db: Arc<Db>, utx: UserCtx, id: i64, list_options: ListOptions) -> Result<Json, warp::Rejection> {
+	let quotes = CollectionMac::list_quotes(&db, &utx, id, &list_options).await?;
+	json_response(quotes)
+}
+
+async fn collection_add_quote(db: Arc<Db>, utx: UserCtx, id: i64, body: AddQuoteBody) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::add_quote(&db, &utx, id, body.quote_id, body.position).await?;
+	json_response(collection)
+}
+
+async fn collection_reorder(db: Arc<Db>, utx: UserCtx, id: i64, body: ReorderBody) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::reorder(&db, &utx, id, &body.quote_ids).await?;
+	json_response(collection)
+}
+
+async fn collection_remove_quote(db: Arc<Db>, utx: UserCtx, id: i64, quote_id: i64) -> Result<Json, warp::Rejection> {
+	let collection = CollectionMac::remove_quote(&db, &utx, id, quote_id).await?;
+	json_response(collection)
+}
+
+async fn collection_cite(db: Arc<Db>, utx: UserCtx, id: i64, params: CiteParams) -> Result<impl Reply, warp::Rejection> {
+	// visibility check
+	CollectionMac::get(&db, &utx, id).await?;
+
+	let filter = QuoteFilter {
+		collection_id: Some(id),
+		..Default::default()
+	};
+	let quotes: Vec<Quote> = QuoteMac::stream(&db, &utx, &filter)?.try_collect().await?;
+	cite_response(&quotes, params)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_collection.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,53 @@
+use super::filter_auth::do_auth;
+use crate::export::{self, ExportFormat};
+use crate::model::{ApiScope, Db, QuoteFilter, QuoteMac};
+use crate::security::UserCtx;
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::http::Response;
+use warp::hyper::Body;
+use warp::Filter;
+
+pub fn export_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db, ApiScope::QuotesRead));
+
+	// EXPORT quotes `GET /export?format=csv` with the list filters (e.g., `&sort=popular&lang=es&collection_id=100`)
+	warp::path(base_path)
+		.and(warp::path("export"))
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(common)
+		.and(warp::query::<ExportParams>())
+		.and(warp::query::<QuoteFilter>())
+		.and_then(export_quotes)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ExportParams {
+	format: Option<String>, // defaults to json
+}
+
+async fn export_quotes(db: Arc<Db>, utx: UserCtx, params: ExportParams, filter: QuoteFilter) -> Result<Response<Body>, warp::Rejection> {
+	let format = match params.format {
+		Some(format) => format.parse::<ExportFormat>()?,
+		None => ExportFormat::default(),
+	};
+
+	// checked before the body, so a denied or invalid export is an error status, not a truncated 200
+	let quotes = QuoteMac::stream(&db, &utx, &filter)?;
+	let body = Body::wrap_stream(export::encode(quotes, format));
+
+	let response = Response::builder()
+		.header("Content-Type", format.content_type())
+		.header("Content-Disposition", format!("attachment; filename=\"{}\"", format.file_name()))
+		.body(body)
+		.map_err(|ex| export::Error::Encode(ex.to_string()))?;
+
+	Ok(response)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_export.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,64 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use crate::model::{ApiScope, Db, FavoriteMac, ListOptions};
+use crate::security::UserCtx;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn favorite_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common_read = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesRead));
+	let common_write = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesWrite));
+	let quotes_path = warp::path(base_path).and(warp::path("quotes"));
+
+	// FAVORITE quote `PUT /quotes/100/favorite`
+	let add = quotes_path
+		.and(warp::put())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("favorite"))
+		.and(warp::path::end())
+		.and_then(favorite_add);
+
+	// UNFAVORITE quote `DELETE /quotes/100/favorite`
+	let remove = quotes_path
+		.and(warp::delete())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("favorite"))
+		.and(warp::path::end())
+		.and_then(favorite_remove);
+
+	// LIST my favorites `GET /me/favorites`
+	let list = warp::path(base_path)
+		.and(warp::path("me"))
+		.and(warp::path("favorites"))
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(common_read.clone())
+		.and(warp::query::<ListOptions>())
+		.and_then(favorite_list);
+
+	add.or(remove).or(list)
+}
+
+async fn favorite_add(db: Arc<Db>, utx: UserCtx, quote_id: i64) -> Result<Json, warp::Rejection> {
+	let quote = FavoriteMac::add(&db, &utx, quote_id).await?;
+	json_response(quote)
+}
+
+async fn favorite_remove(db: Arc<Db>, utx: UserCtx, quote_id: i64) -> Result<Json, warp::Rejection> {
+	let quote = FavoriteMac::remove(&db, &utx, quote_id).await?;
+	json_response(quote)
+}
+
+async fn favorite_list(db: Arc<Db>, utx: UserCtx, list_options: ListOptions) -> Result<Json, warp::Rejection> {
+	let quotes = FavoriteMac::list(&db, &utx, &list_options).await?;
+	json_response(quotes)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_favorite.rs"]
+mod tests;
+// endregion: Test
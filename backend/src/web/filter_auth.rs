@@ -1,23 +1,118 @@
 use super::filter_utils::with_db;
-use crate::model::Db;
-use crate::security::{utx_from_token, UserCtx};
+use crate::config::config;
+use crate::model::{ApiScope, Db};
+use crate::security::{self, refresh_token, utx_from_api_key, utx_from_jwt, utx_from_token, UserCtx};
 use crate::web::Error;
 use std::sync::Arc;
-use warp::{Filter, Rejection};
+use warp::http::header::SET_COOKIE;
+use warp::http::HeaderValue;
+use warp::{Filter, Rejection, Reply};
 
 const HEADER_XAUTH: &str = "X-Auth-Token";
+const HEADER_API_KEY: &str = "X-Api-Key";
+const HEADER_AUTHORIZATION: &str = "Authorization";
+const COOKIE_AUTH: &str = "auth-token";
 
-pub fn do_auth(db: Arc<Db>) -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
+/// The user of the `X-Api-Key` header, when its key has the `scope` of the route,
+/// or else of the `Authorization: Bearer` JWT (see security::jwt),
+/// or else of the `X-Auth-Token` header or of the auth cookie (browser frontend), with all the scopes.
+/// The API keys, sessions and JWTs of admins without two-factor authentication are refused, until they enrol (see `do_auth_totp_enrol`).
+pub fn do_auth(db: Arc<Db>, scope: ApiScope) -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
+	auth(db, scope, false)
+}
+
+/// As `do_auth` with the admin scope, also accepting the sessions of admins enrolling their two-factor authentication.
+pub fn do_auth_totp_enrol(db: Arc<Db>) -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
+	auth(db, ApiScope::Admin, true)
+}
+
+fn auth(db: Arc<Db>, scope: ApiScope, enrolling: bool) -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
 	warp::any()
 		.and(with_db(db))
-		.and(warp::header::optional(HEADER_XAUTH))
-		.and_then(|db: Arc<Db>, xauth: Option<String>| async move {
-			match xauth {
-				Some(xauth) => {
-					let utx = utx_from_token(&db, &xauth).await?;
-					Ok::<UserCtx, Rejection>(utx)
+		.and(warp::header::optional::<String>(HEADER_API_KEY))
+		.and(bearer_token())
+		.and(request_token())
+		.and_then(move |db: Arc<Db>, api_key: Option<String>, bearer: Option<String>, xauth: Option<String>| async move {
+			match (api_key, bearer, xauth) {
+				(Some(api_key), _, _) => {
+					let (utx, scopes) = utx_from_api_key(&db, &api_key).await?;
+					match scopes.iter().any(|s| s.allows(scope)) {
+						// an API key never enrols, the two-factor authentication is set up from a session
+						true => enrolled(utx, false),
+						false => Err(security::Error::MissingScope(scope.as_str().to_string()).into()),
+					}
+				}
+				(None, Some(bearer), _) => enrolled(utx_from_jwt(&db, &bearer).await?, enrolling),
+				(None, None, Some(xauth)) => enrolled(utx_from_token(&db, &xauth).await?, enrolling),
+				(None, None, None) => Err(Error::FailAuthMissingXAuth.into()),
+			}
+		})
+}
+
+/// The user, unless it is an admin who has to enrol its two-factor authentication first.
+fn enrolled(utx: UserCtx, enrolling: bool) -> Result<UserCtx, Rejection> {
+	match utx.must_enrol_totp() && !enrolling {
+		true => Err(security::Error::TotpEnrollmentRequired.into()),
+		false => Ok(utx),
+	}
+}
+
+/// Send a new `X-Auth-Token` response header when the request token is close to its expiration
+/// (or signed with the previous secret), for the client to use from then on.
+/// A token from the auth cookie is refreshed in the cookie.
+pub fn with_token_refresh<F, R>(apis: F) -> impl Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone
+where
+	F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+	R: Reply,
+{
+	warp::header::optional::<String>(HEADER_XAUTH)
+		.and(warp::cookie::optional::<String>(COOKIE_AUTH))
+		.and(apis)
+		.map(|xauth: Option<String>, cookie: Option<String>, reply: R| {
+			let mut response = reply.into_response();
+			let headers = response.headers_mut();
+			if let Some(token) = xauth.as_deref().and_then(refresh_token).and_then(|t| HeaderValue::from_str(&t).ok()) {
+				headers.insert(HEADER_XAUTH, token);
+			} else if !headers.contains_key(SET_COOKIE) {
+				// unless the api set (or cleared) the cookie itself
+				if let Some(cookie) = cookie.as_deref().and_then(refresh_token).and_then(|t| HeaderValue::from_str(&auth_cookie(&t)).ok()) {
+					headers.insert(SET_COOKIE, cookie);
 				}
-				None => Err(Error::FailAuthMissingXAuth.into()),
 			}
+			response
+		})
+}
+
+/// The `Set-Cookie` value of the auth cookie.
+pub fn auth_cookie(token: &str) -> String {
+	site_cookie(COOKIE_AUTH, token, config().token_ttl_secs, "Strict")
+}
+
+/// The `Set-Cookie` value removing the auth cookie.
+pub fn clear_auth_cookie() -> String {
+	site_cookie(COOKIE_AUTH, "", 0, "Strict")
+}
+
+/// A `Set-Cookie` value of the site, not readable by scripts and only sent over https
+/// (except with QUOTE_DEV_MODE, as the plain http dev server would never get it back).
+pub fn site_cookie(name: &str, value: &str, max_age: i64, same_site: &str) -> String {
+	let secure = if config().dev_mode { "" } else { " Secure;" };
+	format!("{}={}; Path=/; Max-Age={}; HttpOnly;{} SameSite={}", name, value, max_age, secure, same_site)
+}
+
+/// The request token, from the `X-Auth-Token` header first, or else from the auth cookie.
+pub fn request_token() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+	warp::header::optional::<String>(HEADER_XAUTH)
+		.and(warp::cookie::optional::<String>(COOKIE_AUTH))
+		.map(|xauth: Option<String>, cookie: Option<String>| xauth.or(cookie))
+}
+
+/// The JWT of the `Authorization: Bearer [jwt]` header.
+fn bearer_token() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+	warp::header::optional::<String>(HEADER_AUTHORIZATION).map(|authorization: Option<String>| {
+		authorization.and_then(|value| {
+			let (scheme, token) = value.trim().split_once(' ')?;
+			scheme.eq_ignore_ascii_case("bearer").then(|| token.trim().to_string())
 		})
-}
\ No newline at end of file
+	})
+}
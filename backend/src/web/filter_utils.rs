@@ -1,3 +1,4 @@
+use crate::mail::Mailer;
 use crate::model::Db;
 use std::convert::Infallible;
 use std::sync::Arc;
@@ -5,4 +6,7 @@ use warp::Filter;
 
 pub fn with_db(db: Arc<Db>) -> impl Filter<Extract = (Arc<Db>,), Error = Infallible> + Clone {
 	warp::any().map(move || db.clone())
-}
\ No newline at end of file
+}
+pub fn with_mailer(mailer: Arc<dyn Mailer>) -> impl Filter<Extract = (Arc<dyn Mailer>,), Error = Infallible> + Clone {
+	warp::any().map(move || mailer.clone())
+}
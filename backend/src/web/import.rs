@@ -0,0 +1,71 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use crate::import::{self, ImportFormat, ImportOptions};
+use crate::model::{ApiScope, Db};
+use crate::security::UserCtx;
+use serde::Deserialize;
+use std::str::from_utf8;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::reply::Json;
+use warp::Filter;
+
+const HEADER_CONTENT_TYPE: &str = "Content-Type";
+const IMPORT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+pub fn import_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db, ApiScope::QuotesWrite));
+
+	// IMPORT quotes `POST /import?format=csv&dry_run=true&map=quote:text with the file as body`
+	warp::path(base_path)
+		.and(warp::path("import"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(common)
+		.and(warp::query::<ImportParams>())
+		.and(warp::header::optional::<String>(HEADER_CONTENT_TYPE))
+		.and(warp::body::content_length_limit(IMPORT_MAX_BYTES))
+		.and(warp::body::bytes())
+		.and_then(import_quotes)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImportParams {
+	format: Option<String>, // defaults to the Content-Type
+	#[serde(default)]
+	dry_run: bool,
+	map: Option<String>,
+	#[serde(default)]
+	notes: bool, // kindle notes
+	batch_size: Option<usize>,
+}
+
+async fn import_quotes(
+	db: Arc<Db>,
+	utx: UserCtx,
+	params: ImportParams,
+	content_type: Option<String>,
+	body: Bytes,
+) -> Result<Json, warp::Rejection> {
+	let format = params
+		.format
+		.or_else(|| content_type.map(|ct| ct.split(';').next().unwrap_or_default().trim().to_string()))
+		.unwrap_or_default();
+	let mut options = ImportOptions::new(format.parse::<ImportFormat>()?);
+	options.dry_run = params.dry_run;
+	options.include_notes = params.notes;
+	options.batch_size = params.batch_size.unwrap_or(options.batch_size);
+	if let Some(map) = params.map {
+		options.mapping = import::parse_mapping(&map)?;
+	}
+
+	let content = from_utf8(&body).map_err(|ex| import::Error::InvalidFile(ex.to_string()))?;
+	let report = import::import_quotes(&db, &utx, content, &options).await?;
+	json_response(report)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_import.rs"]
+mod tests;
+// endregion: Test
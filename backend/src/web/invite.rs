@@ -0,0 +1,59 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use crate::model::{ApiScope, Db, InviteForCreate, InviteMac};
+use crate::security::{self, UserCtx};
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn invite_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::Admin));
+	let invites_path = warp::path(base_path).and(warp::path("invites"));
+
+	// CREATE an invite `POST /invites with body {"role": "contributor", "max_uses": 1, "expires": 1767225600}` (all optional)
+	// (the code is only in this response)
+	let create = invites_path
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(common.clone())
+		.and(warp::body::json())
+		.and_then(invite_create);
+
+	// LIST the invites, with who used them `GET /invites`
+	let list = invites_path
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(common.clone())
+		.and_then(invite_list);
+
+	// REVOKE an invite `DELETE /invites/1000`
+	let revoke = invites_path
+		.and(warp::delete())
+		.and(common.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(invite_revoke);
+
+	create.or(list).or(revoke)
+}
+
+async fn invite_create(db: Arc<Db>, utx: UserCtx, data: InviteForCreate) -> Result<Json, warp::Rejection> {
+	let created = security::create_invite(&db, &utx, data).await?;
+	json_response(created)
+}
+
+async fn invite_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+	let invites = InviteMac::list(&db, &utx).await?;
+	json_response(invites)
+}
+
+async fn invite_revoke(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+	let invite = InviteMac::revoke(&db, &utx, id).await?;
+	json_response(invite)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_invite.rs"]
+mod tests;
+// endregion: Test
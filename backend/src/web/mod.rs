@@ -1,15 +1,48 @@
-use crate::model::{self, Db};
-use crate::security;
+use crate::config::config;
+use crate::import::ImportReport;
+use crate::mail;
+use crate::model::{self, Db, Quote};
+use crate::security::{self, Oidc};
+use crate::web::api_key::api_key_rest_filters;
+use crate::web::auth::auth_rest_filters;
+use crate::web::collection::collection_rest_filters;
+use crate::web::export::export_rest_filters;
+use crate::web::favorite::favorite_rest_filters;
+use crate::web::import::import_rest_filters;
+use crate::web::invite::invite_rest_filters;
+use crate::web::moderation::moderation_rest_filters;
+use crate::web::oidc::oidc_rest_filters;
 use crate::web::quote::quote_rest_filters; // Replaces abovve
+use crate::web::report::report_rest_filters;
+use crate::web::session::session_rest_filters;
+use crate::web::stats::stats_rest_filters;
+use crate::web::totp::totp_rest_filters;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::json;
 use std::convert::Infallible;
 use std::path::Path;
 use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::reply::Json;
 use warp::{Filter, Rejection, Reply};
 
+mod api_key;
+mod auth;
+mod collection;
+mod export;
+mod favorite;
 mod filter_auth;
 mod filter_utils;
+mod import;
+mod invite;
+mod moderation;
+mod oidc;
 mod quote; // Replaces abovve 
+mod report;
+mod session;
+mod stats;
+mod totp;
 
 pub async fn start_web(web_folder: &str, web_port: u16, db: Arc<Db>) -> Result<(), Error> {
     // Validate the web_folder
@@ -18,7 +51,23 @@ pub async fn start_web(web_folder: &str, web_port: u16, db: Arc<Db>) -> Result<(
     }
 
 	// Apis
-	let apis = quote_rest_filters("api", db);
+	let oidc = config().oidc.clone().map(|oidc| Arc::new(Oidc::new(oidc)));
+	let mailer = mail::mailer(&config().mail).map_err(|ex| Error::FailStartMailer(ex.to_string()))?;
+	let apis = auth_rest_filters("api", db.clone(), mailer)
+		.or(oidc_rest_filters("api", db.clone(), oidc))
+		.or(session_rest_filters("api", db.clone()))
+		.or(totp_rest_filters("api", db.clone()))
+		.or(api_key_rest_filters("api", db.clone()))
+		.or(invite_rest_filters("api", db.clone()))
+		.or(quote_rest_filters("api", db.clone()))
+		.or(favorite_rest_filters("api", db.clone()))
+		.or(collection_rest_filters("api", db.clone()))
+		.or(moderation_rest_filters("api", db.clone()))
+		.or(report_rest_filters("api", db.clone()))
+		.or(stats_rest_filters("api", db.clone()))
+		.or(import_rest_filters("api", db.clone()))
+		.or(export_rest_filters("api", db));
+	let apis = filter_auth::with_token_refresh(apis);
 
     // Static content
     let content = warp::fs::dir(web_folder.to_string());
@@ -40,27 +89,65 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
 	// Print to server side
 	println!("ERROR - {:?}", err);
 
+	// Stale If-Match, answer with the current representation
+	if let Some(PreconditionFailed(current)) = err.find::<PreconditionFailed>() {
+		let result = json!({ "errorMessage": "PreconditionFailed", "data": current });
+		let result = warp::reply::json(&result);
+		let result = warp::reply::with_header(result, "ETag", quote::etag(current));
+		return Ok(warp::reply::with_status(result, StatusCode::PRECONDITION_FAILED).into_response());
+	}
+
+	// Import interrupted by a failed batch, answer with what the committed batches created
+	if let Some(ImportInterrupted(report, message)) = err.find::<ImportInterrupted>() {
+		let result = json!({ "errorMessage": "import::Error", "errorDetail": message, "data": report });
+		return Ok(warp::reply::with_status(warp::reply::json(&result), StatusCode::INTERNAL_SERVER_ERROR).into_response());
+	}
+
 	// Quote - Call log API for capture and store
 
 	// Build user message
-	let user_message = match err.find::<WebErrorMessage>() {
-		Some(err) => err.typ.to_string(),
-		None => "Unknown".to_string(),
+	let (status, user_message, detail) = match err.find::<WebErrorMessage>() {
+		Some(err) => (err.status, err.typ.to_string(), Some(err.message.clone())),
+		None => (StatusCode::BAD_REQUEST, "Unknown".to_string(), None),
 	};
 
-	let result = json!({ "errorMessage": user_message });
+	let result = json!({ "errorMessage": user_message, "errorDetail": detail });
 	let result = warp::reply::json(&result);
 
-	Ok(warp::reply::with_status(result, warp::http::StatusCode::BAD_REQUEST))
+	Ok(warp::reply::with_status(result, status).into_response())
+}
+
+// region:    Utils
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+	let response = json!({ "data": data });
+	Ok(warp::reply::json(&response))
 }
 
+fn parse_body<D: DeserializeOwned>(body: &[u8]) -> Result<D, Error> {
+	serde_json::from_slice(body).map_err(|ex| Error::FailBodyJson(ex.to_string()))
+}
+// endregion: Utils
+
 #[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("Web server failed to start because web-folder '{0}' not found.")]
     FailStartWebFolderNotFound(String),
 
-    #[error("Fail authentication missing X-Auth-Token header.")]
+    #[error("Web server failed to start because the mailer is misconfigured ({0}).")]
+    FailStartMailer(String),
+
+    #[error("Fail authentication missing X-Auth-Token header, auth cookie, Authorization bearer token or X-Api-Key header.")]
     FailAuthMissingXAuth,
+
+    #[error("If-Match header '{0}' is not a quote ETag.")]
+    FailIfMatchFormat(String),
+
+    #[error("Unsupported media type '{0}'.")]
+    FailUnsupportedMediaType(String),
+
+    #[error("Fail to parse request body as JSON ({0}).")]
+    FailBodyJson(String),
 }
 
 // region: Warp Custom Error
@@ -68,28 +155,100 @@ pub enum Error {
 pub struct WebErrorMessage {
 	pub typ: &'static str,
 	pub message: String,
+	pub status: StatusCode,
 }
 impl warp::reject::Reject for WebErrorMessage {}
 
 impl WebErrorMessage {
 	pub fn rejection(typ: &'static str, message: String) -> warp::Rejection {
-		warp::reject::custom(WebErrorMessage { typ, message })
+		Self::rejection_with_status(StatusCode::BAD_REQUEST, typ, message)
+	}
+
+	pub fn rejection_with_status(status: StatusCode, typ: &'static str, message: String) -> warp::Rejection {
+		warp::reject::custom(WebErrorMessage { typ, message, status })
 	}
 }
 
+/// Rejection for a stale `If-Match`, carrying the current quote for the 412 body.
+#[derive(Debug)]
+pub struct PreconditionFailed(pub Box<Quote>);
+impl warp::reject::Reject for PreconditionFailed {}
+
+/// Rejection for an import stopped by a failed batch, carrying the report of the committed batches.
+#[derive(Debug)]
+pub struct ImportInterrupted(pub Box<ImportReport>, pub String);
+impl warp::reject::Reject for ImportInterrupted {}
+
 impl From<self::Error> for warp::Rejection {
 	fn from(other: self::Error) -> Self {
-		WebErrorMessage::rejection("web::Error", format!("{}", other))
+		let status = match other {
+			Error::FailUnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+			_ => StatusCode::BAD_REQUEST,
+		};
+		WebErrorMessage::rejection_with_status(status, "web::Error", format!("{}", other))
 	}
 }
 impl From<model::Error2> for warp::Rejection {
 	fn from(other: model::Error2) -> Self {
-		WebErrorMessage::rejection("model::Error", format!("{}", other))
+		match other {
+			model::Error2::QuoteVersionMismatch(current) => warp::reject::custom(PreconditionFailed(current)),
+			model::Error2::PermissionDenied(_) => {
+				WebErrorMessage::rejection_with_status(StatusCode::FORBIDDEN, "model::Error", format!("{}", other))
+			}
+			model::Error2::RateLimited(_) => {
+				WebErrorMessage::rejection_with_status(StatusCode::TOO_MANY_REQUESTS, "model::Error", format!("{}", other))
+			}
+			other => WebErrorMessage::rejection("model::Error", format!("{}", other)),
+		}
+	}
+}
+impl From<crate::import::Error> for warp::Rejection {
+	fn from(other: crate::import::Error) -> Self {
+		let message = format!("{}", other);
+		match other {
+			crate::import::Error::Model(other) => other.into(),
+			// nothing committed, as any other failed import
+			crate::import::Error::Interrupted { report, source } if report.created == 0 => source.into(),
+			crate::import::Error::Interrupted { report, .. } => warp::reject::custom(ImportInterrupted(report, message)),
+			_ => WebErrorMessage::rejection("import::Error", message),
+		}
+	}
+}
+impl From<crate::export::Error> for warp::Rejection {
+	fn from(other: crate::export::Error) -> Self {
+		match other {
+			crate::export::Error::Model(other) => other.into(),
+			crate::export::Error::MissingCitationMetadata(_) => {
+				WebErrorMessage::rejection_with_status(StatusCode::UNPROCESSABLE_ENTITY, "export::Error", format!("{}", other))
+			}
+			other => WebErrorMessage::rejection("export::Error", format!("{}", other)),
+		}
 	}
 }
 impl From<security::Error> for warp::Rejection {
 	fn from(other: security::Error) -> Self {
-		WebErrorMessage::rejection("security::Error", format!("{}", other))
+		let status = match other {
+			security::Error::Model(other) => return other.into(),
+			security::Error::InvalidToken
+			| security::Error::TokenExpired
+			| security::Error::SessionRevoked
+			| security::Error::InvalidJwt(_)
+			| security::Error::Oidc(_)
+			| security::Error::TotpRequired
+			| security::Error::InvalidTotp
+			| security::Error::InvalidApiKey
+			| security::Error::InvalidCredentials => StatusCode::UNAUTHORIZED,
+			security::Error::UserDisabled | security::Error::MissingScope(_) | security::Error::TotpEnrollmentRequired => StatusCode::FORBIDDEN,
+			security::Error::Mail(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			_ => StatusCode::BAD_REQUEST,
+		};
+		WebErrorMessage::rejection_with_status(status, "security::Error", format!("{}", other))
 	}
 }
-// endregion: Warp Custom Error
\ No newline at end of file
+// endregion: Warp Custom Error
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_test_utils.rs"]
+mod test_utils;
+// endregion: Test
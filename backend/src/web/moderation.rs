@@ -0,0 +1,74 @@
+use super::filter_auth::do_auth;
+use super::{json_response, parse_body};
+use crate::model::{ApiScope, Db, ListOptions, ModerationMac};
+use crate::security::UserCtx;
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn moderation_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::Admin));
+	let moderation_path = warp::path(base_path).and(warp::path("moderation"));
+
+	// LIST pending quotes, oldest first `GET /moderation`
+	let list = moderation_path
+		.and(warp::get())
+		.and(warp::path::end())
+		.and(common.clone())
+		.and(warp::query::<ListOptions>())
+		.and_then(moderation_list);
+
+	// APPROVE quote `POST /moderation/1000/approve with body {"reason": "..."}` (body optional)
+	let approve = moderation_path
+		.and(warp::post())
+		.and(common.clone())
+		.and(warp::path::param())
+		.and(warp::path("approve"))
+		.and(warp::path::end())
+		.and(warp::body::bytes())
+		.and_then(moderation_approve);
+
+	// REJECT quote `POST /moderation/1000/reject with body {"reason": "..."}`
+	let reject = moderation_path
+		.and(warp::post())
+		.and(common.clone())
+		.and(warp::path::param())
+		.and(warp::path("reject"))
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(moderation_reject);
+
+	list.or(approve).or(reject)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ModerationBody {
+	reason: Option<String>,
+}
+
+async fn moderation_list(db: Arc<Db>, utx: UserCtx, list_options: ListOptions) -> Result<Json, warp::Rejection> {
+	let quotes = ModerationMac::list_pending(&db, &utx, &list_options).await?;
+	json_response(quotes)
+}
+
+async fn moderation_approve(db: Arc<Db>, utx: UserCtx, id: i64, body: Bytes) -> Result<Json, warp::Rejection> {
+	let body: ModerationBody = match body.is_empty() {
+		true => ModerationBody::default(),
+		false => parse_body(&body)?,
+	};
+	let quote = ModerationMac::approve(&db, &utx, id, body.reason).await?;
+	json_response(quote)
+}
+
+async fn moderation_reject(db: Arc<Db>, utx: UserCtx, id: i64, body: ModerationBody) -> Result<Json, warp::Rejection> {
+	let quote = ModerationMac::reject(&db, &utx, id, body.reason.unwrap_or_default()).await?;
+	json_response(quote)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_moderation.rs"]
+mod tests;
+// endregion: Test
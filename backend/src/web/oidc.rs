@@ -0,0 +1,158 @@
+use super::filter_auth::{auth_cookie, site_cookie};
+use super::json_response;
+use crate::model::{Db, SessionForCreate};
+use crate::security::{self, Oidc, OidcLoginResult, TotpCode};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use warp::http::header::{LOCATION, SET_COOKIE};
+use warp::http::{HeaderValue, StatusCode};
+use warp::reply::Response;
+use warp::{Filter, Reply};
+
+/// Cookie of the state of the SSO login started by the browser, sent back with the redirect of the provider (SameSite=Lax).
+const COOKIE_OIDC_STATE: &str = "oidc-state";
+/// Cookie of the ticket of an SSO login waiting for the TOTP code.
+const COOKIE_OIDC_TOTP: &str = "oidc-totp";
+/// Lifetime of the cookies of the SSO login, as of the login state and ticket (see security::oidc).
+const LOGIN_COOKIE_MAX_AGE: i64 = 600;
+/// Where the callback sends the users with two-factor authentication, for the frontend to ask their code.
+const TOTP_REDIRECT: &str = "/?sso=totp";
+
+/// The query of the provider redirect, with a code, or an error (e.g., `access_denied`).
+#[derive(Debug, Deserialize)]
+struct OidcCallback {
+	code: Option<String>,
+	state: Option<String>,
+	error: Option<String>,
+	error_description: Option<String>,
+}
+
+/// The SSO login routes, not found when no provider is configured (`oidc` None).
+pub fn oidc_rest_filters(
+	base_path: &'static str,
+	db: Arc<Db>,
+	oidc: Option<Arc<Oidc>>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let with_db = super::filter_utils::with_db(db);
+	let with_oidc = warp::any().and_then(move || {
+		let oidc = oidc.clone();
+		async move { oidc.ok_or_else(warp::reject::not_found) }
+	});
+	let oidc_path = warp::path(base_path).and(warp::path("oidc"));
+
+	// LOGIN `GET /oidc/login`, redirects to the provider, with the state of the login in its cookie
+	let login = oidc_path
+		.and(warp::path("login"))
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(with_db.clone())
+		.and(with_oidc.clone())
+		.and_then(oidc_login);
+
+	// CALLBACK `GET /oidc/callback?code=...&state=...` from the provider, only for the browser of the state cookie,
+	// sets the auth cookie and redirects to the site
+	let callback = oidc_path
+		.and(warp::path("callback"))
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(with_db.clone())
+		.and(with_oidc.clone())
+		.and(warp::addr::remote())
+		.and(warp::header::optional::<String>("user-agent"))
+		.and(warp::cookie::optional::<String>(COOKIE_OIDC_STATE))
+		.and(warp::query::<OidcCallback>())
+		.and_then(oidc_callback);
+
+	// TWO-FACTOR `POST /oidc/totp with body {"code": "123456"}` after the callback of a user with TOTP (ticket in its cookie),
+	// answers and sets the auth cookie as the login
+	let totp = oidc_path
+		.and(warp::path("totp"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(with_db)
+		.and(with_oidc)
+		.and(warp::addr::remote())
+		.and(warp::header::optional::<String>("user-agent"))
+		.and(warp::cookie::optional::<String>(COOKIE_OIDC_TOTP))
+		.and(warp::body::json())
+		.and_then(oidc_totp);
+
+	login.or(callback).or(totp)
+}
+
+async fn oidc_login(db: Arc<Db>, oidc: Arc<Oidc>) -> Result<Response, warp::Rejection> {
+	let (url, state) = oidc.authorization_url(&db).await?;
+	let cookie = site_cookie(COOKIE_OIDC_STATE, &state, LOGIN_COOKIE_MAX_AGE, "Lax");
+	Ok(warp::reply::with_header(redirect(&url), SET_COOKIE, cookie).into_response())
+}
+
+async fn oidc_callback(
+	db: Arc<Db>,
+	oidc: Arc<Oidc>,
+	addr: Option<SocketAddr>,
+	user_agent: Option<String>,
+	browser_state: Option<String>,
+	query: OidcCallback,
+) -> Result<Response, warp::Rejection> {
+	let (code, state) = match (query.code, query.state, query.error) {
+		(Some(code), Some(state), None) => (code, state),
+		(_, _, Some(error)) => {
+			let description = query.error_description.unwrap_or_default();
+			return Err(security::Error::Oidc(format!("the provider answered {} {}", error, description).trim().to_string()).into());
+		}
+		_ => return Err(security::Error::Oidc("missing code or state".to_string()).into()),
+	};
+	// a login started by this browser, not one started elsewhere (e.g., by an attacker) and finished here
+	if browser_state.as_deref() != Some(state.as_str()) {
+		return Err(security::Error::Oidc("login state not started by this browser".to_string()).into());
+	}
+	let client = SessionForCreate {
+		ip: addr.map(|addr| addr.ip().to_string()),
+		user_agent,
+	};
+	let (url, cookie) = match oidc.callback(&db, &code, &state, client).await? {
+		OidcLoginResult::Session(result) => ("/", auth_cookie(&result.token)),
+		OidcLoginResult::TotpRequired { ticket } => (TOTP_REDIRECT, totp_cookie(&ticket)),
+	};
+	let mut response = redirect(url).into_response();
+	for cookie in [cookie, site_cookie(COOKIE_OIDC_STATE, "", 0, "Lax")] {
+		let cookie = HeaderValue::from_str(&cookie).map_err(|ex| security::Error::Oidc(ex.to_string()))?;
+		response.headers_mut().append(SET_COOKIE, cookie);
+	}
+	Ok(response)
+}
+
+async fn oidc_totp(
+	db: Arc<Db>,
+	oidc: Arc<Oidc>,
+	addr: Option<SocketAddr>,
+	user_agent: Option<String>,
+	ticket: Option<String>,
+	data: TotpCode,
+) -> Result<Response, warp::Rejection> {
+	let ticket = ticket.ok_or(security::Error::Oidc("no two-factor login in progress".to_string()))?;
+	let client = SessionForCreate {
+		ip: addr.map(|addr| addr.ip().to_string()),
+		user_agent,
+	};
+	let result = oidc.totp_login(&db, &ticket, &data.code, client).await?;
+	let cookie = auth_cookie(&result.token);
+	Ok(warp::reply::with_header(json_response(result)?, SET_COOKIE, cookie).into_response())
+}
+
+/// The `Set-Cookie` value of the ticket, kept as long as the ticket is valid.
+fn totp_cookie(ticket: &str) -> String {
+	site_cookie(COOKIE_OIDC_TOTP, ticket, LOGIN_COOKIE_MAX_AGE, "Strict")
+}
+
+/// A `303 See Other` to the url (warp::redirect only takes a parsed Uri).
+fn redirect(url: &str) -> impl Reply {
+	warp::reply::with_status(warp::reply::with_header(warp::reply(), LOCATION, url), StatusCode::SEE_OTHER)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_oidc.rs"]
+mod tests;
+// endregion: Test
@@ -1,86 +1,276 @@
 // *** Replaces todo.rs from the same folder ***
 use super::filter_auth::do_auth;
-use crate::model::{Db, QuoteMac, QuotePatch};
+use super::{json_response, parse_body, Error};
+use crate::export::{self, CiteFormat};
+use crate::model::{
+	self, parse_accept_language, parse_json_patch, ApiScope, Db, ListOptions, Quote, QuoteFilter, QuoteMac, QuotePatch,
+	TranslationForCreate, JSON_PATCH_MIME, MERGE_PATCH_MIME,
+};
 use crate::security::{utx_from_token, UserCtx};
+use serde::Deserialize;
 use std::convert::Infallible;
-use serde::Serialize;
-use serde_json::json;
 use std::sync::Arc;
-use warp::reply::Json;
-use warp::{Filter, Rejection};
+use warp::hyper::body::Bytes;
+use warp::reply::{Json, WithHeader};
+use warp::{Filter, Rejection, Reply};
+
+const HEADER_ETAG: &str = "ETag";
+const HEADER_IF_MATCH: &str = "If-Match";
+const HEADER_CONTENT_TYPE: &str = "Content-Type";
+const HEADER_ACCEPT_LANGUAGE: &str = "Accept-Language";
+const HEADER_CONTENT_LANGUAGE: &str = "Content-Language";
+const HEADER_VARY: &str = "Vary";
 
 pub fn quote_rest_filters(base_path: &'static str, db: Arc<Db>,) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
 	let quotes_path = warp::path(base_path).and(warp::path("quotes"));
-	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone()));
+	let common_read = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesRead));
+	let common_write = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesWrite));
 
 	// LIST quotes `GET quotes/`
 	let list = quotes_path
 		.and(warp::get())
 		.and(warp::path::end())
-		.and(common.clone())
+		.and(common_read.clone())
+		.and(warp::query::<QuoteFilter>())
+		.and(warp::query::<ListOptions>())
 		.and_then(quote_list);
 
 	// Get quote 'GET /quote/100'
 	let get = quotes_path
 		.and(warp::get())
-		.and(common.clone())
+		.and(common_read.clone())
 		.and(warp::path::param())
+		.and(warp::path::end())
+		.and(warp::query::<QuoteGetParams>())
+		.and(warp::header::optional(HEADER_ACCEPT_LANGUAGE))
 		.and_then(quote_get);
 
 	// CREATE quote `POST /quotes with body QuotePatch`
 	let create = quotes_path
 		.and(warp::post())
-		.and(common.clone())
+		.and(warp::path::end())
+		.and(common_write.clone())
 		.and(warp::body::json())
 		.and_then(quote_create);
 
 	// UPDATE quote `PATCH /quotes/100 with body QuotePatch`
+	// (or a merge patch / JSON patch document, given the Content-Type)
 	let update = quotes_path
 		.and(warp::patch())
-		.and(common.clone())
+		.and(common_write.clone())
 		.and(warp::path::param())
-		.and(warp::body::json())
+		.and(warp::path::end())
+		.and(warp::header::optional(HEADER_IF_MATCH))
+		.and(warp::header::optional(HEADER_CONTENT_TYPE))
+		.and(warp::body::bytes())
 		.and_then(quote_update);
 
 	// DELETE quote `DELETE /quotes/100`
 	let delete = quotes_path
 		.and(warp::delete())
-		.and(common.clone())
+		.and(common_write.clone())
 		.and(warp::path::param())
+		.and(warp::path::end())
+		.and(warp::header::optional(HEADER_IF_MATCH))
 		.and_then(quote_delete);
 
-	list.or(get).or(create).or(update).or(delete)
+	// LIST translations `GET /quotes/100/translations`
+	let list_translations = quotes_path
+		.and(warp::get())
+		.and(common_read.clone())
+		.and(warp::path::param())
+		.and(warp::path("translations"))
+		.and(warp::path::end())
+		.and_then(quote_list_translations);
+
+	// CREATE translation `POST /quotes/100/translations with body TranslationForCreate`
+	let create_translation = quotes_path
+		.and(warp::post())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("translations"))
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(quote_create_translation);
+
+	// CITE quote `GET /quotes/100/cite?format=bibtex` (bibtex, csl-json, apa or mla)
+	let cite = quotes_path
+		.and(warp::get())
+		.and(common_read.clone())
+		.and(warp::path::param())
+		.and(warp::path("cite"))
+		.and(warp::path::end())
+		.and(warp::query::<CiteParams>())
+		.and_then(quote_cite);
+
+	list.or(get)
+		.or(create)
+		.or(update)
+		.or(delete)
+		.or(list_translations)
+		.or(create_translation)
+		.or(cite)
+}
+
+#[derive(Debug, Deserialize)]
+struct QuoteGetParams {
+	lang: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct CiteParams {
+	format: Option<String>, // defaults to bibtex
 }
 
-async fn quote_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
-	let quotes = QuoteMac::list(&db, &utx).await?;
+async fn quote_list(db: Arc<Db>, utx: UserCtx, filter: QuoteFilter, list_options: ListOptions) -> Result<Json, warp::Rejection> {
+	let quotes = QuoteMac::list(&db, &utx, &filter, &list_options).await?;
 	json_response(quotes)
 }
 
-async fn quote_get(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
-	let quote = QuoteMac::get(&db, &utx, id).await?;
-	json_response(quote)
+async fn quote_get(
+	db: Arc<Db>,
+	utx: UserCtx,
+	id: i64,
+	params: QuoteGetParams,
+	accept_language: Option<String>,
+) -> Result<impl Reply, warp::Rejection> {
+	// `?lang=` first, then the Accept-Language ranges as fallback
+	let preferred: Vec<String> = params
+		.lang
+		.into_iter()
+		.chain(accept_language.as_deref().map(parse_accept_language).unwrap_or_default())
+		.collect();
+
+	let quote = if preferred.is_empty() {
+		QuoteMac::get(&db, &utx, id).await?
+	} else {
+		QuoteMac::get_in_lang(&db, &utx, id, &preferred).await?
+	};
+
+	let etag = etag(&quote);
+	let content_language = quote.lang.clone();
+	let reply = warp::reply::with_header(json_response(quote)?, HEADER_ETAG, etag);
+	let reply = warp::reply::with_header(reply, HEADER_CONTENT_LANGUAGE, content_language);
+	Ok(warp::reply::with_header(reply, HEADER_VARY, HEADER_ACCEPT_LANGUAGE))
 }
 
-async fn quote_create(db: Arc<Db>, utx: UserCtx, patch: QuotePatch) -> Result<Json, warp::Rejection> {
+async fn quote_create(db: Arc<Db>, utx: UserCtx, patch: QuotePatch) -> Result<impl Reply, warp::Rejection> {
 	let quote = QuoteMac::create(&db, &utx, patch).await?;
-	json_response(quote)
+	let etag = etag(&quote);
+	Ok(warp::reply::with_header(json_response(quote)?, HEADER_ETAG, etag))
+}
+
+async fn quote_update(
+	db: Arc<Db>,
+	utx: UserCtx,
+	id: i64,
+	if_match: Option<String>,
+	content_type: Option<String>,
+	body: Bytes,
+) -> Result<impl Reply, warp::Rejection> {
+	let expected_version = expected_version(&db, &utx, id, if_match.as_deref()).await?;
+
+	// the media type without its parameters (e.g., `; charset=utf-8`)
+	let mime = content_type
+		.as_deref()
+		.and_then(|ct| ct.split(';').next())
+		.map(|mime| mime.trim().to_ascii_lowercase());
+
+	let quote = match mime.as_deref() {
+		None | Some("application/json") => {
+			let patch: QuotePatch = parse_body(&body)?;
+			QuoteMac::update(&db, &utx, id, patch, expected_version).await?
+		}
+		Some(MERGE_PATCH_MIME) => {
+			let patch = QuotePatch::from_merge_patch(parse_body(&body)?)?;
+			QuoteMac::update(&db, &utx, id, patch, expected_version).await?
+		}
+		Some(JSON_PATCH_MIME) => {
+			let ops = parse_json_patch(parse_body(&body)?)?;
+			QuoteMac::patch(&db, &utx, id, &ops, expected_version).await?
+		}
+		Some(other) => return Err(Error::FailUnsupportedMediaType(other.to_string()).into()),
+	};
+	let etag = etag(&quote);
+	Ok(warp::reply::with_header(json_response(quote)?, HEADER_ETAG, etag))
+}
+
+async fn quote_list_translations(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+	let quotes = QuoteMac::list_translations(&db, &utx, id).await?;
+	json_response(quotes)
 }
 
-async fn quote_update(db: Arc<Db>, utx: UserCtx, id: i64, patch: QuotePatch) -> Result<Json, warp::Rejection> {
-	let quote = QuoteMac::update(&db, &utx, id, patch).await?;
+async fn quote_create_translation(db: Arc<Db>, utx: UserCtx, id: i64, data: TranslationForCreate) -> Result<Json, warp::Rejection> {
+	let quote = QuoteMac::create_translation(&db, &utx, id, data).await?;
 	json_response(quote)
 }
 
-async fn quote_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
-	let quote = QuoteMac::delete(&db, &utx, id).await?;
+async fn quote_delete(db: Arc<Db>, utx: UserCtx, id: i64, if_match: Option<String>) -> Result<Json, warp::Rejection> {
+	let expected_version = expected_version(&db, &utx, id, if_match.as_deref()).await?;
+	let quote = QuoteMac::delete(&db, &utx, id, expected_version).await?;
 	json_response(quote)
 }
 
+async fn quote_cite(db: Arc<Db>, utx: UserCtx, id: i64, params: CiteParams) -> Result<impl Reply, warp::Rejection> {
+	let quote = QuoteMac::get(&db, &utx, id).await?;
+	cite_response(&[quote], params)
+}
+
 // region:    Utils
-fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
-	let response = json!({ "data": data });
-	Ok(warp::reply::json(&response))
+/// The citations as the body, with the Content-Type of the format.
+pub(super) fn cite_response(quotes: &[Quote], params: CiteParams) -> Result<WithHeader<String>, warp::Rejection> {
+	let format = match params.format {
+		Some(format) => format.parse::<CiteFormat>()?,
+		None => CiteFormat::Bibtex,
+	};
+	let citations = export::cite(quotes, format)?;
+	Ok(warp::reply::with_header(citations, HEADER_CONTENT_TYPE, format.content_type()))
+}
+
+/// Strong entity tag of a quote version, with its id as a translation can be served at the URL of its original, e.g. `"100-3"`.
+pub(super) fn etag(quote: &Quote) -> String {
+	format!("\"{}-{}\"", quote.id, quote.version)
+}
+
+#[derive(Debug)]
+enum IfMatch {
+	Any,
+	Version(i64),
+	/// A valid entity tag which is never the one of the quote (of another quote, weak, or a list).
+	NoMatch,
+}
+
+/// Parse an `If-Match` header for the quote `id` (`*` or no header means any version).
+fn parse_if_match(id: i64, if_match: Option<&str>) -> Result<IfMatch, Error> {
+	let value = match if_match.map(str::trim) {
+		None | Some("*") => return Ok(IfMatch::Any),
+		Some(value) => value,
+	};
+	// the strong comparison of If-Match never matches a weak tag, and a single tag is sent for a quote
+	if value.starts_with("W/") || value.contains(',') {
+		return Ok(IfMatch::NoMatch);
+	}
+	let tag = value
+		.strip_prefix('"')
+		.and_then(|v| v.strip_suffix('"'))
+		.ok_or_else(|| Error::FailIfMatchFormat(value.to_string()))?;
+	let version = tag
+		.split_once('-')
+		.filter(|(tag_id, _)| *tag_id == id.to_string())
+		.and_then(|(_, version)| version.parse::<i64>().ok());
+	Ok(version.map_or(IfMatch::NoMatch, IfMatch::Version))
+}
+
+/// The version expected by the `If-Match` header, or the failed precondition with the current quote.
+async fn expected_version(db: &Db, utx: &UserCtx, id: i64, if_match: Option<&str>) -> Result<Option<i64>, warp::Rejection> {
+	match parse_if_match(id, if_match)? {
+		IfMatch::Any => Ok(None),
+		IfMatch::Version(version) => Ok(Some(version)),
+		IfMatch::NoMatch => {
+			let current = QuoteMac::get(db, utx, id).await?;
+			Err(model::Error2::QuoteVersionMismatch(Box::new(current)).into())
+		}
+	}
 }
 // endregion: Utils
 
@@ -0,0 +1,78 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use crate::model::{ApiScope, Db, ListOptions, ReportForCreate, ReportMac, ReportResolution};
+use crate::security::UserCtx;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn report_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common_admin = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::Admin));
+	let common_write = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::QuotesWrite));
+	let reports_path = warp::path(base_path).and(warp::path("moderation")).and(warp::path("reports"));
+
+	// REPORT quote `POST /quotes/100/reports with body {"reason": "misattributed", "comment": "..."}`
+	let create = warp::path(base_path)
+		.and(warp::path("quotes"))
+		.and(warp::post())
+		.and(common_write.clone())
+		.and(warp::path::param())
+		.and(warp::path("reports"))
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(report_create);
+
+	// LIST reported quotes, most reported first `GET /moderation/reports`
+	let list = reports_path
+		.and(warp::get())
+		.and(warp::path::end())
+		.and(common_admin.clone())
+		.and(warp::query::<ListOptions>())
+		.and_then(report_list);
+
+	// LIST the reports of a quote, open and resolved `GET /moderation/reports/100`
+	let list_for_quote = reports_path
+		.and(warp::get())
+		.and(common_admin.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(report_list_for_quote);
+
+	// RESOLVE the open reports of a quote `POST /moderation/reports/100/resolve with body {"outcome": "removed", "note": "..."}`
+	let resolve = reports_path
+		.and(warp::post())
+		.and(common_admin.clone())
+		.and(warp::path::param())
+		.and(warp::path("resolve"))
+		.and(warp::path::end())
+		.and(warp::body::json())
+		.and_then(report_resolve);
+
+	create.or(list).or(list_for_quote).or(resolve)
+}
+
+async fn report_create(db: Arc<Db>, utx: UserCtx, quote_id: i64, data: ReportForCreate) -> Result<Json, warp::Rejection> {
+	let report = ReportMac::create(&db, &utx, quote_id, data).await?;
+	json_response(report)
+}
+
+async fn report_list(db: Arc<Db>, utx: UserCtx, list_options: ListOptions) -> Result<Json, warp::Rejection> {
+	let summaries = ReportMac::list_open(&db, &utx, &list_options).await?;
+	json_response(summaries)
+}
+
+async fn report_list_for_quote(db: Arc<Db>, utx: UserCtx, quote_id: i64) -> Result<Json, warp::Rejection> {
+	let reports = ReportMac::list_for_quote(&db, &utx, quote_id).await?;
+	json_response(reports)
+}
+
+async fn report_resolve(db: Arc<Db>, utx: UserCtx, quote_id: i64, data: ReportResolution) -> Result<Json, warp::Rejection> {
+	let reports = ReportMac::resolve(&db, &utx, quote_id, data).await?;
+	json_response(reports)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_report.rs"]
+mod tests;
+// endregion: Test
@@ -1,39 +0,0 @@
-/*
-use crate::{web, Error, Result};
-use axum::routing::post;
-use axum::{Json, Router};
-use tower_cookies::{Cookie, Cookies, CookieManagerLayer};
-use serde::Deserialize;
-use serde_json::{json, Value};
-
-pub fn routes() -> Router {
-    Router::new().route("/api/login", post(api_login))
-}
-
-async fn api_login(cookies: Cookies, payload: Json<LoginPayload>) -> Result<Json<Value>> {
-    println!("->> {:<12} - api_login", "HANDLER");
-
-    // TODO: Implement a real db/authentication logic.
-    if payload.username != "demo1" || payload.pwd != "welcome" {
-        return Err(Error::LoginFail);
-    }
-
-    // Set cookies
-    cookies.add(Cookie::new(web::AUTH_TOKEN, "user-1.exp.sign"));
-
-    // Create sucess body.
-    let body = Json(json!({
-        "result": {
-            "success": true
-        }
-    }));
-
-    Ok(body)
-}
-
-#[derive(Debug, Deserialize)]
-struct LoginPayload {
-    username: String,
-    pwd: String,
-}
-*/
\ No newline at end of file
@@ -0,0 +1,63 @@
+use super::filter_auth::{clear_auth_cookie, do_auth};
+use super::json_response;
+use crate::model::{ApiScope, Db, SessionMac};
+use crate::security::UserCtx;
+use std::sync::Arc;
+use warp::http::header::SET_COOKIE;
+use warp::reply::{Json, Response};
+use warp::{Filter, Reply};
+
+pub fn session_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db.clone(), ApiScope::Admin));
+	let sessions_path = warp::path(base_path).and(warp::path("me")).and(warp::path("sessions"));
+
+	// LIST my active sessions `GET /me/sessions`, the one of the request with `"current": true`
+	let list = sessions_path
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(common.clone())
+		.and_then(session_list);
+
+	// REVOKE a session `DELETE /me/sessions/1000`
+	let delete = sessions_path
+		.and(warp::delete())
+		.and(common.clone())
+		.and(warp::path::param())
+		.and(warp::path::end())
+		.and_then(session_delete);
+
+	// LOG OUT everywhere `DELETE /me/sessions`
+	let delete_all = sessions_path
+		.and(warp::path::end())
+		.and(warp::delete())
+		.and(common.clone())
+		.and_then(session_delete_all);
+
+	list.or(delete).or(delete_all)
+}
+
+async fn session_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+	let sessions = SessionMac::list(&db, &utx).await?;
+	json_response(sessions)
+}
+
+async fn session_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Response, warp::Rejection> {
+	let session = SessionMac::delete(&db, &utx, id).await?;
+	let response = json_response(session)?;
+	// the auth cookie of a revoked session is of no more use
+	match utx.session_id == Some(id) {
+		true => Ok(warp::reply::with_header(response, SET_COOKIE, clear_auth_cookie()).into_response()),
+		false => Ok(response.into_response()),
+	}
+}
+
+async fn session_delete_all(db: Arc<Db>, utx: UserCtx) -> Result<Response, warp::Rejection> {
+	let sessions = SessionMac::delete_all(&db, &utx).await?;
+	Ok(warp::reply::with_header(json_response(sessions)?, SET_COOKIE, clear_auth_cookie()).into_response())
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_session.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,41 @@
+use super::filter_auth::do_auth;
+use super::json_response;
+use crate::config::config;
+use crate::model::{ApiScope, Db, QuoteFilter, StatsCache, StatsOptions};
+use crate::security::UserCtx;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn stats_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let common = super::filter_utils::with_db(db.clone()).and(do_auth(db, ApiScope::QuotesRead));
+	let cache = Arc::new(StatsCache::new(config().stats_ttl));
+
+	// STATS of the quotes `GET /stats?interval=month&top=5` (with the quote list filters, e.g., `&lang=es`)
+	warp::path(base_path)
+		.and(warp::path("stats"))
+		.and(warp::path::end())
+		.and(warp::get())
+		.and(common)
+		.and(warp::any().map(move || cache.clone()))
+		.and(warp::query::<QuoteFilter>())
+		.and(warp::query::<StatsOptions>())
+		.and_then(stats_get)
+}
+
+async fn stats_get(
+	db: Arc<Db>,
+	utx: UserCtx,
+	cache: Arc<StatsCache>,
+	filter: QuoteFilter,
+	options: StatsOptions,
+) -> Result<Json, warp::Rejection> {
+	let stats = cache.stats(&db, &utx, &filter, &options).await?;
+	json_response(stats)
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_stats.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,62 @@
+use super::filter_auth::{do_auth, do_auth_totp_enrol};
+use super::json_response;
+use crate::model::{ApiScope, Db};
+use crate::security::{self, TotpCode, UserCtx};
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+pub fn totp_rest_filters(base_path: &'static str, db: Arc<Db>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+	let with_db = super::filter_utils::with_db(db.clone());
+	let enrolling = with_db.clone().and(do_auth_totp_enrol(db.clone()));
+	let totp_path = warp::path(base_path).and(warp::path("me")).and(warp::path("totp"));
+
+	// ENROL `POST /me/totp`, gives the secret and its otpauth URI, for the authenticator app
+	let enrol = totp_path
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(enrolling.clone())
+		.and_then(totp_enrol);
+
+	// CONFIRM the enrolment `POST /me/totp/confirm with body {"code": "123456"}`, enables the TOTP and gives the recovery codes
+	let confirm = totp_path
+		.and(warp::path("confirm"))
+		.and(warp::path::end())
+		.and(warp::post())
+		.and(enrolling)
+		.and(warp::body::json())
+		.and_then(totp_confirm);
+
+	// DISABLE `DELETE /me/totp with body {"code": "123456"}` (not for admins)
+	let disable = totp_path
+		.and(warp::path::end())
+		.and(warp::delete())
+		.and(with_db)
+		.and(do_auth(db, ApiScope::Admin))
+		.and(warp::body::json())
+		.and_then(totp_disable);
+
+	enrol.or(confirm).or(disable)
+}
+
+async fn totp_enrol(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+	let enrollment = security::enrol_totp(&db, &utx).await?;
+	json_response(enrollment)
+}
+
+async fn totp_confirm(db: Arc<Db>, utx: UserCtx, data: TotpCode) -> Result<Json, warp::Rejection> {
+	let recovery_codes = security::confirm_totp(&db, &utx, &data.code).await?;
+	json_response(recovery_codes)
+}
+
+async fn totp_disable(db: Arc<Db>, utx: UserCtx, data: TotpCode) -> Result<Json, warp::Rejection> {
+	security::disable_totp(&db, &utx, &data.code).await?;
+	json_response(json!({ "success": true }))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../tests/web_totp.rs"]
+mod tests;
+// endregion: Test
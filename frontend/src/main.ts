@@ -1,3 +1,4 @@
 console.log("->> hello from main.ts");
 import './ui/commons.js';
-import './ui/quote-mvc.js';
\ No newline at end of file
+import './ui/quote-mvc.js';
+import './ui/login.js';
\ No newline at end of file
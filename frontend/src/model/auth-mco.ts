@@ -0,0 +1,22 @@
+import { hub } from 'dom-native';
+import { webPost } from '../webc.js';
+
+export interface LoginData {
+  username: string;
+  password: string;
+  totp?: string; // code or recovery code, when two-factor authentication is enabled
+}
+
+class AuthMco {
+
+  async login(data: LoginData): Promise<any> {
+    // to server, which sets the auth cookie sent with the next calls
+    const result = await webPost('login', data);
+    // event
+    hub('dataHub').pub('Auth', 'login', result);
+
+    return result;
+  }
+}
+
+export const authMco = new AuthMco();
\ No newline at end of file
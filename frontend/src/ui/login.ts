@@ -0,0 +1,54 @@
+import { BaseHTMLElement, customElement, html, onEvent, onHub } from 'dom-native';
+import { authMco } from '../model/auth-mco';
+
+/* ------------------- login-form ------------------- */
+// Shown when an API call needs a login (401), until the login sets the auth cookie.
+@customElement("login-form")
+class LoginForm extends BaseHTMLElement {
+
+  init() {
+    this.hidden = true;
+    this.append(html`
+      <h2>login</h2>
+      <input type="text" placeholder="Username" autocomplete="username">
+      <input type="password" placeholder="Password" autocomplete="current-password">
+      <input type="text" placeholder="Two-factor code (if enabled)" autocomplete="one-time-code">
+      <div class="login-error"></div>
+    `);
+  }
+
+  @onEvent('keyup', 'input')
+  async onInputKeyUp(evt: KeyboardEvent) {
+    if (evt.key !== "Enter") return;
+
+    const inputs = this.querySelectorAll('input');
+    const username = inputs[0].value.trim();
+    const password = inputs[1].value;
+    const totp = inputs[2].value.trim() || undefined;
+    const errorEl = this.querySelector('.login-error')!;
+
+    if (username && password) {
+      try {
+        await authMco.login({ username, password, totp });
+        inputs[1].value = '';
+        inputs[2].value = '';
+        errorEl.textContent = '';
+        this.hidden = true;
+      } catch (err) {
+        errorEl.textContent = (err as Error).message;
+      }
+    }
+  }
+
+  @onHub('dataHub', 'Auth', 'required')
+  onAuthRequired() {
+    this.hidden = false;
+  }
+}
+
+// type augmentation
+declare global {
+  interface HTMLElementTagNameMap {
+    'login-form': LoginForm;
+  }
+}
\ No newline at end of file
@@ -4,11 +4,13 @@ import {
   first,
   getChildren,
   html,
+  hub,
   OnEvent,
   onEvent,
   onHub
 } from 'dom-native';
 import { Quote, quoteMco } from '../model/quote-mco';
+import { WebError } from '../webc';
 
 // Region: Quote Model View Controller
 @customElement("quote-mvc")
@@ -43,7 +45,11 @@ class QuoteMvc extends BaseHTMLElement {
       this.#quoteListEl.innerHTML = '';
       this.#quoteListEl.append(htmlContent);
     } catch (err) {
-      console.error('Failed to load quotes:', err);
+      if (err instanceof WebError && err.status == 401) {
+        hub('dataHub').pub('Auth', 'required');
+      } else {
+        console.error('Failed to load quotes:', err);
+      }
     }
   }
 
@@ -73,6 +79,11 @@ class QuoteMvc extends BaseHTMLElement {
   onQuoteCreate(data: Quote) {
     this.refresh();
   }
+
+  @onHub('dataHub', 'Auth', 'login')
+  onLogin() {
+    this.refresh();
+  }
   // #endregion
 }
 
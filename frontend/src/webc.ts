@@ -2,6 +2,16 @@ type WebMethod = "GET" | "POST" | "DELETE" | "PATCH";
 
 const API_BASE_PATH = '/api';
 
+/** Error of an API call, with its http status (e.g., 401 when not logged in). */
+export class WebError extends Error {
+  readonly status: number;
+
+  constructor(status: number, message: string) {
+    super(message);
+    this.status = status;
+  }
+}
+
 export async function webGet(path: string, data?: any) {
   return webCall("GET", path, data);
 }
@@ -23,13 +33,16 @@ async function webCall(httpMethod: WebMethod, path: string, data?: any) {
     method: httpMethod,
     mode: 'same-origin',
     cache: 'no-cache',
+    credentials: 'same-origin', // auth cookie set by POST /api/login
     headers: {
-      'Content-Type': 'application/json',
-      'X-Auth-Token': '123'
+      'Content-Type': 'application/json'
     },
     body: JSON.stringify(data)
   });
 
   let res = await response.json();
+  if (!response.ok) {
+    throw new WebError(response.status, res.errorDetail ?? res.errorMessage);
+  }
   return res.data;
 }
\ No newline at end of file
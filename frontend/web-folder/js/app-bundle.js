@@ -1126,6 +1126,19 @@
   ], Ico);
 
   const API_BASE_PATH = '/api';
+  /** Error of an API call, with its http status (e.g., 401 when not logged in). */
+  class WebError extends Error {
+      constructor(status, message) {
+          super(message);
+          Object.defineProperty(this, "status", {
+              enumerable: true,
+              configurable: true,
+              writable: true,
+              value: void 0
+          });
+          this.status = status;
+      }
+  }
   async function webGet(path, data) {
       return webCall("GET", path, data);
   }
@@ -1144,13 +1157,16 @@
           method: httpMethod,
           mode: 'same-origin',
           cache: 'no-cache',
+          credentials: 'same-origin',
           headers: {
-              'Content-Type': 'application/json',
-              'X-Auth-Token': '123'
+              'Content-Type': 'application/json'
           },
           body: JSON.stringify(data)
       });
       let res = await response.json();
+      if (!response.ok) {
+          throw new WebError(response.status, res.errorDetail ?? res.errorMessage);
+      }
       return res.data;
   }
 
@@ -1223,7 +1239,12 @@
               __classPrivateFieldGet(this, _QuoteMvc_quoteListEl, "f").append(htmlContent);
           }
           catch (err) {
-              console.error('Failed to load quotes:', err);
+              if (err instanceof WebError && err.status == 401) {
+                  hub('dataHub').pub('Auth', 'required');
+              }
+              else {
+                  console.error('Failed to load quotes:', err);
+              }
           }
       }
       // #region --- UI Events
@@ -1248,6 +1269,9 @@
       onQuoteCreate(data) {
           this.refresh();
       }
+      onLogin() {
+          this.refresh();
+      }
   };
   _QuoteMvc_quoteInputEl = new WeakMap();
   _QuoteMvc_quoteListEl = new WeakMap();
@@ -1260,6 +1284,9 @@
   __decorate([
       onHub('dataHub', 'Quote', 'create')
   ], QuoteMvc.prototype, "onQuoteCreate", null);
+  __decorate([
+      onHub('dataHub', 'Auth', 'login')
+  ], QuoteMvc.prototype, "onLogin", null);
   QuoteMvc = __decorate([
       customElement("quote-mvc")
   ], QuoteMvc);
@@ -1360,6 +1387,65 @@
       customElement('quote-item')
   ], QuoteItem);
 
+  class AuthMco {
+      async login(data) {
+          // to server, which sets the auth cookie sent with the next calls
+          const result = await webPost('login', data);
+          // event
+          hub('dataHub').pub('Auth', 'login', result);
+          return result;
+      }
+  }
+  const authMco = new AuthMco();
+
+  /* ------------------- login-form ------------------- */
+  // Shown when an API call needs a login (401), until the login sets the auth cookie.
+  let LoginForm = class LoginForm extends BaseHTMLElement {
+      init() {
+          this.hidden = true;
+          this.append(html `
+      <h2>login</h2>
+      <input type="text" placeholder="Username" autocomplete="username">
+      <input type="password" placeholder="Password" autocomplete="current-password">
+      <input type="text" placeholder="Two-factor code (if enabled)" autocomplete="one-time-code">
+      <div class="login-error"></div>
+    `);
+      }
+      async onInputKeyUp(evt) {
+          if (evt.key !== "Enter")
+              return;
+          const inputs = this.querySelectorAll('input');
+          const username = inputs[0].value.trim();
+          const password = inputs[1].value;
+          const totp = inputs[2].value.trim() || undefined;
+          const errorEl = this.querySelector('.login-error');
+          if (username && password) {
+              try {
+                  await authMco.login({ username, password, totp });
+                  inputs[1].value = '';
+                  inputs[2].value = '';
+                  errorEl.textContent = '';
+                  this.hidden = true;
+              }
+              catch (err) {
+                  errorEl.textContent = err.message;
+              }
+          }
+      }
+      onAuthRequired() {
+          this.hidden = false;
+      }
+  };
+  __decorate([
+      onEvent('keyup', 'input')
+  ], LoginForm.prototype, "onInputKeyUp", null);
+  __decorate([
+      onHub('dataHub', 'Auth', 'required')
+  ], LoginForm.prototype, "onAuthRequired", null);
+  LoginForm = __decorate([
+      customElement("login-form")
+  ], LoginForm);
+
   console.log("->> hello from main.ts");
 
 })();